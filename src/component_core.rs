@@ -0,0 +1,65 @@
+use std::collections::HashMap as Map;
+
+use walrus::ExportItem;
+
+use crate::error::Error;
+use crate::merge_options::{MAIN_MEMORY_NAME, MergeOptions};
+use crate::named_module::NamedParsedModule;
+
+/// The canonical ABI exports a component's core module must preserve.
+pub(crate) const CANONICAL_ABI_EXPORTS: [&str; 2] = ["cabi_realloc", "cabi_free"];
+
+/// The `options` that keep the canonical ABI exports of the `parsed` modules,
+/// even when they resolve imports of other modules. Each of these may be
+/// exported by a single module only.
+pub(crate) fn keeping_canonical_abi(
+    options: &MergeOptions,
+    parsed: &[NamedParsedModule<'_>],
+) -> Result<MergeOptions, Error> {
+    let mut options = options.clone();
+    let mut keep_exports = options.keep_exports.take().unwrap_or_default();
+    let mut exporters: Map<&str, &str> = Map::new();
+    for NamedParsedModule { name, module } in parsed {
+        for export in module.exports.iter() {
+            let abi_export = CANONICAL_ABI_EXPORTS.contains(&export.name.as_str());
+            if !abi_export || !matches!(export.item, ExportItem::Function(_)) {
+                continue;
+            }
+            if let Some(other) = exporters.insert(&export.name, name) {
+                return Err(Error::ComponentCoreConvention(format!(
+                    "{} is exported by both {other} and {name}",
+                    export.name
+                )));
+            }
+            keep_exports.keep_function(name.as_ref().into(), export.name.clone());
+        }
+    }
+    options.keep_exports = Some(keep_exports);
+    Ok(options)
+}
+
+/// Exports the single memory of `module` as [`MAIN_MEMORY_NAME`].
+pub(crate) fn export_single_memory(module: &mut walrus::Module) -> Result<(), Error> {
+    let memories: Vec<_> = module.memories.iter().map(walrus::Memory::id).collect();
+    let [memory] = memories[..] else {
+        return Err(Error::ComponentCoreConvention(format!(
+            "a single memory is required, found {}",
+            memories.len()
+        )));
+    };
+    let main_export = module
+        .exports
+        .iter()
+        .find(|export| export.name == MAIN_MEMORY_NAME);
+    match main_export.map(|export| export.item) {
+        Some(ExportItem::Memory(exported)) if exported == memory => return Ok(()),
+        Some(_) => {
+            return Err(Error::ComponentCoreConvention(format!(
+                "{MAIN_MEMORY_NAME} is exported, though not as the memory"
+            )));
+        }
+        None => {}
+    }
+    module.exports.add(MAIN_MEMORY_NAME, memory);
+    Ok(())
+}
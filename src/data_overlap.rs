@@ -0,0 +1,90 @@
+use std::collections::HashMap as Map;
+use std::ops::Range;
+
+use walrus::ir::Value;
+use walrus::{ConstExpr, DataKind};
+
+use crate::error::Error;
+use crate::kinds::IdentifierModule;
+use crate::merge_builder::AllResolved;
+use crate::merger::old_to_new_mapping::OldIdMemory;
+use crate::named_module::NamedParsedModule;
+use crate::resolver::Node;
+
+/// A memory of the merged module, by where it is defined.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+enum SharedMemory {
+    /// Defined by a module, at the index.
+    Local(IdentifierModule, OldIdMemory),
+    /// Imported from the module under the name.
+    Import(String, String),
+}
+
+impl AllResolved {
+    /// Validates that no two modules initialise overlapping ranges of the same
+    /// memory by their active data segments. Only segments at constant offsets
+    /// are considered, other offsets are only known upon instantiation.
+    pub(crate) fn check_data_overlap(&self, parsed: &[NamedParsedModule<'_>]) -> Result<(), Error> {
+        let mut sources = Map::new();
+        for (node, source) in &self.all_reduced.memories.reduction_map {
+            let node = match node {
+                Node::Import(import) => {
+                    (import.importing_module().clone(), *import.imported_index())
+                }
+                Node::Local(local) => (local.module().clone(), *local.index()),
+                Node::Export(_) => continue,
+            };
+            let source = match source {
+                Node::Import(import) => SharedMemory::Import(
+                    import.exporting_module().identifier().to_string(),
+                    import.exporting_identifier().identifier().to_string(),
+                ),
+                Node::Local(local) => SharedMemory::Local(local.module().clone(), *local.index()),
+                Node::Export(_) => unreachable!("exports always reduce to a source"),
+            };
+            sources.insert(node, source);
+        }
+
+        let mut initialised: Map<SharedMemory, Vec<(&str, Range<u64>)>> = Map::new();
+        for NamedParsedModule { name, module } in parsed {
+            let module_name: IdentifierModule = name.as_ref().into();
+            for data in module.data.iter() {
+                let DataKind::Active { memory, offset } = &data.kind else {
+                    continue;
+                };
+                let Some(start) = constant_offset(offset) else {
+                    continue;
+                };
+                let Some(source) = sources.get(&(module_name.clone(), (*memory).into())) else {
+                    continue;
+                };
+                let end = start.saturating_add(data.value.len() as u64);
+                let ranges = initialised.entry(source.clone()).or_default();
+                for (other, range) in ranges.iter() {
+                    let overlap = start.max(range.start)..end.min(range.end);
+                    if *other != name.as_ref() && !overlap.is_empty() {
+                        let memory = match source {
+                            SharedMemory::Local(module, _) => module.identifier().to_string(),
+                            SharedMemory::Import(module, name) => format!("{module}::{name}"),
+                        };
+                        return Err(Error::DataOverlap {
+                            memory,
+                            range: overlap,
+                        });
+                    }
+                }
+                ranges.push((name.as_ref(), start..end));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The offset of an active data segment, when it is constant.
+fn constant_offset(offset: &ConstExpr) -> Option<u64> {
+    match offset {
+        ConstExpr::Value(Value::I32(offset)) => Some(u64::from(offset.cast_unsigned())),
+        ConstExpr::Value(Value::I64(offset)) => Some(offset.cast_unsigned()),
+        _ => None,
+    }
+}
@@ -22,8 +22,13 @@ pub enum Error {
     /// ```
     /// Here, `A`'s `"a"` is just `B`'s `"b"`, and `B`'s `"b"` is just `A`'s `"a"`.
     /// No actual function is defined anywhere, so resolution is not possible.
-    #[error("Infinite Import Cycle")]
-    ImportCycle,
+    /// The error names the `kind` of the cycle's items and the `modules`
+    /// taking part in it, here `Function` and `["A", "B"]`.
+    #[error("Infinite Import Cycle: {kind:?} among {modules:?}")]
+    ImportCycle {
+        kind: crate::kinds::ExportKind,
+        modules: Vec<String>,
+    },
 
     /// Types Mismatch
     ///
@@ -51,4 +56,236 @@ pub enum Error {
     /// would result in a `Map { "f" -> { A:f, B:f } }`.
     #[error("Export Name Clash")]
     ExportNameClash(crate::kinds::ClashesMap),
+
+    /// Import Name Clash
+    ///
+    /// Under a [`NamePolicy`] settling imports by signalling clashes,
+    /// function imports that
+    /// share their module & name but differ in type, as `(module, name)`.
+    /// Merging the following modules:
+    /// ```wat
+    /// (module "A" (import "env" "log" (func (param i32))))
+    /// (module "B" (import "env" "log" (func (param f64))))
+    /// ```
+    /// Would result in `ImportNameClash(vec![("env", "log")])`.
+    ///
+    /// [`NamePolicy`]: crate::merge_options::NamePolicy
+    #[error("Import Name Clash")]
+    ImportNameClash(Vec<(String, String)>),
+
+    /// Memory Limits Mismatch
+    ///
+    /// An imported memory is linked to a memory whose limits
+    /// do not satisfy those required by the import. A too small
+    /// initial size is not a mismatch, the memory is grown to fit,
+    /// unless that exceeds its maximum.
+    ///
+    /// Eg.
+    /// ```wat
+    /// (module "A" (memory (export "m") 2))
+    /// (module "B" (import "A" "m" (memory 1 1)))
+    /// ```
+    /// Here, `B` requires a maximum of 1 page, while `A` provides
+    /// an (unbounded) memory of at least 2 pages.
+    #[error("Memory Limits Mismatch")]
+    MemoryLimitsMismatch(Vec<crate::kinds::CrossModuleMismatch>),
+
+    /// Table Limits Mismatch
+    ///
+    /// An imported table is linked to a table whose limits do not satisfy
+    /// those required by the import. Unlike memories, tables are not grown
+    /// to fit.
+    ///
+    /// Eg.
+    /// ```wat
+    /// (module "A" (table (export "t") 1 1 funcref))
+    /// (module "B" (import "A" "t" (table 2 funcref)))
+    /// ```
+    /// Here, `B` requires at least 2 elements, while `A` provides at most 1.
+    #[error("Table Limits Mismatch")]
+    TableLimitsMismatch(Vec<crate::kinds::CrossModuleMismatch>),
+
+    /// Aggregate
+    ///
+    /// All errors encountered during resolution, reported together when
+    /// [`MergeOptions::collect_all_errors`] is set.
+    ///
+    /// [`MergeOptions::collect_all_errors`]: crate::merge_options::MergeOptions::collect_all_errors
+    #[error("Multiple Errors: {0:?}")]
+    Aggregate(Vec<Error>),
+
+    /// Data Offset Overflow
+    ///
+    /// An active data segment of the given module is placed past the
+    /// addressable range of its memory, so instantiation would always fail.
+    ///
+    /// Eg.
+    /// ```wat
+    /// (module "A"
+    ///   (memory 1 1)
+    ///   (data (i32.const 65535) "ab"))
+    /// ```
+    /// Here, the second byte lands outside of the single page.
+    #[error("Data Offset Overflow: {0}")]
+    DataOffsetOverflow(String),
+
+    /// Orphaned Data Segment
+    ///
+    /// An active data segment of the given module targets a memory
+    /// that is not part of the merged module.
+    #[error("Orphaned Data Segment: {0}")]
+    OrphanedDataSegment(String),
+
+    /// Data Overlap
+    ///
+    /// Active data segments of different modules initialise the same `range`
+    /// of a shared memory, the outcome depends on the order of initialisation.
+    /// The `memory` is named by its defining module, or as `module::name` when
+    /// it remains imported.
+    ///
+    /// Eg.
+    /// ```wat
+    /// (module "A" (import "env" "memory" (memory 1)) (data (i32.const 0) "ab"))
+    /// (module "B" (import "env" "memory" (memory 1)) (data (i32.const 1) "cd"))
+    /// ```
+    /// Would result in `DataOverlap { memory: "env::memory", range: 1..2 }`.
+    #[error("Data Overlap: {range:?} of {memory} is initialised by multiple modules")]
+    DataOverlap {
+        memory: String,
+        range: std::ops::Range<u64>,
+    },
+
+    /// Unresolved Imports
+    ///
+    /// The merged module was required to be self-contained, yet some
+    /// imports, listed as `(module, name, kind)`, have no provider.
+    ///
+    /// Eg.
+    /// ```wat
+    /// (module "A" (import "host" "log" (func)))
+    /// ```
+    /// Would result in `UnresolvedImports([("host", "log", Function)])`.
+    #[error("Unresolved Imports: {0:?}")]
+    UnresolvedImports(Vec<(String, String, crate::kinds::ExportKind)>),
+
+    /// Output Too Large
+    ///
+    /// The emitted merged module counts `actual` bytes,
+    /// exceeding the configured `limit`.
+    #[error("Output Too Large: {actual} bytes exceed the limit of {limit} bytes")]
+    OutputTooLarge { actual: usize, limit: usize },
+
+    /// Feature Not In Target
+    ///
+    /// A module makes use of a feature (eg. `simd`) that
+    /// the targeted runtime does not support.
+    #[error("Feature Not In Target: {0}")]
+    FeatureNotInTarget(String),
+
+    /// Feature Not Allowed
+    ///
+    /// The input `module` makes use of a `feature` outside of the configured
+    /// feature profile, see `MergeOptions::feature_profile`.
+    #[error("Feature Not Allowed: {feature} in {module}")]
+    FeatureNotAllowed { feature: String, module: String },
+
+    /// Merged Feature Not Allowed
+    ///
+    /// The merged module makes use of a feature outside of the configured
+    /// feature profile that none of the inputs use, eg. `multi-memory` when
+    /// merging single-memory modules or `tail-call` from
+    /// `MergeOptions::tailcall_optimize`.
+    #[error("Merged Feature Not Allowed: {0}")]
+    MergedFeatureNotAllowed(String),
+
+    /// Duplicate Module Name
+    ///
+    /// Module names are the namespace imports are resolved against, hence
+    /// each participating module must be named uniquely.
+    ///
+    /// Eg.
+    /// ```wat
+    /// (module "A" (export "f"))
+    /// (module "A" (export "g"))
+    /// ```
+    /// Would result in `DuplicateModuleName("A")`.
+    #[error("Duplicate Module Name: {0}")]
+    DuplicateModuleName(String),
+
+    /// Duplicate Export In Module
+    ///
+    /// A module exports two items under the same `name`,
+    /// which makes the module invalid.
+    ///
+    /// Eg.
+    /// ```wat
+    /// (module "A" (export "f" (func 0)) (export "f" (global 0)))
+    /// ```
+    /// Would result in `DuplicateExportInModule { module: "A", name: "f" }`.
+    #[error("Duplicate Export In Module: {module} exports {name} twice")]
+    DuplicateExportInModule { module: String, name: String },
+
+    /// Empty Module Name
+    ///
+    /// A module with an empty name cannot be targeted by an import.
+    #[error("Empty Module Name")]
+    EmptyModuleName,
+
+    /// Unknown Module
+    ///
+    /// A module was referred to by name, yet no module of that name
+    /// takes part in the merge.
+    #[error("Unknown Module: {0}")]
+    UnknownModule(String),
+
+    /// Unmapped Content Hash
+    ///
+    /// A content-addressed module, identified by its hex-encoded SHA-256
+    /// hash, has no name in the manifest.
+    #[error("Unmapped Content Hash: {0}")]
+    UnmappedContentHash(String),
+
+    /// Reserved Export Name
+    ///
+    /// An export the configuration adds to the merged module, eg. the exported
+    /// function table or an exported start, is named after an existing export.
+    #[error("Reserved Export Name: {0}")]
+    ReservedExportName(String),
+
+    /// Invalid Input
+    ///
+    /// The input `module` is not valid WebAssembly, as described by `detail`.
+    #[error("Invalid Input: {module}: {detail}")]
+    InvalidInput { module: String, detail: String },
+
+    /// Unsupported Value Type
+    ///
+    /// A local, global, table, element segment or `ref.null` of `module` is of
+    /// a value type whose values cannot be copied over faithfully, eg. a
+    /// reference to a concrete heap type.
+    #[error("Unsupported Value Type: {ty} in {module}")]
+    UnsupportedValType { module: String, ty: String },
+
+    /// Self Check Failed
+    ///
+    /// The merged module is inconsistent with its inputs, as described by
+    /// the detail, see `MergeConfiguration::merge_verified`.
+    #[error("Self Check Failed: {0}")]
+    SelfCheckFailed(String),
+
+    /// Component Core Convention
+    ///
+    /// The merged module cannot follow the conventions of a component's core
+    /// module, as described by the detail, see `MergeOptions::component_core`.
+    #[error("Component Core Convention: {0}")]
+    ComponentCoreConvention(String),
+
+    /// Instantiation Failed
+    ///
+    /// The merged module could not be instantiated with the provided
+    /// host imports, see `MergeConfiguration::merge_and_check`. Only raised
+    /// with the `instantiate-check` feature, yet always present so that
+    /// matching on [`Error`] does not depend on the enabled features.
+    #[error("Instantiation Failed: {0}")]
+    InstantiationFailed(anyhow::Error),
 }
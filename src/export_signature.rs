@@ -0,0 +1,120 @@
+use walrus::{RefType, ValType};
+
+use crate::kinds::{ExportKind, FuncType};
+use crate::merge_builder::{AllResolved, MergeRenamer};
+use crate::named_module::NamedParsedModule;
+use crate::resolver::Export;
+
+/// An export of the merged module, by its final name, along with its type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ExportSig {
+    /// The name of the export in the merged module.
+    pub name: String,
+    /// The input module that defines the export.
+    pub module: String,
+    pub ty: ExportType,
+}
+
+/// The type of an export.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ExportType {
+    Function(FuncType),
+    Table {
+        element_ty: RefType,
+        initial: u64,
+        maximum: Option<u64>,
+    },
+    Memory {
+        initial: u64,
+        maximum: Option<u64>,
+    },
+    Global {
+        ty: ValType,
+        mutable: bool,
+    },
+    Tag(FuncType),
+}
+
+impl ExportType {
+    #[must_use]
+    pub fn kind(&self) -> ExportKind {
+        match self {
+            ExportType::Function(_) => ExportKind::Function,
+            ExportType::Table { .. } => ExportKind::Table,
+            ExportType::Memory { .. } => ExportKind::Memory,
+            ExportType::Global { .. } => ExportKind::Global,
+            ExportType::Tag(_) => ExportKind::Tag,
+        }
+    }
+}
+
+impl AllResolved {
+    /// The signature of each remaining export, named as it will be in the
+    /// merged module. The types are looked up in the `parsed` input modules.
+    pub(crate) fn export_signatures(self, parsed: &[NamedParsedModule<'_>]) -> Vec<ExportSig> {
+        let module_of = |export_module: &str| {
+            let parsed = parsed.iter().find(|parsed| parsed.name == export_module);
+            &parsed
+                .expect("Exports originate from the parsed modules")
+                .module
+        };
+        let mut signatures = vec![];
+        let renamer = &self.rename_map;
+        let all_reduced = &self.all_reduced;
+
+        for export in &all_reduced.functions.remaining_exports {
+            let (name, module) = final_name(renamer, export);
+            let ty = ExportType::Function(export.ty.clone());
+            signatures.push(ExportSig { name, module, ty });
+        }
+        for export in &all_reduced.tables.remaining_exports {
+            let (name, module) = final_name(renamer, export);
+            let table = module_of(&module).tables.get(*export.index);
+            let ty = ExportType::Table {
+                element_ty: table.element_ty,
+                initial: table.initial,
+                maximum: table.maximum,
+            };
+            signatures.push(ExportSig { name, module, ty });
+        }
+        for export in &all_reduced.memories.remaining_exports {
+            let (name, module) = final_name(renamer, export);
+            let memory = module_of(&module).memories.get(*export.index);
+            let initial = self
+                .memory_minimums
+                .get(&(export.module().clone(), export.index))
+                .copied()
+                .unwrap_or(memory.initial);
+            let ty = ExportType::Memory {
+                initial,
+                maximum: memory.maximum,
+            };
+            signatures.push(ExportSig { name, module, ty });
+        }
+        for export in &all_reduced.globals.remaining_exports {
+            let (name, module) = final_name(renamer, export);
+            let global = module_of(&module).globals.get(*export.index);
+            let ty = ExportType::Global {
+                ty: global.ty,
+                mutable: global.mutable,
+            };
+            signatures.push(ExportSig { name, module, ty });
+        }
+        for export in &all_reduced.tags.remaining_exports {
+            let (name, module) = final_name(renamer, export);
+            let ty = ExportType::Tag(export.ty.clone());
+            signatures.push(ExportSig { name, module, ty });
+        }
+        signatures
+    }
+}
+
+/// The final name & the defining module of `export`, once renamed.
+fn final_name<Kind, Type, Index>(
+    renamer: &MergeRenamer,
+    export: &Export<Kind, Type, Index>,
+) -> (String, String) {
+    let name = renamer.export_name(export.module(), export.identifier().identifier());
+    let module = export.module().identifier().to_string();
+    (name.to_string(), module)
+}
@@ -0,0 +1,533 @@
+use std::collections::HashSet as Set;
+use std::fmt::Display;
+
+use walrus::ir::{BinaryOp, Instr, InstrLocId, InstrSeq, InstrSeqType, LoadKind, StoreKind};
+use walrus::ir::{TernaryOp, UnaryOp, Value, Visitor};
+use walrus::{
+    ConstExpr, DataKind, ElementKind, FunctionKind, GlobalKind, Module, RefType, ValType,
+};
+
+/// A post-MVP WebAssembly feature.
+///
+/// ref: <https://webassembly.org/features/>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Feature {
+    SignExtension,
+    SaturatingFloatToInt,
+    MultiValue,
+    BulkMemory,
+    ReferenceTypes,
+    Simd,
+    RelaxedSimd,
+    Threads,
+    TailCall,
+    ExceptionHandling,
+    ExtendedConst,
+    MultiMemory,
+    Memory64,
+    FunctionReferences,
+    Gc,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 15] = [
+        Feature::SignExtension,
+        Feature::SaturatingFloatToInt,
+        Feature::MultiValue,
+        Feature::BulkMemory,
+        Feature::ReferenceTypes,
+        Feature::Simd,
+        Feature::RelaxedSimd,
+        Feature::Threads,
+        Feature::TailCall,
+        Feature::ExceptionHandling,
+        Feature::ExtendedConst,
+        Feature::MultiMemory,
+        Feature::Memory64,
+        Feature::FunctionReferences,
+        Feature::Gc,
+    ];
+
+    /// The name of the feature, as used by the WebAssembly proposals.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::SignExtension => "sign-extension",
+            Feature::SaturatingFloatToInt => "nontrapping-float-to-int",
+            Feature::MultiValue => "multi-value",
+            Feature::BulkMemory => "bulk-memory",
+            Feature::ReferenceTypes => "reference-types",
+            Feature::Simd => "simd",
+            Feature::RelaxedSimd => "relaxed-simd",
+            Feature::Threads => "threads",
+            Feature::TailCall => "tail-call",
+            Feature::ExceptionHandling => "exception-handling",
+            Feature::ExtendedConst => "extended-const",
+            Feature::MultiMemory => "multi-memory",
+            Feature::Memory64 => "memory64",
+            Feature::FunctionReferences => "function-references",
+            Feature::Gc => "gc",
+        }
+    }
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A set of post-MVP features a target runtime supports.
+///
+/// The default (empty) set describes an MVP-only runtime.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureSet {
+    features: Set<Feature>,
+}
+
+impl FeatureSet {
+    /// Only the features of the WebAssembly MVP.
+    #[must_use]
+    pub fn mvp() -> Self {
+        Self::default()
+    }
+
+    /// All features known to this crate.
+    #[must_use]
+    pub fn all() -> Self {
+        Self {
+            features: Feature::ALL.into_iter().collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn with(mut self, feature: Feature) -> Self {
+        self.features.insert(feature);
+        self
+    }
+
+    #[must_use]
+    pub fn without(mut self, feature: Feature) -> Self {
+        self.features.remove(&feature);
+        self
+    }
+
+    #[must_use]
+    pub fn contains(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+}
+
+impl FromIterator<Feature> for FeatureSet {
+    fn from_iter<T: IntoIterator<Item = Feature>>(iter: T) -> Self {
+        Self {
+            features: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// A well-known set of features the merged module is restricted to,
+/// see [`MergeOptions::feature_profile`](crate::merge_options::MergeOptions::feature_profile).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FeatureProfile {
+    /// Only the features of the WebAssembly MVP.
+    Mvp,
+    /// The features standardised in WebAssembly 2.0.
+    ///
+    /// ref: <https://webassembly.github.io/spec/versions/core/WebAssembly-2.0.pdf>
+    Wasm2,
+    /// All features known to this crate.
+    #[default]
+    All,
+}
+
+impl FeatureProfile {
+    /// The features of this profile.
+    #[must_use]
+    pub fn features(&self) -> FeatureSet {
+        match self {
+            FeatureProfile::Mvp => FeatureSet::mvp(),
+            FeatureProfile::Wasm2 => [
+                Feature::SignExtension,
+                Feature::SaturatingFloatToInt,
+                Feature::MultiValue,
+                Feature::BulkMemory,
+                Feature::ReferenceTypes,
+                Feature::Simd,
+            ]
+            .into_iter()
+            .collect(),
+            FeatureProfile::All => FeatureSet::all(),
+        }
+    }
+}
+
+/// The first post-MVP feature `module` makes use of outside of `features`.
+pub(crate) fn unsupported_feature(module: &Module, features: &FeatureSet) -> Option<Feature> {
+    used_features(module)
+        .into_iter()
+        .find(|used| !features.contains(*used))
+}
+
+/// The post-MVP features a module makes use of, in the order of [`Feature::ALL`].
+pub(crate) fn used_features(module: &Module) -> Vec<Feature> {
+    let mut used = Set::new();
+
+    if module.memories.len() > 1 {
+        used.insert(Feature::MultiMemory);
+    }
+    for memory in module.memories.iter() {
+        if memory.shared {
+            used.insert(Feature::Threads);
+        }
+        if memory.memory64 {
+            used.insert(Feature::Memory64);
+        }
+    }
+
+    if module.tables.iter().count() > 1 {
+        used.insert(Feature::ReferenceTypes);
+    }
+    for table in module.tables.iter() {
+        if table.table64 {
+            used.insert(Feature::Memory64);
+        }
+        if table.element_ty != RefType::FUNCREF {
+            used.insert(Feature::ReferenceTypes);
+        }
+    }
+
+    for ty in module.types.iter() {
+        if ty.results().len() > 1 {
+            used.insert(Feature::MultiValue);
+        }
+        for val_type in ty.params().iter().chain(ty.results()) {
+            used.extend(value_type_feature(*val_type));
+        }
+    }
+
+    for local in module.locals.iter() {
+        used.extend(value_type_feature(local.ty()));
+    }
+
+    for global in module.globals.iter() {
+        used.extend(value_type_feature(global.ty));
+        if let GlobalKind::Local(ConstExpr::Extended(_)) = global.kind {
+            used.insert(Feature::ExtendedConst);
+        }
+    }
+
+    for data in module.data.iter() {
+        match &data.kind {
+            DataKind::Passive => {
+                used.insert(Feature::BulkMemory);
+            }
+            DataKind::Active { offset, .. } => {
+                if let ConstExpr::Extended(_) = offset {
+                    used.insert(Feature::ExtendedConst);
+                }
+            }
+        }
+    }
+
+    for element in module.elements.iter() {
+        match &element.kind {
+            ElementKind::Passive | ElementKind::Declared => {
+                used.insert(Feature::BulkMemory);
+            }
+            ElementKind::Active { .. } => {}
+        }
+    }
+
+    if module.tags.iter().next().is_some() {
+        used.insert(Feature::ExceptionHandling);
+    }
+
+    for function in module.funcs.iter() {
+        if let FunctionKind::Local(local_function) = &function.kind {
+            let mut scanner = InstructionScanner {
+                module,
+                used: &mut used,
+            };
+            walrus::ir::dfs_in_order(&mut scanner, local_function, local_function.entry_block());
+        }
+    }
+
+    Feature::ALL
+        .into_iter()
+        .filter(|feature| used.contains(feature))
+        .collect()
+}
+
+fn value_type_feature(val_type: ValType) -> Option<Feature> {
+    match val_type {
+        ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64 => None,
+        ValType::V128 => Some(Feature::Simd),
+        ValType::Ref(_) => Some(Feature::ReferenceTypes),
+    }
+}
+
+fn unary_operation_feature(op: UnaryOp) -> Option<Feature> {
+    match op {
+        UnaryOp::I32Extend8S
+        | UnaryOp::I32Extend16S
+        | UnaryOp::I64Extend8S
+        | UnaryOp::I64Extend16S
+        | UnaryOp::I64Extend32S => Some(Feature::SignExtension),
+        UnaryOp::I32TruncSSatF32
+        | UnaryOp::I32TruncUSatF32
+        | UnaryOp::I32TruncSSatF64
+        | UnaryOp::I32TruncUSatF64
+        | UnaryOp::I64TruncSSatF32
+        | UnaryOp::I64TruncUSatF32
+        | UnaryOp::I64TruncSSatF64
+        | UnaryOp::I64TruncUSatF64 => Some(Feature::SaturatingFloatToInt),
+        UnaryOp::I32x4RelaxedTruncF32x4S
+        | UnaryOp::I32x4RelaxedTruncF32x4U
+        | UnaryOp::I32x4RelaxedTruncF64x2SZero
+        | UnaryOp::I32x4RelaxedTruncF64x2UZero => Some(Feature::RelaxedSimd),
+        UnaryOp::I32Eqz
+        | UnaryOp::I32Clz
+        | UnaryOp::I32Ctz
+        | UnaryOp::I32Popcnt
+        | UnaryOp::I64Eqz
+        | UnaryOp::I64Clz
+        | UnaryOp::I64Ctz
+        | UnaryOp::I64Popcnt
+        | UnaryOp::F32Abs
+        | UnaryOp::F32Neg
+        | UnaryOp::F32Ceil
+        | UnaryOp::F32Floor
+        | UnaryOp::F32Trunc
+        | UnaryOp::F32Nearest
+        | UnaryOp::F32Sqrt
+        | UnaryOp::F64Abs
+        | UnaryOp::F64Neg
+        | UnaryOp::F64Ceil
+        | UnaryOp::F64Floor
+        | UnaryOp::F64Trunc
+        | UnaryOp::F64Nearest
+        | UnaryOp::F64Sqrt
+        | UnaryOp::I32WrapI64
+        | UnaryOp::I32TruncSF32
+        | UnaryOp::I32TruncUF32
+        | UnaryOp::I32TruncSF64
+        | UnaryOp::I32TruncUF64
+        | UnaryOp::I64ExtendSI32
+        | UnaryOp::I64ExtendUI32
+        | UnaryOp::I64TruncSF32
+        | UnaryOp::I64TruncUF32
+        | UnaryOp::I64TruncSF64
+        | UnaryOp::I64TruncUF64
+        | UnaryOp::F32ConvertSI32
+        | UnaryOp::F32ConvertUI32
+        | UnaryOp::F32ConvertSI64
+        | UnaryOp::F32ConvertUI64
+        | UnaryOp::F32DemoteF64
+        | UnaryOp::F64ConvertSI32
+        | UnaryOp::F64ConvertUI32
+        | UnaryOp::F64ConvertSI64
+        | UnaryOp::F64ConvertUI64
+        | UnaryOp::F64PromoteF32
+        | UnaryOp::I32ReinterpretF32
+        | UnaryOp::I64ReinterpretF64
+        | UnaryOp::F32ReinterpretI32
+        | UnaryOp::F64ReinterpretI64 => None,
+        // The remaining operators are vector operators
+        _ => Some(Feature::Simd),
+    }
+}
+
+fn binary_operation_feature(op: BinaryOp) -> Option<Feature> {
+    match op {
+        BinaryOp::I8x16RelaxedSwizzle
+        | BinaryOp::F32x4RelaxedMin
+        | BinaryOp::F32x4RelaxedMax
+        | BinaryOp::F64x2RelaxedMin
+        | BinaryOp::F64x2RelaxedMax
+        | BinaryOp::I16x8RelaxedQ15mulrS
+        | BinaryOp::I16x8RelaxedDotI8x16I7x16S => Some(Feature::RelaxedSimd),
+        BinaryOp::I32Eq
+        | BinaryOp::I32Ne
+        | BinaryOp::I32LtS
+        | BinaryOp::I32LtU
+        | BinaryOp::I32GtS
+        | BinaryOp::I32GtU
+        | BinaryOp::I32LeS
+        | BinaryOp::I32LeU
+        | BinaryOp::I32GeS
+        | BinaryOp::I32GeU
+        | BinaryOp::I64Eq
+        | BinaryOp::I64Ne
+        | BinaryOp::I64LtS
+        | BinaryOp::I64LtU
+        | BinaryOp::I64GtS
+        | BinaryOp::I64GtU
+        | BinaryOp::I64LeS
+        | BinaryOp::I64LeU
+        | BinaryOp::I64GeS
+        | BinaryOp::I64GeU
+        | BinaryOp::F32Eq
+        | BinaryOp::F32Ne
+        | BinaryOp::F32Lt
+        | BinaryOp::F32Gt
+        | BinaryOp::F32Le
+        | BinaryOp::F32Ge
+        | BinaryOp::F64Eq
+        | BinaryOp::F64Ne
+        | BinaryOp::F64Lt
+        | BinaryOp::F64Gt
+        | BinaryOp::F64Le
+        | BinaryOp::F64Ge
+        | BinaryOp::I32Add
+        | BinaryOp::I32Sub
+        | BinaryOp::I32Mul
+        | BinaryOp::I32DivS
+        | BinaryOp::I32DivU
+        | BinaryOp::I32RemS
+        | BinaryOp::I32RemU
+        | BinaryOp::I32And
+        | BinaryOp::I32Or
+        | BinaryOp::I32Xor
+        | BinaryOp::I32Shl
+        | BinaryOp::I32ShrS
+        | BinaryOp::I32ShrU
+        | BinaryOp::I32Rotl
+        | BinaryOp::I32Rotr
+        | BinaryOp::I64Add
+        | BinaryOp::I64Sub
+        | BinaryOp::I64Mul
+        | BinaryOp::I64DivS
+        | BinaryOp::I64DivU
+        | BinaryOp::I64RemS
+        | BinaryOp::I64RemU
+        | BinaryOp::I64And
+        | BinaryOp::I64Or
+        | BinaryOp::I64Xor
+        | BinaryOp::I64Shl
+        | BinaryOp::I64ShrS
+        | BinaryOp::I64ShrU
+        | BinaryOp::I64Rotl
+        | BinaryOp::I64Rotr
+        | BinaryOp::F32Add
+        | BinaryOp::F32Sub
+        | BinaryOp::F32Mul
+        | BinaryOp::F32Div
+        | BinaryOp::F32Min
+        | BinaryOp::F32Max
+        | BinaryOp::F32Copysign
+        | BinaryOp::F64Add
+        | BinaryOp::F64Sub
+        | BinaryOp::F64Mul
+        | BinaryOp::F64Div
+        | BinaryOp::F64Min
+        | BinaryOp::F64Max
+        | BinaryOp::F64Copysign => None,
+        // The remaining operators are vector operators
+        _ => Some(Feature::Simd),
+    }
+}
+
+fn ternary_operation_feature(op: TernaryOp) -> Option<Feature> {
+    match op {
+        TernaryOp::F32x4RelaxedMadd
+        | TernaryOp::F32x4RelaxedNmadd
+        | TernaryOp::F64x2RelaxedMadd
+        | TernaryOp::F64x2RelaxedNmadd
+        | TernaryOp::I8x16RelaxedLaneselect
+        | TernaryOp::I16x8RelaxedLaneselect
+        | TernaryOp::I32x4RelaxedLaneselect
+        | TernaryOp::I64x2RelaxedLaneselect
+        | TernaryOp::I32x4RelaxedDotI8x16I7x16AddS => Some(Feature::RelaxedSimd),
+    }
+}
+
+fn instruction_feature(instr: &Instr) -> Option<Feature> {
+    match instr {
+        Instr::Unop(unop) => unary_operation_feature(unop.op),
+        Instr::Binop(binop) => binary_operation_feature(binop.op),
+        Instr::TernOp(ternop) => ternary_operation_feature(ternop.op),
+        Instr::Const(constant) => match constant.value {
+            Value::V128(_) => Some(Feature::Simd),
+            Value::I32(_) | Value::I64(_) | Value::F32(_) | Value::F64(_) => None,
+        },
+        Instr::Load(load) => match load.kind {
+            LoadKind::V128 => Some(Feature::Simd),
+            kind if kind.atomic() => Some(Feature::Threads),
+            _ => None,
+        },
+        Instr::Store(store) => match store.kind {
+            StoreKind::V128 => Some(Feature::Simd),
+            kind if kind.atomic() => Some(Feature::Threads),
+            _ => None,
+        },
+        Instr::V128Bitselect(_)
+        | Instr::I8x16Swizzle(_)
+        | Instr::I8x16Shuffle(_)
+        | Instr::LoadSimd(_) => Some(Feature::Simd),
+        Instr::AtomicRmw(_)
+        | Instr::Cmpxchg(_)
+        | Instr::AtomicNotify(_)
+        | Instr::AtomicWait(_)
+        | Instr::AtomicFence(_) => Some(Feature::Threads),
+        Instr::MemoryInit(_)
+        | Instr::DataDrop(_)
+        | Instr::MemoryCopy(_)
+        | Instr::MemoryFill(_)
+        | Instr::TableInit(_)
+        | Instr::ElemDrop(_)
+        | Instr::TableCopy(_) => Some(Feature::BulkMemory),
+        Instr::TableGet(_)
+        | Instr::TableSet(_)
+        | Instr::TableGrow(_)
+        | Instr::TableSize(_)
+        | Instr::TableFill(_)
+        | Instr::RefNull(_)
+        | Instr::RefIsNull(_)
+        | Instr::RefFunc(_) => Some(Feature::ReferenceTypes),
+        Instr::Select(select) if select.ty.is_some() => Some(Feature::ReferenceTypes),
+        Instr::ReturnCall(_) | Instr::ReturnCallIndirect(_) => Some(Feature::TailCall),
+        Instr::RefAsNonNull(_)
+        | Instr::BrOnNull(_)
+        | Instr::BrOnNonNull(_)
+        | Instr::CallRef(_)
+        | Instr::ReturnCallRef(_) => Some(Feature::FunctionReferences),
+        Instr::RefI31(_)
+        | Instr::I31GetS(_)
+        | Instr::I31GetU(_)
+        | Instr::RefTest(_)
+        | Instr::RefCast(_)
+        | Instr::BrOnCast(_)
+        | Instr::BrOnCastFail(_)
+        | Instr::AnyConvertExtern(_)
+        | Instr::ExternConvertAny(_) => Some(Feature::Gc),
+        Instr::TryTable(_)
+        | Instr::Throw(_)
+        | Instr::ThrowRef(_)
+        | Instr::Try(_)
+        | Instr::Rethrow(_) => Some(Feature::ExceptionHandling),
+        _ => None,
+    }
+}
+
+struct InstructionScanner<'a> {
+    module: &'a Module,
+    used: &'a mut Set<Feature>,
+}
+
+impl<'instr> Visitor<'instr> for InstructionScanner<'_> {
+    fn start_instr_seq(&mut self, instr_seq: &'instr InstrSeq) {
+        if let InstrSeqType::MultiValue(ty) = instr_seq.ty {
+            let ty = self.module.types.get(ty);
+            if !ty.params().is_empty() || ty.results().len() > 1 {
+                self.used.insert(Feature::MultiValue);
+            }
+        }
+    }
+
+    fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+        if let Some(feature) = instruction_feature(instr) {
+            self.used.insert(feature);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet as Set;
+
+use wasmtime::{
+    Engine, Extern, ExternType, Func, Global, Instance, Memory, Ref, Store, Table, Val,
+};
+
+use crate::error::Error;
+use crate::kinds::ExportKind;
+
+/// Instantiates `merged`, satisfying each of its imports with a stub when the
+/// import is part of `host_imports`.
+///
+/// Stub functions return default values, stub globals, memories and tables
+/// are default initialised.
+pub(crate) fn check(merged: &[u8], host_imports: &[(&str, &str, ExportKind)]) -> Result<(), Error> {
+    let host_imports: Set<_> = host_imports.iter().copied().collect();
+
+    let engine = Engine::default();
+    let module = wasmtime::Module::new(&engine, merged).map_err(Error::InstantiationFailed)?;
+    let mut store = Store::new(&engine, ());
+
+    let mut missing = vec![];
+    let mut externs = vec![];
+    for import in module.imports() {
+        let kind = match import.ty() {
+            ExternType::Func(_) => ExportKind::Function,
+            ExternType::Global(_) => ExportKind::Global,
+            ExternType::Memory(_) => ExportKind::Memory,
+            ExternType::Table(_) => ExportKind::Table,
+            ExternType::Tag(_) => ExportKind::Tag,
+        };
+        if !host_imports.contains(&(import.module(), import.name(), kind)) {
+            missing.push((import.module().to_string(), import.name().to_string(), kind));
+            continue;
+        }
+        let stub = stub(&mut store, import.ty()).map_err(Error::InstantiationFailed)?;
+        externs.push(stub);
+    }
+
+    if !missing.is_empty() {
+        return Err(Error::UnresolvedImports(missing));
+    }
+
+    Instance::new(&mut store, &module, &externs).map_err(Error::InstantiationFailed)?;
+    Ok(())
+}
+
+fn stub(store: &mut Store<()>, ty: ExternType) -> wasmtime::Result<Extern> {
+    let default_of = |ty: &wasmtime::ValType| {
+        Val::default_for_ty(ty).ok_or_else(|| anyhow::anyhow!("no default value for {ty}"))
+    };
+    Ok(match ty {
+        ExternType::Func(ty) => {
+            let results: Vec<_> = ty.results().collect();
+            Func::new(&mut *store, ty, move |_, _, outputs| {
+                for (output, result) in outputs.iter_mut().zip(&results) {
+                    *output = default_of(result)?;
+                }
+                Ok(())
+            })
+            .into()
+        }
+        ExternType::Global(ty) => {
+            let value = default_of(ty.content())?;
+            Global::new(&mut *store, ty, value)?.into()
+        }
+        ExternType::Memory(ty) => Memory::new(&mut *store, ty)?.into(),
+        ExternType::Table(ty) => {
+            let init = Ref::null(ty.element().heap_type());
+            Table::new(&mut *store, ty, init)?.into()
+        }
+        ExternType::Tag(_) => anyhow::bail!("stubbing tags is not supported"),
+    })
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap as Map;
 use std::{hash::Hash, marker::PhantomData};
 
 use derive_more::{Display, From, Into};
-use walrus::{LocalId, Module, TypeId, ValType};
+use walrus::{ExportItem, ImportKind, LocalId, Module, TypeId, ValType};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct CrossModuleMismatch {
@@ -16,6 +16,29 @@ pub enum ExportKind {
     Global,
     Memory,
     Table,
+    Tag,
+}
+
+/// The kind of an exported `item`.
+pub(crate) fn export_kind(item: ExportItem) -> ExportKind {
+    match item {
+        ExportItem::Function(_) => ExportKind::Function,
+        ExportItem::Table(_) => ExportKind::Table,
+        ExportItem::Memory(_) => ExportKind::Memory,
+        ExportItem::Global(_) => ExportKind::Global,
+        ExportItem::Tag(_) => ExportKind::Tag,
+    }
+}
+
+/// The kind of an imported item.
+pub(crate) fn import_kind(kind: &ImportKind) -> ExportKind {
+    match kind {
+        ImportKind::Function(_) => ExportKind::Function,
+        ImportKind::Table(_) => ExportKind::Table,
+        ImportKind::Memory(_) => ExportKind::Memory,
+        ImportKind::Global(_) => ExportKind::Global,
+        ImportKind::Tag(_) => ExportKind::Tag,
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -26,8 +49,9 @@ pub struct ConcreteExport {
 
 pub type ClashesMap = Map<String, Vec<ConcreteExport>>;
 
+/// The signature of a function.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub(crate) struct FuncType {
+pub struct FuncType {
     params: Box<[ValType]>,
     results: Box<[ValType]>,
 }
@@ -47,12 +71,12 @@ impl FuncType {
     }
 
     #[must_use]
-    pub(crate) fn params(&self) -> &[ValType] {
+    pub fn params(&self) -> &[ValType] {
         &self.params
     }
 
     #[must_use]
-    pub(crate) fn results(&self) -> &[ValType] {
+    pub fn results(&self) -> &[ValType] {
         &self.results
     }
 
@@ -62,19 +86,19 @@ impl FuncType {
 }
 
 // Supported kinds
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Function;
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Table;
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Memory;
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Global;
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Tag;
 
 // Identifiers
-#[derive(Debug, Clone, Hash, PartialEq, Eq, From, Into)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, From, Into)]
 pub struct IdentifierItem<Kind> {
     identifier: String,
     kind: PhantomData<Kind>,
@@ -101,7 +125,7 @@ impl<Kind> IdentifierItem<Kind> {
     }
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, From, Into, Display)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, From, Into, Display)]
 #[from(String, &str)]
 pub struct IdentifierModule(String);
 
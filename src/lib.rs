@@ -1,30 +1,57 @@
 #![allow(clippy::multiple_crate_versions)]
 
 pub mod error;
+pub mod features;
 pub mod kinds;
 pub mod merge_options;
 
+mod component_core;
+mod data_overlap;
+mod export_signature;
+#[cfg(feature = "instantiate-check")]
+mod instantiate_check;
 mod merge_builder;
 mod merge_configuration;
+mod merge_patch;
+mod merge_report;
 mod merger;
 mod named_module;
 mod resolver;
+mod self_check;
+#[cfg(feature = "serde")]
+mod serializable_options;
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 
+use sha2::{Digest, Sha256};
+
 use error::Error;
+use features::{FeatureProfile, FeatureSet};
+use kinds::{CrossModuleMismatch, ExportKind};
 use merge_builder::Resolver;
-use merge_options::MergeOptions;
-use merger::Merger;
+use merge_options::{MergeOptions, ModuleOrder};
+use merger::{Merged, Merger};
+use named_module::NamedParsedModule;
 
+pub use export_signature::{ExportSig, ExportType};
 pub use merge_configuration::MergeConfiguration;
+pub use merge_patch::MergePatch;
+pub use merge_report::{MergeReport, MergeWarning, PruneReason, ResolutionTarget};
+pub use merger::call_graph::{CallGraph, CallGraphNode};
+pub use merger::old_to_new_mapping::IdMapping;
 pub use named_module::NamedBufferModule;
 pub use named_module::NamedModule;
+pub use named_module::OwnedNamedModule;
 
 pub type ModuleName = String;
 pub type Name = String;
 pub type NewName = String;
 
+/// The SHA-256 digest of a module's bytes, see
+/// [`MergeConfiguration::from_bytes_with_manifest`].
+pub type ContentHash = [u8; 32];
+
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub struct MergedOutcome {
     merged_module: Vec<u8>,
@@ -34,34 +61,407 @@ pub struct MergedOutcome {
 /// The methods that can be called from the public API
 impl<'a> MergeConfiguration<'a, &'a [u8]> {
     #[must_use]
-    pub fn new(modules: &'a [&NamedBufferModule<'a>], options: MergeOptions) -> Self {
+    pub fn new(modules: &[&NamedBufferModule<'a>], options: MergeOptions) -> Self {
+        let modules = modules.iter().map(|&module| module.clone()).collect();
+        Self::new_empty_builder(modules, options)
+    }
+
+    /// Configure the modules from `(name, bytes)` pairs, in input order.
+    #[must_use]
+    pub fn from_named_bytes(modules: &[(&'a str, &'a [u8])], options: MergeOptions) -> Self {
+        let modules = modules
+            .iter()
+            .map(|&(name, module)| NamedModule::new(name, module))
+            .collect();
+        Self::new_empty_builder(modules, options)
+    }
+
+    /// Configure content-addressed modules, in input order, each named by
+    /// the `manifest` entry of its [`ContentHash`].
+    ///
+    /// # Errors
+    /// When the hash of a module is absent from the `manifest`.
+    pub fn from_bytes_with_manifest(
+        modules: &[&'a [u8]],
+        manifest: &HashMap<ContentHash, String>,
+        options: MergeOptions,
+    ) -> Result<Self, Error> {
+        let modules = modules
+            .iter()
+            .map(|&module| {
+                let hash: ContentHash = Sha256::digest(module).into();
+                let Some(name) = manifest.get(&hash) else {
+                    let hash = hash.iter().map(|byte| format!("{byte:02x}")).collect();
+                    return Err(Error::UnmappedContentHash(hash));
+                };
+                Ok(NamedModule {
+                    name: Cow::Owned(name.clone()),
+                    module,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new_empty_builder(modules, options))
+    }
+}
+
+impl MergeConfiguration<'static, Vec<u8>> {
+    /// Configure the modules from owned names & bytes, in input order.
+    #[must_use]
+    pub fn from_owned(modules: Vec<OwnedNamedModule>, options: MergeOptions) -> Self {
         Self::new_empty_builder(modules, options)
     }
+}
 
+impl<Module: AsRef<[u8]>> MergeConfiguration<'_, Module> {
     /// # Errors
     /// When parsing fails or when structural assumptions do not hold
     /// eg. linking imports that are inconsistently typed.
     pub fn merge(&mut self) -> Result<Vec<u8>, Error> {
-        let parsed_modules: Vec<NamedModule<'a, walrus::Module>> =
+        let (merged, _report) = self.merge_with_report()?;
+        Ok(merged)
+    }
+
+    /// Merge only the modules named in `names`, in their configured order.
+    ///
+    /// Imports that target excluded modules remain unresolved imports of the
+    /// merged module.
+    ///
+    /// # Errors
+    /// When a name is not part of the configuration, when parsing fails or
+    /// when structural assumptions do not hold eg. linking imports that are
+    /// inconsistently typed.
+    pub fn merge_subset(&mut self, names: &[&str]) -> Result<Vec<u8>, Error> {
+        let configured = |name: &&str| self.modules.iter().any(|module| module.name == *name);
+        if let Some(unknown) = names.iter().find(|name| !configured(name)) {
+            return Err(Error::UnknownModule((*unknown).to_string()));
+        }
+        let subset: Vec<NamedBufferModule<'_>> = self
+            .modules
+            .iter()
+            .filter(|module| names.contains(&module.name.as_ref()))
+            .map(|module| NamedModule::new(module.name.as_ref(), module.module.as_ref()))
+            .collect();
+        let mut subset = MergeConfiguration::new_empty_builder(subset, self.options.clone());
+        subset.rename_modules.clone_from(&self.rename_modules);
+        subset.merge()
+    }
+
+    /// Merge, verifying the merged module instantiates when the host
+    /// provides `host_imports`, as `(module, name, kind)`.
+    ///
+    /// # Errors
+    /// When merging fails, when an import of the merged module is not part
+    /// of `host_imports` or when instantiation fails.
+    #[cfg(feature = "instantiate-check")]
+    pub fn merge_and_check(
+        &mut self,
+        host_imports: &[(&str, &str, ExportKind)],
+    ) -> Result<Vec<u8>, Error> {
+        let merged = self.merge()?;
+        instantiate_check::check(&merged, host_imports)?;
+        Ok(merged)
+    }
+
+    /// Merge, describing what the other modules add to the module named
+    /// `base`, see [`MergePatch`]. The modules are merged only once.
+    ///
+    /// # Errors
+    /// When `base` is not part of the configuration, when parsing fails or
+    /// when structural assumptions do not hold eg. linking imports that are
+    /// inconsistently typed.
+    pub fn merge_patch(&mut self, base: &str) -> Result<MergePatch, Error> {
+        let (parsed_modules, resolver) = self.considered()?;
+        let Some(base) = parsed_modules.iter().find(|module| module.name == base) else {
+            return Err(Error::UnknownModule(base.to_string()));
+        };
+        let merged = self.merged_from(&parsed_modules, resolver, &self.options)?;
+        let mapping = merged.mapping.to_id_mapping(&merged.module);
+        Ok(MergePatch::between(
+            base,
+            &parsed_modules,
+            &merged.module,
+            &mapping,
+        ))
+    }
+
+    /// Validates each input module on its own, such that an invalid module
+    /// is reported by its name before any merging takes place.
+    ///
+    /// # Errors
+    /// When an input module is not valid WebAssembly.
+    pub fn validate_inputs(&self) -> Result<(), Error> {
+        for NamedModule { name, module } in &self.modules {
+            wasmparser::validate(module.as_ref()).map_err(|error| Error::InvalidInput {
+                module: name.to_string(),
+                detail: error.to_string(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Reports every type mismatch between linked imports & exports, of all
+    /// kinds, rather than failing on the first kind with mismatches.
+    ///
+    /// # Errors
+    /// When parsing fails or when imports form a cycle.
+    pub fn collect_mismatches(&self) -> Result<Vec<(ExportKind, CrossModuleMismatch)>, Error> {
+        let _ = self.module_names()?; // Validate names before parsing
+        self.check_ref_null_types()?;
+
+        let parsed_modules = self.try_parse().map_err(Error::Parse)?;
+        let mut resolver: Resolver = Resolver::for_modules(&parsed_modules);
+        for parsed_module in &parsed_modules {
+            resolver.consider(parsed_module)?;
+        }
+        resolver.collect_mismatches(&self.options)
+    }
+
+    /// Checks whether the merge output is supported by a runtime offering
+    /// the given features, by merging and scanning the merged module for the
+    /// features it makes use of.
+    ///
+    /// Merging can introduce features none of the inputs use, eg. several
+    /// single-memory inputs yield a multi-memory module.
+    ///
+    /// # Errors
+    /// When merging fails or when a feature outside of `features` is used.
+    pub fn check_target(&self, features: &FeatureSet) -> Result<(), Error> {
+        let (parsed_modules, resolver) = self.considered()?;
+        let merged = self.merged_from(&parsed_modules, resolver, &self.options)?;
+        match features::unsupported_feature(&merged.module, features) {
+            Some(feature) => Err(Error::FeatureNotInTarget(feature.name().to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// The signature of each export of the merged module, by its final name,
+    /// without merging: only the imports & exports are resolved.
+    ///
+    /// Exports that are added when building the merged module are not
+    /// included, ie. the exported function table and memory accessors.
+    ///
+    /// # Errors
+    /// When parsing fails or when structural assumptions do not hold
+    /// eg. linking imports that are inconsistently typed.
+    pub fn export_signatures(&self) -> Result<Vec<ExportSig>, Error> {
+        let (parsed_modules, resolver) = self.considered()?;
+        let resolved = resolver.resolve(&self.options)?;
+        Ok(resolved.export_signatures(&parsed_modules))
+    }
+
+    /// Merge, additionally reporting on the outcome of the merge.
+    ///
+    /// # Errors
+    /// When parsing fails or when structural assumptions do not hold
+    /// eg. linking imports that are inconsistently typed.
+    pub fn merge_with_report(&mut self) -> Result<(Vec<u8>, MergeReport), Error> {
+        let merged = self.merged()?;
+        Self::emitted(merged, &self.options)
+    }
+
+    /// Merge, additionally returning the call graph of the merged module.
+    ///
+    /// # Errors
+    /// When parsing fails or when structural assumptions do not hold
+    /// eg. linking imports that are inconsistently typed.
+    pub fn merge_with_callgraph(&mut self) -> Result<(Vec<u8>, CallGraph), Error> {
+        let (parsed_modules, resolver) = self.considered()?;
+        let mut merged = self.merged_from(&parsed_modules, resolver, &self.options)?;
+        let call_graph = merged.call_graph(&parsed_modules);
+        let (wasm, _report) = Self::emitted(merged, &self.options)?;
+        Ok((wasm, call_graph.emitted()))
+    }
+
+    /// Merge once for each of the `variants`, eg. a stripped release build
+    /// and a debug build. The inputs are parsed & considered once, under the
+    /// configured options: the parse error callback & the module order of the
+    /// variants are not taken into account.
+    ///
+    /// # Errors
+    /// When parsing fails or when structural assumptions do not hold
+    /// eg. linking imports that are inconsistently typed, for any variant.
+    pub fn merge_variants(&mut self, variants: &[MergeOptions]) -> Result<Vec<Vec<u8>>, Error> {
+        let (parsed_modules, resolver) = self.considered()?;
+        variants
+            .iter()
+            .map(|options| {
+                let merged = self.merged_from(&parsed_modules, resolver.clone(), options)?;
+                let (wasm, _report) = Self::emitted(merged, options)?;
+                Ok(wasm)
+            })
+            .collect()
+    }
+
+    /// Merge, then check the merged module for consistency: it must parse
+    /// & validate, every kept export must be present and every remaining
+    /// import must be an import of an input module.
+    ///
+    /// # Errors
+    /// When merging fails or when the merged module is inconsistent.
+    pub fn merge_verified(&mut self) -> Result<Vec<u8>, Error> {
+        let (parsed_modules, resolver) = self.considered()?;
+        let merged = self.merged_from(&parsed_modules, resolver, &self.options)?;
+        let mapping = merged.mapping.to_id_mapping(&merged.module);
+        self_check::check(&parsed_modules, &merged.module, &mapping, &self.options)?;
+        let (wasm, _report) = Self::emitted(merged, &self.options)?;
+        self_check::check_emitted(&wasm)?;
+        Ok(wasm)
+    }
+
+    /// Merge, emitting only the interface of the merged module: a module with
+    /// the same imports & exports, of the same types, whose exported functions
+    /// trap. Useful for documentation or to generate stubs.
+    ///
+    /// # Errors
+    /// When parsing fails or when structural assumptions do not hold
+    /// eg. linking imports that are inconsistently typed.
+    pub fn merge_interface(&mut self) -> Result<Vec<u8>, Error> {
+        let mut interface = self.merged()?.into_interface();
+        Ok(interface.emit_wasm())
+    }
+
+    /// Merge, without emitting the merged module, along with where each item
+    /// of the input modules ended up in the merged module.
+    ///
+    /// A `dylink.0` section is only added upon emission and hence absent.
+    ///
+    /// # Errors
+    /// When parsing fails or when structural assumptions do not hold
+    /// eg. linking imports that are inconsistently typed.
+    pub fn merge_with_mapping(&mut self) -> Result<(walrus::Module, IdMapping), Error> {
+        let merged = self.merged()?;
+        let mapping = merged.mapping.to_id_mapping(&merged.module);
+        Ok((merged.module, mapping))
+    }
+
+    fn merged(&mut self) -> Result<Merged, Error> {
+        let (parsed_modules, resolver) = self.considered()?;
+        self.merged_from(&parsed_modules, resolver, &self.options)
+    }
+
+    /// The parsed modules, in the order in which they are merged, and the
+    /// resolver having considered each of them: the first pass.
+    fn considered(&self) -> Result<(Vec<NamedParsedModule<'_>>, Resolver), Error> {
+        let _ = self.module_names()?; // Validate names before parsing
+        self.check_unique_exports()?;
+        self.check_ref_null_types()?;
+
+        let mut parsed_modules: Vec<NamedModule<'_, walrus::Module>> =
             self.try_parse().map_err(Error::Parse)?;
+        if self.options.order == ModuleOrder::Topological {
+            parsed_modules = merge_configuration::in_topological_order(parsed_modules);
+        }
 
         // First pass: consider each parsed module
-        let mut resolver: Resolver = Resolver::new();
+        let mut resolver: Resolver = Resolver::for_modules(&parsed_modules);
         for parsed_module in &parsed_modules {
             resolver.consider(parsed_module)?;
         }
+        Ok((parsed_modules, resolver))
+    }
+
+    /// Merge the `parsed_modules`, as considered by `resolver`, under `options`.
+    fn merged_from(
+        &self,
+        parsed_modules: &[NamedParsedModule<'_>],
+        resolver: Resolver,
+        options: &MergeOptions,
+    ) -> Result<Merged, Error> {
+        let component_options;
+        let options = if options.component_core {
+            component_options = component_core::keeping_canonical_abi(options, parsed_modules)?;
+            &component_options
+        } else {
+            options
+        };
+
+        if options.feature_profile != FeatureProfile::All {
+            let allowed = options.feature_profile.features();
+            for NamedModule { name, module } in parsed_modules {
+                if let Some(feature) = features::unsupported_feature(module, &allowed) {
+                    return Err(Error::FeatureNotAllowed {
+                        feature: feature.name().to_string(),
+                        module: name.to_string(),
+                    });
+                }
+            }
+        }
+
+        let isolated: Vec<MergeWarning> = parsed_modules
+            .iter()
+            .filter(|parsed| parsed.module.imports.iter().next().is_none())
+            .filter(|parsed| parsed.module.exports.iter().next().is_none())
+            .map(|parsed| MergeWarning::IsolatedModule(parsed.name.to_string()))
+            .collect();
+        let unshared_waits = MergeReport::unshared_waits_of(parsed_modules);
 
         // Next, with the given modules, resolve imports & exports
-        let reduced_dependencies = resolver.resolve(&self.options)?;
+        let reduced_dependencies = resolver.resolve(options)?;
+        reduced_dependencies.check_data_overlap(parsed_modules)?;
+        let resolutions = MergeReport::resolutions_of(&reduced_dependencies.all_reduced.functions);
+        let pruned_exports = MergeReport::pruned_exports_of(parsed_modules, options);
         let mut merged_builder = Merger::new(reduced_dependencies);
+        if options.embed_input_hashes {
+            // Only the modules that take part, by the name they are merged under
+            for NamedModule { name, .. } in parsed_modules {
+                let input = self
+                    .modules
+                    .iter()
+                    .find(|input| self.module_alias(&input.name) == name);
+                if let Some(NamedModule { module, .. }) = input {
+                    merged_builder.hash_input(name, module.as_ref());
+                }
+            }
+        }
+
+        if options.tree_shake {
+            for parsed_module in parsed_modules {
+                let input = self
+                    .modules
+                    .iter()
+                    .find(|input| self.module_alias(&input.name) == parsed_module.name);
+                if let Some(NamedModule { module, .. }) = input {
+                    merged_builder.keep_dead_calls(parsed_module, module.as_ref());
+                }
+            }
+        }
 
         // Next follows the second pass in which content is copied over
+        for parsed_module in parsed_modules {
+            merged_builder.include_global_initialisers(parsed_module);
+        }
         for parsed_module in parsed_modules {
             merged_builder.include(parsed_module)?;
         }
 
         // Build merged module
-        Ok(merged_builder.build().emit_wasm())
+        let mut merged = merged_builder.build(options)?;
+        if options.feature_profile != FeatureProfile::All {
+            let allowed = options.feature_profile.features();
+            if let Some(feature) = features::unsupported_feature(&merged.module, &allowed) {
+                return Err(Error::MergedFeatureNotAllowed(feature.name().to_string()));
+            }
+        }
+        merged.report.function_resolutions = resolutions;
+        merged.report.pruned_exports = pruned_exports;
+        merged.report.warnings.extend(isolated);
+        merged.report.warnings.extend(unshared_waits);
+        if options.require_self_contained && !merged.report.is_self_contained {
+            return Err(Error::UnresolvedImports(merged.unresolved_imports()));
+        }
+        Ok(merged)
+    }
+
+    /// Emit the `merged` module, within the output size limit of `options`.
+    fn emitted(merged: Merged, options: &MergeOptions) -> Result<(Vec<u8>, MergeReport), Error> {
+        let (wasm, report) = merged.emit_wasm();
+        if let Some(limit) = options.max_output_bytes
+            && wasm.len() > limit
+        {
+            let actual = wasm.len();
+            return Err(Error::OutputTooLarge { actual, limit });
+        }
+        Ok((wasm, report))
     }
 }
 
@@ -3,27 +3,32 @@ use std::hash::Hash;
 use std::marker::PhantomData;
 
 use anyhow::anyhow;
-use walrus::Module;
+use walrus::ir::{RefNull, Visitor, dfs_in_order};
+use walrus::{ConstExpr, ConstOp, ElementItems, GlobalKind, Module, ValType};
 #[cfg(debug_assertions)]
 use walrus::{FunctionId, GlobalId, ImportId, MemoryId, TableId, TagId};
 
 use crate::MergeOptions;
 use crate::error::Error;
-use crate::kinds::ClashesMap;
+use crate::kinds::{ClashesMap, CrossModuleMismatch, export_kind};
 use crate::kinds::{ConcreteExport, ExportKind, FuncType, IdentifierItem, IdentifierModule};
 use crate::merge_options::{ClashingExports, ExportIdentifier, KeepExports, LinkTypeMismatch};
-use crate::merge_options::{DEFAULT_RENAMER, RenameStrategy};
+use crate::merge_options::{DEFAULT_RENAMER, MAIN_MEMORY_NAME, RenameStrategy, ResolvedExports};
+use crate::merge_report::MergeWarning;
 use crate::merger::old_to_new_mapping::{
     OldIdFunction, OldIdGlobal, OldIdMemory, OldIdTable, OldIdTag,
 };
 use crate::merger::provenance_identifier::{Identifier, Old};
+use crate::merger::walrus_copy::is_supported_val_type;
 use crate::named_module::NamedParsedModule;
 use crate::resolver::dependency_reduction::ReducedDependencies;
 use crate::resolver::error::TypeMismatch;
 use crate::resolver::instantiated::{
     ImportDataFunction, ImportDataGlobal, ImportDataMemory, ImportDataTable, ImportDataTag,
+    ImportFunction, LocalDataGlobal, MemoryLimits, TableLimits,
 };
-use crate::resolver::{Export, Import, Local, Resolver as GraphResolver, instantiated};
+use crate::resolver::subtyping::StructuralMatch;
+use crate::resolver::{Export, Import, Local, Node, Resolver as GraphResolver, instantiated};
 
 #[rustfmt::skip]
 pub(crate) mod builder_instantiated {
@@ -55,6 +60,11 @@ pub(crate) struct Resolver {
     memory: builder_instantiated::ResolverMemory,
     global: builder_instantiated::ResolverGlobal,
     tag: builder_instantiated::ResolverTag,
+    /// The exports of the considered modules as `(module, name)`, in the
+    /// order in which they are considered.
+    exports: Vec<(String, String)>,
+    /// The considered modules, in the order in which they are considered.
+    modules: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -67,23 +77,105 @@ pub(crate) struct AllReducedDependencies {
 }
 
 type KeepRetriever<Kind> = fn(&KeepExports) -> &Set<ExportIdentifier<IdentifierItem<Kind>>>;
-type RenameRetriever<Kind> =
-    fn(&RenameStrategy) -> &fn(&IdentifierModule, IdentifierItem<Kind>) -> IdentifierItem<Kind>;
+
+/// The number of items of one kind in a module, imported or local, & the
+/// number of exports of that kind.
+#[derive(Debug, Default, Clone, Copy)]
+struct ItemCount {
+    indices: usize,
+    exports: usize,
+}
+
+impl ItemCount {
+    /// The item count of `module`, for each kind.
+    fn of(module: &Module) -> Map<ExportKind, ItemCount> {
+        let mut counts: Map<ExportKind, ItemCount> = Map::with_capacity(5);
+        let indices = [
+            (ExportKind::Function, module.funcs.iter().count()),
+            (ExportKind::Table, module.tables.iter().count()),
+            (ExportKind::Memory, module.memories.iter().count()),
+            (ExportKind::Global, module.globals.iter().count()),
+            (ExportKind::Tag, module.tags.iter().count()),
+        ];
+        for (kind, indices) in indices {
+            counts.entry(kind).or_default().indices = indices;
+        }
+        for export in module.exports.iter() {
+            counts.entry(export_kind(export.item)).or_default().exports += 1;
+        }
+        counts
+    }
+}
 
 impl Resolver {
-    pub(crate) fn new() -> Self {
+    /// A resolver with room for all items of the `modules` it will consider,
+    /// such that merging many small modules does not repeatedly grow the
+    /// graphs & maps of each kind.
+    pub(crate) fn for_modules(modules: &[NamedParsedModule<'_>]) -> Self {
+        let mut totals: Map<ExportKind, ItemCount> = Map::with_capacity(5);
+        for NamedParsedModule { module, .. } in modules {
+            for (kind, count) in ItemCount::of(module) {
+                let total = totals.entry(kind).or_default();
+                total.indices += count.indices;
+                total.exports += count.exports;
+            }
+        }
+        let total = |kind| totals.get(&kind).copied().unwrap_or_default();
+        let (modules, function, table) = (
+            modules.len(),
+            total(ExportKind::Function),
+            total(ExportKind::Table),
+        );
+        let (memory, global, tag) = (
+            total(ExportKind::Memory),
+            total(ExportKind::Global),
+            total(ExportKind::Tag),
+        );
         Self {
-            function: GraphResolver::new(),
-            table: GraphResolver::new(),
-            global: GraphResolver::new(),
-            memory: GraphResolver::new(),
-            tag: GraphResolver::new(),
+            function: GraphResolver::with_capacity(modules, function.indices, function.exports),
+            table: GraphResolver::with_capacity(modules, table.indices, table.exports),
+            memory: GraphResolver::with_capacity(modules, memory.indices, memory.exports),
+            global: GraphResolver::with_capacity(modules, global.indices, global.exports),
+            tag: GraphResolver::with_capacity(modules, tag.indices, tag.exports),
+            exports: Vec::with_capacity(
+                function.exports + table.exports + memory.exports + global.exports + tag.exports,
+            ),
+            modules: Vec::with_capacity(modules),
+        }
+    }
+
+    /// Reserve room in the resolver of each kind for `module`.
+    fn reserve_module(&mut self, considering_module: &IdentifierModule, module: &Module) {
+        for (kind, ItemCount { indices, exports }) in ItemCount::of(module) {
+            match kind {
+                ExportKind::Function => {
+                    self.function
+                        .reserve_module(considering_module, indices, exports);
+                }
+                ExportKind::Table => {
+                    self.table
+                        .reserve_module(considering_module, indices, exports)
+                }
+                ExportKind::Memory => {
+                    self.memory
+                        .reserve_module(considering_module, indices, exports);
+                }
+                ExportKind::Global => {
+                    self.global
+                        .reserve_module(considering_module, indices, exports);
+                }
+                ExportKind::Tag => self
+                    .tag
+                    .reserve_module(considering_module, indices, exports),
+            }
         }
     }
 
     pub(crate) fn consider(&mut self, module: &NamedParsedModule<'_>) -> Result<(), Error> {
         let NamedParsedModule { name, module } = module;
         let considering_module: IdentifierModule = (*name).to_string().into();
+        self.modules
+            .push(considering_module.identifier().to_string());
 
         #[cfg(debug_assertions)]
         let (
@@ -94,6 +186,9 @@ impl Resolver {
             mut covered_imports_tag,
         ) = (Set::new(), Set::new(), Set::new(), Set::new(), Set::new());
 
+        Self::check_val_types(&considering_module, module)?;
+        self.reserve_module(&considering_module, module);
+
         self.consider_imports(
             &considering_module,
             module,
@@ -175,15 +270,20 @@ impl Resolver {
                     let table = module.tables.get(*old_id_table);
                     let ty = table.element_ty;
                     let old_id: OldIdTable = (*old_id_table).into();
-                    let data = ImportDataTable;
+                    let data = ImportDataTable {
+                        limits: TableLimits::of(table),
+                    };
                     let import = Self::import_from(import, considering_module, old_id, ty, data);
                     self.table.add_import(import);
                 }
                 walrus::ImportKind::Memory(old_id_memory) => {
                     #[cfg(debug_assertions)]
                     covered_imports_memory.insert((old_id_memory, import.id()));
+                    let memory = module.memories.get(*old_id_memory);
                     let old_id: OldIdMemory = (*old_id_memory).into();
-                    let data = ImportDataMemory;
+                    let data = ImportDataMemory {
+                        limits: MemoryLimits::of(memory),
+                    };
                     let import = Self::import_from(import, considering_module, old_id, (), data);
                     self.memory.add_import(import);
                 }
@@ -271,8 +371,12 @@ impl Resolver {
             match &global.kind {
                 walrus::GlobalKind::Local(local_global) => {
                     let _ = local_global; // Particular expression is not of interest @ consideration time
+                    let data = LocalDataGlobal {
+                        mutable: global.mutable,
+                        shared: global.shared,
+                    };
                     let local =
-                        Self::local_from(considering_module, global.id().into(), global.ty, ());
+                        Self::local_from(considering_module, global.id().into(), global.ty, data);
                     self.global.add_local(local);
                 }
                 walrus::GlobalKind::Import(i) => {
@@ -297,7 +401,8 @@ impl Resolver {
                 #[cfg(debug_assertions)]
                 debug_assert!(covered_imports_memory.contains(&(&memory.id(), *i)));
             } else {
-                let local = Self::local_from(considering_module, memory.id().into(), (), ());
+                let limits = MemoryLimits::of(memory);
+                let local = Self::local_from(considering_module, memory.id().into(), (), limits);
                 self.memory.add_local(local);
             }
         }
@@ -315,8 +420,13 @@ impl Resolver {
                 #[cfg(debug_assertions)]
                 debug_assert!(covered_imports_table.contains(&(&table.id(), *i)));
             } else {
-                let local =
-                    Self::local_from(considering_module, table.id().into(), table.element_ty, ());
+                let data = TableLimits::of(table);
+                let local = Self::local_from(
+                    considering_module,
+                    table.id().into(),
+                    table.element_ty,
+                    data,
+                );
                 self.table.add_local(local);
             }
         }
@@ -324,6 +434,8 @@ impl Resolver {
 
     fn consider_exports(&mut self, considering_module: &IdentifierModule, module: &Module) {
         for export in module.exports.iter() {
+            let export_module = considering_module.identifier().to_string();
+            self.exports.push((export_module, export.name.clone()));
             match &export.item {
                 walrus::ExportItem::Function(old_id_function) => {
                     let func = module.funcs.get(*old_id_function);
@@ -410,39 +522,488 @@ impl Resolver {
         }
     }
 
+    /// All type mismatches among linked imports & exports, of all kinds,
+    /// without resolving any of them.
+    pub(crate) fn collect_mismatches(
+        self,
+        merge_options: &MergeOptions,
+    ) -> Result<Vec<(ExportKind, CrossModuleMismatch)>, Error> {
+        let structural = matches!(
+            merge_options.link_type_mismatch,
+            LinkTypeMismatch::Structural
+        );
+        let mut mismatches = vec![];
+        for (kind, kind_mismatches) in [
+            (
+                ExportKind::Function,
+                Self::kind_mismatches(self.function, ExportKind::Function, structural)?,
+            ),
+            (
+                ExportKind::Table,
+                Self::kind_mismatches(self.table, ExportKind::Table, structural)?,
+            ),
+            (
+                ExportKind::Memory,
+                Self::kind_mismatches(self.memory, ExportKind::Memory, structural)?,
+            ),
+            (
+                ExportKind::Global,
+                Self::kind_mismatches(self.global, ExportKind::Global, structural)?,
+            ),
+            (
+                ExportKind::Tag,
+                Self::kind_mismatches(self.tag, ExportKind::Tag, structural)?,
+            ),
+        ] {
+            mismatches.extend(kind_mismatches.into_iter().map(|mismatch| (kind, mismatch)));
+        }
+        Ok(mismatches)
+    }
+
+    fn kind_mismatches<Kind, Type, Index, ImportData, LocalData>(
+        resolver: GraphResolver<Kind, Type, Index, ImportData, LocalData>,
+        kind: ExportKind,
+        structural: bool,
+    ) -> Result<Vec<CrossModuleMismatch>, Error>
+    where
+        Index: Clone + Eq + Hash,
+        Kind: Clone + Eq + Hash + StructuralMatch<Type>,
+        Type: Clone + Eq + Hash,
+        ImportData: Clone + Eq + Hash,
+        LocalData: Clone + Eq + Hash,
+    {
+        let linked = resolver
+            .link_nodes()
+            .map_err(|cycle| cycle.into_error(kind))?;
+        match linked.type_check_mismatch_signal_with(structural) {
+            Ok(()) => Ok(vec![]),
+            Err(TypeMismatch(mismatches)) => Ok(mismatches),
+        }
+    }
+
     pub(crate) fn resolve(self, merge_options: &MergeOptions) -> Result<AllResolved, Error> {
-        let all_reduced = AllReducedDependencies {
-            functions: Self::resolve_kind(self.function, merge_options, KeepExports::functions)?,
-            tables: Self::resolve_kind(self.table, merge_options, KeepExports::tables)?,
-            memories: Self::resolve_kind(self.memory, merge_options, KeepExports::memories)?,
-            globals: Self::resolve_kind(self.global, merge_options, KeepExports::globals)?,
-            tags: Self::resolve_kind(self.tag, merge_options, KeepExports::tags)?,
+        let mut errors = vec![];
+        let mut collect = |error| errors.push(error);
+
+        let main_memory = merge_options.main_memory.as_ref();
+        let mut renames = merge_options.export_renames.clone();
+        let functions = Self::resolve_kind(
+            self.function,
+            ExportKind::Function,
+            merge_options,
+            KeepExports::functions,
+            None,
+            &mut renames,
+        );
+        let tables = Self::resolve_kind(
+            self.table,
+            ExportKind::Table,
+            merge_options,
+            KeepExports::tables,
+            None,
+            &mut renames,
+        );
+        let memories = Self::resolve_kind(
+            self.memory,
+            ExportKind::Memory,
+            merge_options,
+            KeepExports::memories,
+            main_memory,
+            &mut renames,
+        );
+        let globals = Self::resolve_kind(
+            self.global,
+            ExportKind::Global,
+            merge_options,
+            KeepExports::globals,
+            None,
+            &mut renames,
+        );
+        let tags = Self::resolve_kind(
+            self.tag,
+            ExportKind::Tag,
+            merge_options,
+            KeepExports::tags,
+            None,
+            &mut renames,
+        );
+
+        let functions = functions.map_err(&mut collect).ok();
+        let tables = tables.map_err(&mut collect).ok();
+        let memories = memories.map_err(&mut collect).ok();
+        let globals = globals.map_err(&mut collect).ok();
+        let tags = tags.map_err(&mut collect).ok();
+
+        let memory_minimums = match &memories {
+            Some(memories) => Self::check_memory_limits(memories)
+                .map_err(&mut collect)
+                .unwrap_or_default(),
+            None => Map::new(),
         };
 
-        let clashes_result = Self::identify_clashes(&all_reduced);
-        let rename_map = merge_options
-            .clashing_exports
+        if let Some(tables) = &tables
+            && let Err(error) = Self::check_table_limits(tables)
+        {
+            collect(error);
+        }
+
+        // Clashes are identified among all kinds that could be resolved
+        let mut dependencies: Vec<Box<dyn CollectExports>> = vec![];
+        if let Some(functions) = &functions {
+            dependencies.push(Box::new(functions));
+        }
+        if let Some(globals) = &globals {
+            dependencies.push(Box::new(globals));
+        }
+        if let Some(memories) = &memories {
+            dependencies.push(Box::new(memories));
+        }
+        if let Some(tables) = &tables {
+            dependencies.push(Box::new(tables));
+        }
+        if let Some(tags) = &tags {
+            dependencies.push(Box::new(tags));
+        }
+        let clashes_result = Self::identify_clashes(&dependencies);
+        drop(dependencies);
+        let policy = &merge_options.name_policy;
+        let rename_map = policy
+            .clashes
             .clone()
-            .handle(clashes_result)?;
+            .handle(clashes_result)
+            .map_err(&mut collect)
+            .ok();
+        let import_renames = match &functions {
+            Some(functions) if policy.settle_imports => {
+                Self::rename_import_clashes(functions, &policy.clashes, &self.modules)
+                    .map_err(&mut collect)
+                    .unwrap_or_default()
+            }
+            _ => ImportRenames::new(),
+        };
+
+        if !errors.is_empty() {
+            return Err(if merge_options.collect_all_errors {
+                Error::Aggregate(errors)
+            } else {
+                errors.swap_remove(0)
+            });
+        }
+
+        let all_reduced = AllReducedDependencies {
+            functions: functions.unwrap(),
+            tables: tables.unwrap(),
+            memories: memories.unwrap(),
+            globals: globals.unwrap(),
+            tags: tags.unwrap(),
+        };
+
+        let kept = merge_options
+            .keep_exports
+            .as_ref()
+            .map(KeepExports::names)
+            .unwrap_or_default();
+        let remaining = Self::remaining_exports(&self.exports, &all_reduced, &renames);
+        let mut rename_map = rename_map
+            .unwrap()
+            .keeping(kept)
+            .renaming_imports(import_renames);
+        rename_map.settle(&remaining)?;
 
         Ok(AllResolved {
             all_reduced,
             rename_map,
+            dedupe_imports: policy.dedupe_imports,
+            inline_const_globals: merge_options.inline_const_globals,
+            memory_minimums,
+            renames,
+            modules: self.modules.into_iter().map(Into::into).collect(),
         })
     }
 
+    /// The remaining exports, by the name they take before clashes are
+    /// settled, in the order in which their modules were considered.
+    fn remaining_exports(
+        exports: &[(String, String)],
+        all_reduced: &AllReducedDependencies,
+        renames: &ExportRenames,
+    ) -> Vec<RemainingExport> {
+        fn collect<Kind, Type, Index, ImportData, LocalData>(
+            reduced: &ReducedDependencies<Kind, Type, Index, ImportData, LocalData>,
+            kind: ExportKind,
+            remaining: &mut Map<(String, String), ExportKind>,
+        ) {
+            for export in &reduced.remaining_exports {
+                let module = export.module().identifier().to_string();
+                let name = export.identifier().identifier().to_string();
+                remaining.insert((module, name), kind);
+            }
+        }
+
+        let mut remaining = Map::new();
+        collect(&all_reduced.functions, ExportKind::Function, &mut remaining);
+        collect(&all_reduced.tables, ExportKind::Table, &mut remaining);
+        collect(&all_reduced.memories, ExportKind::Memory, &mut remaining);
+        collect(&all_reduced.globals, ExportKind::Global, &mut remaining);
+        collect(&all_reduced.tags, ExportKind::Tag, &mut remaining);
+
+        exports
+            .iter()
+            .filter_map(|export_id| {
+                let name = renames.get(export_id).unwrap_or(&export_id.1);
+                let key = (export_id.0.clone(), name.clone());
+                let kind = remaining.remove(&key)?;
+                let (module, name) = key;
+                Some(RemainingExport { module, name, kind })
+            })
+            .collect()
+    }
+
+    /// Validates that the locals, globals, tables, element segments &
+    /// `ref.null`s of `module` are of value types that are copied over
+    /// faithfully, see [`is_supported_val_type`]. A concrete heap type indexes
+    /// the types of its module, yet walrus cannot emit these (yet), such that
+    /// it cannot be carried over into the merged module.
+    fn check_val_types(
+        considering_module: &IdentifierModule,
+        module: &Module,
+    ) -> Result<(), Error> {
+        struct RefNulls(Vec<ValType>);
+
+        impl<'instr> Visitor<'instr> for RefNulls {
+            fn visit_ref_null(&mut self, ref_null: &RefNull) {
+                self.0.push(ValType::Ref(ref_null.ty));
+            }
+        }
+
+        fn const_ref_nulls(const_expr: &ConstExpr) -> Vec<ValType> {
+            match const_expr {
+                ConstExpr::RefNull(ty) => vec![ValType::Ref(*ty)],
+                ConstExpr::Extended(const_ops) => const_ops
+                    .iter()
+                    .filter_map(|const_op| match const_op {
+                        ConstOp::RefNull(ty) => Some(ValType::Ref(*ty)),
+                        _ => None,
+                    })
+                    .collect(),
+                ConstExpr::Value(_) | ConstExpr::Global(_) | ConstExpr::RefFunc(_) => vec![],
+            }
+        }
+
+        let locals = module.locals.iter().map(walrus::Local::ty);
+        let globals = module.globals.iter().map(|global| global.ty);
+        let tables = module
+            .tables
+            .iter()
+            .map(|table| ValType::Ref(table.element_ty));
+        let elements = module
+            .elements
+            .iter()
+            .flat_map(|element| match &element.items {
+                ElementItems::Functions(_) => vec![],
+                ElementItems::Expressions(ref_type, const_exprs) => {
+                    let mut tys = vec![ValType::Ref(*ref_type)];
+                    tys.extend(const_exprs.iter().flat_map(const_ref_nulls));
+                    tys
+                }
+            });
+        let initialisers = module.globals.iter().flat_map(|global| match &global.kind {
+            GlobalKind::Local(const_expr) => const_ref_nulls(const_expr),
+            GlobalKind::Import(_) => vec![],
+        });
+        let mut ref_nulls = RefNulls(vec![]);
+        for (_, function) in module.funcs.iter_local() {
+            dfs_in_order(&mut ref_nulls, function, function.entry_block());
+        }
+        let unsupported = locals
+            .chain(globals)
+            .chain(tables)
+            .chain(elements)
+            .chain(initialisers)
+            .chain(ref_nulls.0)
+            .find(|ty| !is_supported_val_type(*ty));
+        match unsupported {
+            Some(ty) => Err(Error::UnsupportedValType {
+                module: considering_module.identifier().to_string(),
+                ty: ty.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates that each linked table import is satisfied by the limits of
+    /// the table it resolves to.
+    fn check_table_limits(
+        tables: &builder_instantiated::ReducedDependenciesTable,
+    ) -> Result<(), Error> {
+        let mut mismatches = vec![];
+        for (node, source) in &tables.reduction_map {
+            let Node::Import(import) = node else {
+                continue;
+            };
+            let (module, limits) = match source {
+                Node::Import(source) if source == import => continue,
+                Node::Import(source) => (source.importing_module(), source.limits()),
+                Node::Local(source) => (source.module(), source.data()),
+                Node::Export(_) => unreachable!("exports always reduce to a source"),
+            };
+            if !limits.matches(import.limits()) {
+                mismatches.push(CrossModuleMismatch {
+                    importing: import.importing_module().clone(),
+                    exporting: module.clone(),
+                });
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::TableLimitsMismatch(mismatches))
+        }
+    }
+
+    /// Validates that each linked memory import is satisfied by the limits
+    /// of the memory it resolves to. An import requiring more initial pages
+    /// than its source declares is reconciled by growing the source, as long
+    /// as its maximum permits. Returns the reconciled initial size of each
+    /// source memory that must grow, keyed by its defining module & index.
+    fn check_memory_limits(
+        memories: &builder_instantiated::ReducedDependenciesMemory,
+    ) -> Result<MemoryMinimums, Error> {
+        let mut minimums: MemoryMinimums = Map::new();
+        let mut mismatches = vec![];
+        for (node, source) in &memories.reduction_map {
+            let Node::Import(import) = node else {
+                continue;
+            };
+            let (module, index, limits) = match source {
+                Node::Import(source) if source == import => continue,
+                Node::Import(source) => (
+                    source.importing_module(),
+                    source.imported_index(),
+                    &source.data.limits,
+                ),
+                Node::Local(source) => (source.module(), source.index(), source.data()),
+                Node::Export(_) => unreachable!("exports always reduce to a source"),
+            };
+            let required = &import.data.limits;
+            let grown = MemoryLimits {
+                initial: limits.initial.max(required.initial),
+                ..limits.clone()
+            };
+            let within_maximum = limits
+                .maximum
+                .is_none_or(|maximum| grown.initial <= maximum);
+            if !within_maximum || !grown.matches(required) {
+                mismatches.push(CrossModuleMismatch {
+                    importing: import.importing_module().clone(),
+                    exporting: module.clone(),
+                });
+            } else if grown.initial > limits.initial {
+                let minimum = minimums.entry((module.clone(), *index)).or_default();
+                *minimum = (*minimum).max(grown.initial);
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(minimums)
+        } else {
+            Err(Error::MemoryLimitsMismatch(mismatches))
+        }
+    }
+
+    /// The final names of the function imports that share their module &
+    /// name but differ in type, renamed by the `clashes` policy.
+    fn rename_import_clashes(
+        functions: &builder_instantiated::ReducedDependenciesFunction,
+        clashes: &ClashingExports,
+        modules: &[String],
+    ) -> Result<ImportRenames, Error> {
+        let name_of = |import: &ImportFunction<OldIdFunction>| {
+            let module = import.exporting_module().identifier().to_string();
+            let name = import.exporting_identifier().identifier().to_string();
+            (module, name)
+        };
+
+        let mut types: Map<(String, String), Set<&FuncType>> = Map::new();
+        for import in &functions.remaining_imports {
+            types
+                .entry(name_of(import))
+                .or_default()
+                .insert(import.ty());
+        }
+        let mut clashing: Vec<(String, String)> = types
+            .into_iter()
+            .filter(|(_, types)| types.len() > 1)
+            .map(|(name, _)| name)
+            .collect();
+        if clashing.is_empty() {
+            return Ok(ImportRenames::new());
+        }
+
+        let strategy = match clashes {
+            ClashingExports::Rename(strategy) | ClashingExports::AlwaysPrefix(strategy) => strategy,
+            ClashingExports::Signal => {
+                clashing.sort();
+                return Err(Error::ImportNameClash(clashing));
+            }
+        };
+
+        // Clashing imports are settled like clashing exports, in input order
+        let position =
+            |module: &IdentifierModule| modules.iter().position(|name| name == module.identifier());
+        let mut imports: Vec<_> = functions
+            .remaining_imports
+            .iter()
+            .filter(|import| clashing.contains(&name_of(import)))
+            .collect();
+        imports.sort_by_key(|import| {
+            (
+                position(import.importing_module()),
+                import.imported_index().index(),
+            )
+        });
+
+        let mut taken: Set<(String, String)> =
+            functions.remaining_imports.iter().map(name_of).collect();
+        let mut encountered = Set::new();
+        let mut settled = Set::new();
+        let mut counts: Map<(String, String), usize> = Map::new();
+        let mut renames = ImportRenames::new();
+        for import in imports {
+            let (module, name) = name_of(import);
+            let importing = import.importing_module().clone();
+            if !settled.insert((importing.clone(), module.clone(), name.clone())) {
+                continue; // Imported once more by the same module
+            }
+            let first = encountered.insert((module.clone(), name.clone()));
+            if first && !strategy.first_occurrence {
+                continue;
+            }
+            let renamed = match strategy.numbered {
+                Some(numbered) => {
+                    let count = counts.entry((module.clone(), name.clone())).or_default();
+                    loop {
+                        *count += 1;
+                        let numbered = numbered(&name, *count);
+                        if !taken.contains(&(module.clone(), numbered.clone())) {
+                            break numbered;
+                        }
+                    }
+                }
+                None => strategy.rename(ExportKind::Function, importing.identifier(), &name),
+            };
+            taken.insert((module.clone(), renamed.clone()));
+            renames.insert((importing, module, name), renamed);
+        }
+        Ok(renames)
+    }
+
     /// Identifies all name clashes, as all export names should be unique.
     /// ref: <https://webassembly.github.io/spec/core/syntax/modules.html#exports>
-    fn identify_clashes(reduced_dependencies: &AllReducedDependencies) -> ClashesResult {
+    fn identify_clashes(dependencies: &[Box<dyn CollectExports + '_>]) -> ClashesResult {
         let mut module_exports: Map<String, Vec<ConcreteExport>> = Map::new();
 
-        let dependencies: &[Box<dyn CollectExports>] = &[
-            Box::new(&reduced_dependencies.functions),
-            Box::new(&reduced_dependencies.globals),
-            Box::new(&reduced_dependencies.memories),
-            Box::new(&reduced_dependencies.tables),
-        ];
-
         for dependency in dependencies {
             dependency.collect_into(&mut module_exports);
         }
@@ -460,64 +1021,163 @@ impl Resolver {
         }
     }
 
+    /// Resolve the imports & exports of a single kind. The `main` export, as
+    /// `(module, name)`, takes the name [`MAIN_MEMORY_NAME`], other exports
+    /// of this kind by that name are prefixed by their module. The final
+    /// names of renamed exports are recorded in `renames`.
     fn resolve_kind<Kind, Type, Index, ImportData, LocalData>(
         resolver: GraphResolver<Kind, Type, Index, ImportData, LocalData>,
+        kind: ExportKind,
         merge_options: &MergeOptions,
         keep_retriever: KeepRetriever<Kind>,
+        main: Option<&(String, String)>,
+        renames: &mut ExportRenames,
     ) -> Result<ReducedDependencies<Kind, Type, Index, ImportData, LocalData>, Error>
     where
         Index: Clone + Eq + Hash,
-        Kind: Clone + Eq + Hash,
+        Kind: Clone + Eq + Hash + StructuralMatch<Type>,
         Type: Clone + Eq + Hash,
         ImportData: Clone + Eq + Hash,
         LocalData: Clone + Eq + Hash,
     {
-        let mut linked = resolver.link_nodes().map_err(|_| Error::ImportCycle)?;
+        let mut linked = resolver
+            .link_nodes()
+            .map_err(|cycle| cycle.into_error(kind))?;
 
         match &merge_options.link_type_mismatch {
             LinkTypeMismatch::Ignore => linked.type_check_mismatch_break(),
             LinkTypeMismatch::Signal => linked
                 .type_check_mismatch_signal()
                 .map_err(|TypeMismatch(mismatches)| Error::TypeMismatch(mismatches))?,
+            LinkTypeMismatch::Structural => linked
+                .type_check_mismatch_signal_structural()
+                .map_err(|TypeMismatch(mismatches)| Error::TypeMismatch(mismatches))?,
         }
 
         let keeper = merge_options.keep_exports.as_ref().map(keep_retriever);
-        Ok(linked.reduce_dependencies(keeper))
+        let mut reduced = linked.reduce_dependencies(keeper);
+
+        // Resolved exports that are kept remain, such that they partake in
+        // clash detection alongside the unresolved exports
+        if merge_options.resolved_exports == ResolvedExports::Keep {
+            let resolved = reduced.reduction_map.keys().filter_map(Node::as_export);
+            let resolved: Vec<_> = resolved.cloned().collect();
+            reduced.remaining_exports.extend(resolved);
+        }
+
+        // Internal exports have served linking, now they are dropped
+        let internal_exports = &merge_options.internal_exports;
+        if !internal_exports.is_empty() {
+            reduced.remaining_exports.retain(|export| {
+                let module = export.module().identifier().to_string();
+                let name = export.identifier().identifier().to_string();
+                !internal_exports.contains(&(module, name))
+            });
+        }
+
+        // Explicitly renamed exports take their final name before clashes are identified
+        let export_renames = &merge_options.export_renames;
+        if !export_renames.is_empty() || main.is_some() {
+            reduced.remaining_exports = reduced
+                .remaining_exports
+                .drain()
+                .map(|mut export| {
+                    let module = export.module().identifier().to_string();
+                    let name = export.identifier().identifier().to_string();
+                    let export_id = (module, name);
+                    let new_name = export_renames.get(&export_id).unwrap_or(&export_id.1);
+                    let new_name = if main == Some(&export_id) {
+                        MAIN_MEMORY_NAME.to_string()
+                    } else if main.is_some() && new_name == MAIN_MEMORY_NAME {
+                        format!("{}.{new_name}", export_id.0)
+                    } else {
+                        new_name.clone()
+                    };
+                    if new_name != export_id.1 {
+                        renames.insert(export_id, new_name.clone());
+                    }
+                    export.identifier = new_name.into();
+                    export
+                })
+                .collect();
+        }
+
+        Ok(reduced)
     }
 }
 
 pub(crate) struct AllResolved {
     pub(crate) all_reduced: AllReducedDependencies,
     pub(crate) rename_map: MergeRenamer,
+    pub(crate) dedupe_imports: bool,
+    pub(crate) inline_const_globals: bool,
+    /// The initial size that a source memory grows to, to satisfy its importers.
+    pub(crate) memory_minimums: MemoryMinimums,
+    /// The final name of each renamed export, by `(module, name)`.
+    pub(crate) renames: ExportRenames,
+    /// The considered modules, in the order in which they are considered.
+    pub(crate) modules: Vec<IdentifierModule>,
 }
 
+/// Final export names, by `(module, name)`.
+pub(crate) type ExportRenames = Map<(String, String), String>;
+
+/// Final import names, by `(importing module, module, name)`.
+pub(crate) type ImportRenames = Map<(IdentifierModule, String, String), String>;
+
+impl AllResolved {
+    /// The name under which `name`, exported by `module`, remains.
+    pub(crate) fn final_export_name(&self, module: &IdentifierModule, name: &str) -> String {
+        let export_id = (module.identifier().to_string(), name.to_string());
+        self.renames.get(&export_id).cloned().unwrap_or(export_id.1)
+    }
+}
+
+/// The reconciled initial size of a memory, by its module & index.
+pub(crate) type MemoryMinimums = Map<(IdentifierModule, OldIdMemory), u64>;
+
 impl ClashingExports {
     fn handle(self, clashes_result: ClashesResult) -> Result<MergeRenamer, Error> {
-        let ClashesResult::Some(clashes) = clashes_result else {
-            return Ok(MergeRenamer::for_no_clashes_present());
-        };
-
-        match self {
-            ClashingExports::Rename(strategy) => Ok(MergeRenamer::new(clashes, strategy)),
-            ClashingExports::Signal => Err(Error::ExportNameClash(clashes)),
+        match (self, clashes_result) {
+            (ClashingExports::AlwaysPrefix(strategy), _) => Ok(MergeRenamer::for_all(strategy)),
+            (_, ClashesResult::None) => Ok(MergeRenamer::for_no_clashes_present()),
+            (ClashingExports::Rename(strategy), ClashesResult::Some(clashes)) => {
+                Ok(MergeRenamer::new(clashes, strategy))
+            }
+            (ClashingExports::Signal, ClashesResult::Some(clashes)) => {
+                Err(Error::ExportNameClash(clashes))
+            }
         }
     }
 }
 
+/// A remaining export of the merged module, see [`Resolver::remaining_exports`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RemainingExport {
+    module: String,
+    name: String,
+    kind: ExportKind,
+}
+
 pub(crate) struct MergeRenamer {
     pub(crate) clashes_map: ClashesMap,
     pub(crate) rename_strategy: RenameStrategy,
 
-    /// During the growing phase, set of renamed names.
-    rename_encountered: Set<String>,
-
-    /// Allow constructor to express that clashes should be present.
-    #[cfg(debug_assertions)]
-    clashes_should_be_present: bool,
-    /// Growing set validating clashes will not occur if the flag
-    /// [`MergeRenamer::no_clashes_present`] is true.
-    #[cfg(debug_assertions)]
-    encountered: Set<String>,
+    /// Rename all exports, regardless of clashes.
+    rename_all: bool,
+
+    /// The final name of each renamed export, by `(module, name)`, see
+    /// [`MergeRenamer::settle`].
+    names: ExportRenames,
+
+    /// Exports kept by the user, as `(module, name)`.
+    kept: Set<(String, String)>,
+    /// The final names of renamed imports, see [`MergeRenamer::import_name`].
+    import_renames: ImportRenames,
+    /// Warnings for kept exports that were renamed.
+    pub(crate) kept_renamed: Vec<MergeWarning>,
+    /// The final export names, by the module defining them.
+    pub(crate) export_groups: Map<String, Vec<String>>,
 }
 
 impl MergeRenamer {
@@ -525,29 +1185,124 @@ impl MergeRenamer {
         Self {
             clashes_map,
             rename_strategy,
-            rename_encountered: Set::default(),
-
-            #[cfg(debug_assertions)]
-            clashes_should_be_present: true,
-            #[cfg(debug_assertions)]
-            encountered: Set::default(),
+            rename_all: false,
+            names: ExportRenames::default(),
+            kept: Set::default(),
+            import_renames: ImportRenames::default(),
+            kept_renamed: Vec::default(),
+            export_groups: Map::default(),
         }
     }
 
     pub(crate) fn for_no_clashes_present() -> Self {
         let clashes_map = ClashesMap::new();
         let rename_strategy = DEFAULT_RENAMER; // ... unused anyway 🙈
+        Self::new(clashes_map, rename_strategy)
+    }
 
-        Self {
-            clashes_map,
-            rename_strategy,
-            rename_encountered: Set::default(),
+    pub(crate) fn for_all(rename_strategy: RenameStrategy) -> Self {
+        let mut renamer = Self::new(ClashesMap::new(), rename_strategy);
+        renamer.rename_all = true;
+        renamer
+    }
 
-            #[cfg(debug_assertions)]
-            clashes_should_be_present: false,
-            #[cfg(debug_assertions)]
-            encountered: Set::default(),
+    pub(crate) fn keeping(mut self, kept: Set<(String, String)>) -> Self {
+        self.kept = kept;
+        self
+    }
+
+    pub(crate) fn renaming_imports(mut self, import_renames: ImportRenames) -> Self {
+        self.import_renames = import_renames;
+        self
+    }
+
+    /// The name under which the function `import` remains imported.
+    pub(crate) fn import_name<'a>(&'a self, import: &'a ImportFunction<OldIdFunction>) -> &'a str {
+        let name = import.exporting_identifier().identifier();
+        let key = (
+            import.importing_module().clone(),
+            import.exporting_module().identifier().to_string(),
+            name.to_string(),
+        );
+        self.import_renames.get(&key).map_or(name, String::as_str)
+    }
+
+    /// Settle the final name of each of the `remaining` exports, given in
+    /// input order. The first occurrence of a clashing name is the first
+    /// export by that name in input order. A numbered rename takes the next
+    /// count for which the name is not taken by another export.
+    ///
+    /// # Errors
+    /// When exports still share a name once renamed, eg. when the strategy
+    /// leaves a kind as is.
+    pub(crate) fn settle(&mut self, remaining: &[RemainingExport]) -> Result<(), Error> {
+        let mut encountered = Set::new();
+        let mut renamed = vec![];
+        let mut taken = Set::new();
+        for export in remaining {
+            let first = encountered.insert(export.name.as_str());
+            let clashes = self.clashes_map.contains_key(&export.name);
+            let rename =
+                self.rename_all || (clashes && (self.rename_strategy.first_occurrence || !first));
+            if rename {
+                renamed.push(export);
+            } else {
+                taken.insert(export.name.clone());
+            }
         }
+
+        let mut counts: Map<&str, usize> = Map::new();
+        for RemainingExport { module, name, kind } in renamed {
+            let new = match self.rename_strategy.numbered {
+                Some(numbered) => {
+                    let count = counts.entry(name).or_default();
+                    loop {
+                        *count += 1;
+                        let numbered = numbered(name, *count);
+                        if !taken.contains(&numbered) {
+                            break numbered;
+                        }
+                    }
+                }
+                None => self.rename_strategy.rename(*kind, module, name),
+            };
+            taken.insert(new.clone());
+
+            let export_id = (module.clone(), name.clone());
+            if new != *name && self.kept.contains(&export_id) {
+                self.kept_renamed.push(MergeWarning::KeptExportRenamed {
+                    module: module.clone(),
+                    original: name.clone(),
+                    new: new.clone(),
+                });
+            }
+            self.names.insert(export_id, new);
+        }
+
+        // Export names must be unique, also after renaming
+        let mut final_names: ClashesMap = Map::new();
+        for RemainingExport { module, name, kind } in remaining {
+            let final_name = self.names.get(&(module.clone(), name.clone()));
+            let export = ConcreteExport {
+                kind: *kind,
+                exporting_module: module.clone(),
+            };
+            let final_name = final_name.unwrap_or(name).clone();
+            final_names.entry(final_name).or_default().push(export);
+        }
+        final_names.retain(|_, exports| exports.len() > 1);
+        if final_names.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ExportNameClash(final_names))
+        }
+    }
+
+    /// The final name of the export `name` of `module`, once clashes are
+    /// settled.
+    pub(crate) fn export_name<'a>(&'a self, module: &IdentifierModule, name: &'a str) -> &'a str {
+        let export_id = (module.identifier().to_string(), name.to_string());
+        self.names.get(&export_id).map_or(name, String::as_str)
     }
 
     /// This method will compute the export name in the output module given the
@@ -558,52 +1313,14 @@ impl MergeRenamer {
     pub(crate) fn compute_export_name<Kind: Clone, Type, Index>(
         &mut self,
         old_export: &mut Export<Kind, Type, Index>,
-        rename_fetcher: RenameRetriever<Kind>,
     ) {
-        #[cfg(debug_assertions)]
-        {
-            let clashes_not_present = !self.clashes_should_be_present;
-            if clashes_not_present {
-                let newly_inserted = self
-                    .encountered
-                    .insert(old_export.identifier.identifier().to_string());
-                debug_assert!(newly_inserted);
-            }
-        }
-
-        let clashes = self
-            .clashes_map
-            .contains_key(old_export.identifier().identifier());
-
-        if clashes {
-            let newly_inserted = self
-                .rename_encountered
-                .insert(String::from(old_export.identifier().identifier()));
-
-            // If renaming the first is not enabled but the insertion was new:
-            if !self.rename_strategy.first_occurrence && newly_inserted {
-                // Skip the rename
-                return;
-            }
-
-            // Perform the rename
-            let renamer = rename_fetcher(&self.rename_strategy);
-            old_export.identifier = renamer(old_export.module(), old_export.identifier().clone());
-        }
-    }
-}
-
-#[cfg(debug_assertions)]
-impl Drop for MergeRenamer {
-    /// Assert that the first phase & the effective merge agree on the outcome.
-    fn drop(&mut self) {
-        let rename_did_not_happen = self.rename_encountered.is_empty();
-        let rename_did_happen = !rename_did_not_happen;
-        if self.clashes_should_be_present {
-            debug_assert!(rename_did_happen);
-        } else {
-            debug_assert!(rename_did_not_happen);
-        }
+        let name = self.export_name(old_export.module(), old_export.identifier().identifier());
+        let name = name.to_string();
+        self.export_groups
+            .entry(old_export.module().identifier().to_string())
+            .or_default()
+            .push(name.clone());
+        old_export.identifier = name.into();
     }
 }
 
@@ -644,6 +1361,15 @@ impl From<&instantiated::ExportMemory<OldIdMemory>> for ConcreteExport {
     }
 }
 
+impl From<&instantiated::ExportTag<OldIdTag>> for ConcreteExport {
+    fn from(export: &instantiated::ExportTag<OldIdTag>) -> Self {
+        Self {
+            kind: ExportKind::Tag,
+            exporting_module: export.module().identifier().to_string(),
+        }
+    }
+}
+
 impl From<&instantiated::ExportTable<OldIdTable>> for ConcreteExport {
     fn from(export: &instantiated::ExportTable<OldIdTable>) -> Self {
         Self {
@@ -668,3 +1394,101 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod local_type_tests {
+    use walrus::{AbstractHeapType, ElementKind, FunctionBuilder, HeapType, RefType};
+
+    use super::*;
+
+    fn module_with_local(ty: ValType) -> Module {
+        let mut module = Module::default();
+        let _ = module.locals.add(ty);
+        module
+    }
+
+    #[test]
+    fn supported_local_types() {
+        let module_name: IdentifierModule = "A".to_string().into();
+        let abstract_ref = ValType::Ref(RefType {
+            nullable: true,
+            heap_type: HeapType::Abstract(AbstractHeapType::Extern),
+        });
+        for ty in [ValType::I32, ValType::V128, abstract_ref] {
+            let module = module_with_local(ty);
+            assert!(Resolver::check_val_types(&module_name, &module).is_ok());
+        }
+    }
+
+    #[test]
+    fn concrete_heap_type_unsupported() {
+        let module_name: IdentifierModule = "A".to_string().into();
+        let concrete_ref = ValType::Ref(RefType {
+            nullable: true,
+            heap_type: HeapType::Concrete(0),
+        });
+        let module = module_with_local(concrete_ref);
+        let result = Resolver::check_val_types(&module_name, &module);
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedValType { module, .. }) if module == "A"
+        ));
+    }
+
+    #[test]
+    fn concrete_element_type_unsupported() {
+        let module_name: IdentifierModule = "A".to_string().into();
+        let concrete_ref = RefType {
+            nullable: true,
+            heap_type: HeapType::Concrete(0),
+        };
+        let mut module = Module::default();
+        let items = ElementItems::Expressions(concrete_ref, vec![]);
+        let _ = module.elements.add(ElementKind::Passive, items);
+        let result = Resolver::check_val_types(&module_name, &module);
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedValType { module, .. }) if module == "A"
+        ));
+    }
+
+    /// A `ref.null` of a concrete heap type is reported, whether it occurs in
+    /// a function body, in a global initialiser or in an element segment of
+    /// an abstract reference type.
+    #[test]
+    fn concrete_ref_null_unsupported() {
+        let module_name: IdentifierModule = "A".to_string().into();
+        let funcref = RefType {
+            nullable: true,
+            heap_type: HeapType::Abstract(AbstractHeapType::Func),
+        };
+        let concrete_ref = RefType {
+            nullable: true,
+            heap_type: HeapType::Concrete(0),
+        };
+
+        let mut in_body = Module::default();
+        let mut builder = FunctionBuilder::new(&mut in_body.types, &[], &[ValType::I32]);
+        builder.func_body().ref_null(concrete_ref).ref_is_null();
+        let _ = builder.finish(vec![], &mut in_body.funcs);
+
+        let mut in_global = Module::default();
+        let initialiser = ConstExpr::RefNull(concrete_ref);
+        let _ = in_global
+            .globals
+            .add_local(ValType::Ref(funcref), false, false, initialiser);
+
+        let mut in_element = Module::default();
+        let items = ElementItems::Expressions(funcref, vec![ConstExpr::RefNull(concrete_ref)]);
+        let _ = in_element.elements.add(ElementKind::Passive, items);
+
+        for module in [in_body, in_global, in_element] {
+            let result = Resolver::check_val_types(&module_name, &module);
+            assert!(matches!(
+                result,
+                Err(Error::UnsupportedValType { module, ty })
+                    if module == "A" && ty == "(ref null 0)"
+            ));
+        }
+    }
+}
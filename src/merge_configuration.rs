@@ -1,5 +1,10 @@
-use crate::merge_options::MergeOptions;
-use crate::named_module::NamedBufferModule;
+use std::borrow::Cow;
+use std::collections::{HashMap as Map, HashSet as Set};
+
+use anyhow::anyhow;
+
+use crate::error::Error;
+use crate::merge_options::{MergeOptions, ParseRecovery};
 use crate::named_module::NamedModule;
 use crate::named_module::NamedParsedModule;
 
@@ -13,25 +18,181 @@ pub struct MergeConfiguration<'a, Module> {
     //
     /// The modules that will be included in the output merged module.
     /// The order is relevant.
-    pub modules: &'a [&'a NamedModule<'a, Module>],
+    pub modules: Vec<NamedModule<'a, Module>>,
     pub options: MergeOptions,
+    /// Aliases of module names, from the name a module is configured under to
+    /// the name it is merged under. Imports from the alias resolve against the
+    /// aliased module, the options refer to the module by its alias.
+    pub rename_modules: Map<String, String>,
 }
 
-impl<'a> MergeConfiguration<'a, &'a [u8]> {
+impl<Module> MergeConfiguration<'_, Module> {
+    /// The names of all modules participating in this configuration,
+    /// in input order, under their alias in [`Self::rename_modules`].
+    ///
+    /// # Errors
+    /// When a module name is empty or when two modules share a name.
+    pub fn module_names(&self) -> Result<Vec<&str>, Error> {
+        let mut encountered = Set::new();
+        self.modules
+            .iter()
+            .map(|module| {
+                let name = self.module_alias(&module.name);
+                if name.is_empty() {
+                    return Err(Error::EmptyModuleName);
+                }
+                if !encountered.insert(name) {
+                    return Err(Error::DuplicateModuleName(name.to_string()));
+                }
+                Ok(name)
+            })
+            .collect()
+    }
+
+    /// The name under which the module configured as `name` is merged.
+    pub(crate) fn module_alias<'s>(&'s self, name: &'s str) -> &'s str {
+        self.rename_modules.get(name).map_or(name, String::as_str)
+    }
+}
+
+impl<'a, Module: AsRef<[u8]>> MergeConfiguration<'a, Module> {
     #[must_use]
     pub(crate) fn new_empty_builder(
-        modules: &'a [&'a NamedBufferModule<'a>],
+        modules: Vec<NamedModule<'a, Module>>,
         options: MergeOptions,
     ) -> Self {
-        Self { modules, options }
+        Self {
+            modules,
+            options,
+            rename_modules: Map::new(),
+        }
+    }
+
+    /// Validates that no module exports two items under the same name. The
+    /// exports are read from the bytes, as parsing rejects duplicate exports
+    /// without naming them. Malformed modules are left for parsing to report.
+    pub(crate) fn check_unique_exports(&self) -> Result<(), Error> {
+        for module in &self.modules {
+            for payload in wasmparser::Parser::new(0).parse_all(module.module.as_ref()) {
+                let Ok(wasmparser::Payload::ExportSection(exports)) = payload else {
+                    continue;
+                };
+                let mut encountered = Set::new();
+                for export in exports.into_iter().flatten() {
+                    if !encountered.insert(export.name) {
+                        return Err(Error::DuplicateExportInModule {
+                            module: module.name.to_string(),
+                            name: export.name.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A `ref.null` of a concrete heap type is not (yet) supported by the
+    /// parser, which panics rather than failing on it, hence these are reported
+    /// up front as a parse error.
+    pub(crate) fn check_ref_null_types(&self) -> Result<(), Error> {
+        for module in &self.modules {
+            for payload in wasmparser::Parser::new(0).parse_all(module.module.as_ref()) {
+                let Ok(wasmparser::Payload::CodeSectionEntry(body)) = payload else {
+                    continue;
+                };
+                let Ok(operators) = body.get_operators_reader() else {
+                    continue;
+                };
+                for operator in operators.into_iter().flatten() {
+                    let wasmparser::Operator::RefNull {
+                        hty:
+                            wasmparser::HeapType::Concrete(index) | wasmparser::HeapType::Exact(index),
+                    } = operator
+                    else {
+                        continue;
+                    };
+                    let index = index
+                        .as_module_index()
+                        .map_or(String::new(), |i| i.to_string());
+                    return Err(Error::Parse(anyhow!(
+                        "{}: unsupported heap type for (ref.null {index})",
+                        module.name
+                    )));
+                }
+            }
+        }
+        Ok(())
     }
 
     #[must_use = "Parsing can become expensive, this result must be used"]
-    pub(crate) fn try_parse(&self) -> anyhow::Result<Vec<NamedParsedModule<'a>>> {
-        self.modules
-            .iter()
-            .copied()
-            .map(TryInto::try_into)
-            .collect()
+    pub(crate) fn try_parse(&self) -> anyhow::Result<Vec<NamedParsedModule<'_>>> {
+        let mut parsed_modules = Vec::with_capacity(self.modules.len());
+        for module in &self.modules {
+            let error = match NamedParsedModule::try_from(module) {
+                Ok(mut parsed) => {
+                    parsed.name = Cow::Borrowed(self.module_alias(&module.name));
+                    parsed_modules.push(parsed);
+                    continue;
+                }
+                Err(error) => error,
+            };
+            let Some(on_parse_error) = &self.options.on_parse_error else {
+                return Err(error);
+            };
+            match on_parse_error(&module.name, &error) {
+                ParseRecovery::Abort => return Err(error),
+                ParseRecovery::Skip => {}
+                ParseRecovery::Replace(replacement) => {
+                    let name = self.module_alias(&module.name);
+                    let module = walrus::Module::from_buffer(&replacement)?;
+                    parsed_modules.push(NamedModule::new(name, module));
+                }
+            }
+        }
+        Ok(parsed_modules)
+    }
+}
+
+/// The parsed modules ordered such that modules come before the modules that
+/// import from them. Ties, as well as modules that import from each other,
+/// are ordered by input order.
+pub(crate) fn in_topological_order(
+    parsed_modules: Vec<NamedParsedModule<'_>>,
+) -> Vec<NamedParsedModule<'_>> {
+    let names: Vec<String> = parsed_modules.iter().map(|m| m.name.to_string()).collect();
+    let dependencies: Vec<Set<usize>> = parsed_modules
+        .iter()
+        .enumerate()
+        .map(|(index, parsed)| {
+            parsed
+                .module
+                .imports
+                .iter()
+                .filter_map(|import| names.iter().position(|name| *name == import.module))
+                .filter(|dependency| *dependency != index)
+                .collect()
+        })
+        .collect();
+
+    let mut remaining: Vec<Option<NamedParsedModule<'_>>> =
+        parsed_modules.into_iter().map(Some).collect();
+    let mut ordered = Vec::with_capacity(remaining.len());
+    let mut included = Set::new();
+    while ordered.len() < remaining.len() {
+        let pending = || (0..remaining.len()).filter(|index| !included.contains(index));
+        let next = pending()
+            .find(|index| dependencies[*index].is_subset(&included))
+            .or_else(|| pending().next()) // Modules import from each other
+            .expect("A module is pending");
+        included.insert(next);
+        ordered.push(next);
     }
+    ordered
+        .into_iter()
+        .map(|index| {
+            remaining[index]
+                .take()
+                .expect("Each module is ordered once")
+        })
+        .collect()
 }
@@ -1,12 +1,24 @@
-use std::collections::HashSet as Set;
+use std::collections::{HashMap as Map, HashSet as Set};
+use std::fmt::Debug;
+use std::sync::Arc;
 
+use crate::features::FeatureProfile;
+use crate::kinds::{ExportKind, IdentifierItem, IdentifierModule};
 use crate::kinds::{Function, Global, Memory, Table, Tag};
-use crate::kinds::{IdentifierItem, IdentifierModule};
+
+#[cfg(feature = "serde")]
+pub use crate::serializable_options::{
+    RenameStrategyName, SerializableClashingExports, SerializableFeatureProfile,
+    SerializableKeepExports, SerializableLinkTypeMismatch, SerializableMergeOptions,
+    SerializableModuleOrder, SerializableNamePolicy, SerializableResolvedExports,
+};
 
 #[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
 pub enum ResolvedExports {
     #[default]
     Remove,
+    /// Keep the exports that resolve an import of another input module. They
+    /// partake in clash detection, see [`NamePolicy::clashes`].
     Keep,
 }
 
@@ -23,7 +35,12 @@ pub type IdentifierGlobal = IdentifierItem<Global>;
 pub type IdentifierTag = IdentifierItem<Tag>;
 
 /// The rename strategy for exports.
+///
+/// Custom strategies are created by [`RenameStrategy::new`], or derived from
+/// a provided one by assigning its fields, eg. `strategy.functions = my_rename`.
+/// The struct is non-exhaustive, such that fields can be added later on.
 #[derive(Debug, Hash, Clone)]
+#[non_exhaustive]
 pub struct RenameStrategy {
     pub first_occurrence: bool,
     pub functions: fn(&IdentifierModule, IdentifierFunction) -> IdentifierFunction,
@@ -31,9 +48,36 @@ pub struct RenameStrategy {
     pub memories: fn(&IdentifierModule, IdentifierMemory) -> IdentifierMemory,
     pub globals: fn(&IdentifierModule, IdentifierGlobal) -> IdentifierGlobal,
     pub tags: fn(&IdentifierModule, IdentifierTag) -> IdentifierTag,
+    /// When present, the `n`-th rename (counting from 1) of each export name
+    /// is computed by this function from the name alone, rather than by the
+    /// renames of each kind. Counts whose name is taken are skipped. Eg. see
+    /// [`numeric_suffix_rename`].
+    pub numbered: Option<fn(&str, usize) -> String>,
 }
 
 impl RenameStrategy {
+    /// Rename the exports of each kind by the respective function. The first
+    /// occurrence of a clashing name is renamed as well, no renames are
+    /// numbered.
+    #[must_use]
+    pub const fn new(
+        functions: fn(&IdentifierModule, IdentifierFunction) -> IdentifierFunction,
+        tables: fn(&IdentifierModule, IdentifierTable) -> IdentifierTable,
+        memories: fn(&IdentifierModule, IdentifierMemory) -> IdentifierMemory,
+        globals: fn(&IdentifierModule, IdentifierGlobal) -> IdentifierGlobal,
+        tags: fn(&IdentifierModule, IdentifierTag) -> IdentifierTag,
+    ) -> Self {
+        Self {
+            first_occurrence: true,
+            functions,
+            tables,
+            memories,
+            globals,
+            tags,
+            numbered: None,
+        }
+    }
+
     #[must_use]
     pub fn functions(&self) -> &fn(&IdentifierModule, IdentifierFunction) -> IdentifierFunction {
         &self.functions
@@ -58,20 +102,88 @@ impl RenameStrategy {
     pub fn tags(&self) -> &fn(&IdentifierModule, IdentifierTag) -> IdentifierTag {
         &self.tags
     }
+
+    /// The name `name` of `module` takes when an export of `kind` is renamed.
+    pub(crate) fn rename(&self, kind: ExportKind, module: &str, name: &str) -> String {
+        let module: IdentifierModule = module.into();
+        let name = name.to_string();
+        match kind {
+            ExportKind::Function => String::from((self.functions)(&module, name.into())),
+            ExportKind::Table => String::from((self.tables)(&module, name.into())),
+            ExportKind::Memory => String::from((self.memories)(&module, name.into())),
+            ExportKind::Global => String::from((self.globals)(&module, name.into())),
+            ExportKind::Tag => String::from((self.tags)(&module, name.into())),
+        }
+    }
+
+    /// Rename function exports by `rename`, exports of the other kinds keep
+    /// their name (see [`identity_rename`]). Merging fails with
+    /// [`Error::ExportNameClash`] when these clash.
+    ///
+    /// [`Error::ExportNameClash`]: crate::error::Error::ExportNameClash
+    ///
+    /// Eg. `ClashingExports::AlwaysPrefix(RenameStrategy::functions_only(namespace_dot_rename))`
+    /// namespaces the functions only.
+    #[must_use]
+    pub const fn functions_only(
+        rename: fn(&IdentifierModule, IdentifierFunction) -> IdentifierFunction,
+    ) -> Self {
+        Self::new(
+            rename,
+            identity_rename,
+            identity_rename,
+            identity_rename,
+            identity_rename,
+        )
+    }
 }
 
 #[derive(Debug, Default, Hash, Clone)]
 pub enum ClashingExports {
     Rename(RenameStrategy),
+    /// Rename all exports, whether they clash or not.
+    AlwaysPrefix(RenameStrategy),
     #[default]
     Signal,
 }
 
+/// A single policy for the names of the merged module, governing imports &
+/// exports alike. See [`MergeOptions::name_policy`].
+#[derive(Debug, Hash, Clone)]
+pub struct NamePolicy {
+    /// How clashing names are settled. Exports clash when they share a name,
+    /// function imports when they share a module & name but differ in type.
+    pub clashes: ClashingExports,
+    /// Settle clashing imports by [`NamePolicy::clashes`] as well, renaming
+    /// them by the same rule as clashing exports, in the namespace of the
+    /// module that imports them or numbered in input order. Imports are only
+    /// renamed when they clash, also for [`ClashingExports::AlwaysPrefix`],
+    /// as the host provides them by name. When not set, clashing imports are
+    /// imported side by side.
+    pub settle_imports: bool,
+    /// Collapse remaining imports of the same name & type, imported by
+    /// different modules, into a single import of the merged module.
+    pub dedupe_imports: bool,
+}
+
+impl Default for NamePolicy {
+    fn default() -> Self {
+        Self {
+            clashes: ClashingExports::default(),
+            settle_imports: false,
+            dedupe_imports: true,
+        }
+    }
+}
+
 #[derive(Debug, Default, Hash, Clone)]
 pub enum LinkTypeMismatch {
     Ignore,
     #[default]
     Signal,
+    /// Signal mismatches, but allow a function import to link to an export
+    /// whose type is a subtype of the imported type.
+    Structural,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -109,6 +221,28 @@ impl KeepExports {
         &self.tags
     }
 
+    /// All kept exports as `(module, name)`, export names being unique
+    /// within a module regardless of their kind.
+    pub(crate) fn names(&self) -> Set<(String, String)> {
+        fn name_of<Kind>(export: &ExportIdentifier<IdentifierItem<Kind>>) -> (String, String) {
+            let module = export.module.identifier().to_string();
+            let name = export.name.identifier().to_string();
+            (module, name)
+        }
+
+        let functions = self.functions.iter().map(name_of);
+        let tables = self.tables.iter().map(name_of);
+        let memories = self.memories.iter().map(name_of);
+        let globals = self.globals.iter().map(name_of);
+        let tags = self.tags.iter().map(name_of);
+        functions
+            .chain(tables)
+            .chain(memories)
+            .chain(globals)
+            .chain(tags)
+            .collect()
+    }
+
     pub fn keep_function(&mut self, module: IdentifierModule, name: String) {
         let name = name.into();
         let identifier = ExportIdentifier { module, name };
@@ -134,14 +268,239 @@ impl KeepExports {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+/// The order in which modules are included in the merged module, which
+/// determines eg. the order of memories and of the start functions.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ModuleOrder {
+    /// The order in which the modules are given.
+    #[default]
+    Inclusion,
+    /// Modules precede the modules importing from them, otherwise (eg. for
+    /// modules importing from each other) the order in which they are given.
+    Topological,
+}
+
+/// How the starts of the merged modules are run, in light of one of them
+/// trapping. Traps cannot be caught from within WebAssembly, recovering from
+/// one is up to the host.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum StartTrapPolicy {
+    /// Call all starts from a single start of the merged module. A trapping
+    /// start aborts the instantiation, the starts after it are not called.
+    #[default]
+    Abort,
+    /// Export each start as [`START_EXPORT_PREFIX`] followed by its module
+    /// name rather than combining them, for the host to call each in turn
+    /// after instantiation and to carry on past a trapping start.
+    Continue,
+}
+
+/// The prefix of the exported starts under [`StartTrapPolicy::Continue`].
+pub const START_EXPORT_PREFIX: &str = "start_";
+
+/// How to recover when a participating module fails to parse.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
+pub enum ParseRecovery {
+    /// Fail the merge, reporting the parse error.
+    #[default]
+    Abort,
+    /// Leave the module out of the merge.
+    Skip,
+    /// Substitute the module by the given (eg. stub) module bytes.
+    Replace(Vec<u8>),
+}
+
+/// A parse error recovery hook, see [`MergeOptions::on_parse_error`].
+pub type OnParseError = Arc<dyn Fn(&str, &anyhow::Error) -> ParseRecovery + Send + Sync>;
+
+/// A post-processing hook, see [`MergeOptions::post_process`].
+pub type PostProcess = Arc<dyn Fn(&mut walrus::Module) + Send + Sync>;
+
+#[derive(Clone)]
 pub struct MergeOptions {
-    pub clashing_exports: ClashingExports,
+    /// How the names of imports & exports are settled, see [`NamePolicy`].
+    pub name_policy: NamePolicy,
     pub link_type_mismatch: LinkTypeMismatch,
     pub resolved_exports: ResolvedExports,
+    /// Exports to keep, even when they are resolved. Kept exports partake in
+    /// clash detection: kept exports of the same name are signalled or
+    /// renamed, see [`NamePolicy::clashes`].
     pub keep_exports: Option<KeepExports>,
+    /// Called with the module name and the parse error when a module fails
+    /// to parse. When absent, parse errors abort the merge.
+    pub on_parse_error: Option<OnParseError>,
+    /// Exports, as `(module, name)`, that only serve linking. They are used
+    /// to resolve imports but never included in the merged module, even
+    /// when they are kept by [`MergeOptions::keep_exports`]. Hence also the
+    /// counterpart of the latter, to keep all exports except these.
+    pub internal_exports: Set<(String, String)>,
+    /// Report all resolution errors as one [`Error::Aggregate`] rather
+    /// than failing on the first one.
+    ///
+    /// [`Error::Aggregate`]: crate::error::Error::Aggregate
+    pub collect_all_errors: bool,
+    /// Leave out the custom sections of the input modules,
+    /// as well as the name of the merged module.
+    pub strip_custom_sections: bool,
+    /// Record this library in the `producers` section of the merged module.
+    pub emit_producers: bool,
+    /// Whether the starts are combined or exported, see [`StartTrapPolicy`].
+    pub start_trap_policy: StartTrapPolicy,
+    /// Fail with [`Error::UnresolvedImports`] when the merged module
+    /// still has imports, ie. when it is not self-contained.
+    ///
+    /// [`Error::UnresolvedImports`]: crate::error::Error::UnresolvedImports
+    pub require_self_contained: bool,
+    /// Replace each `global.get` of an immutable global that is initialized
+    /// by a constant with that constant. The global itself is left in place,
+    /// when no longer read it can be removed by dead code elimination.
+    pub inline_const_globals: bool,
+    /// Fail with [`Error::OutputTooLarge`] when the merged module, once
+    /// emitted, exceeds this amount of bytes.
+    ///
+    /// [`Error::OutputTooLarge`]: crate::error::Error::OutputTooLarge
+    pub max_output_bytes: Option<usize>,
+    /// Remove all items of the merged module that are unreachable from its
+    /// exports and start function. Kept exports are never removed. Active
+    /// data segments are kept, as is the memory they initialise, since
+    /// initialisation can trap. Shaking is conservative: a function that is
+    /// referred to is kept, even when it is only called from code that never
+    /// executes, eg. in a branch on a constant or following an `unreachable`,
+    /// `br` or `return`. Eliminating such dead code is out of scope.
+    pub tree_shake: bool,
+    /// The final name of exports, by `(module, name)`. Exports that are not
+    /// listed keep their name. Renamed exports still partake in clash
+    /// detection, see [`NamePolicy::clashes`].
+    pub export_renames: Map<(String, String), String>,
+    /// Keep imports that are not referenced, eg. host imports relied upon
+    /// for their side effects. Only imports that remain unresolved are kept,
+    /// resolved imports are replaced by their source regardless.
+    /// Relevant when [`MergeOptions::tree_shake`] is set.
+    pub keep_unused_imports: bool,
+    /// Export a `funcref` table under this name, holding each exported
+    /// function ordered by export name, for hosts to dispatch indirectly.
+    /// The slot of each function is reported by [`MergeReport::function_table`].
+    ///
+    /// [`MergeReport::function_table`]: crate::MergeReport::function_table
+    pub export_function_table: Option<String>,
+    /// Add a `merge.inputs` custom section to the merged module, listing
+    /// the name and SHA-256 hash of each input module. The section is a
+    /// vector of names, encoded as WebAssembly strings, each followed by
+    /// the 32 bytes of its hash. The section survives
+    /// [`MergeOptions::strip_custom_sections`].
+    pub embed_input_hashes: bool,
+    /// Replace each exported memory `name` by the exported accessor functions
+    /// `read_name(offset) -> i32` and `write_name(offset, byte: i32)`, for
+    /// hosts that cannot access (multiple) memories directly.
+    pub generate_memory_accessors: bool,
+    /// Keep the name of each function in the name section, prefixed by its
+    /// module name as `module::name`. Names are made unique by a `#n` suffix,
+    /// eg. when a function name itself contains `::`.
+    /// Names are left out by [`MergeOptions::strip_custom_sections`].
+    pub preserve_function_names: bool,
+    /// The order in which modules are included, see [`ModuleOrder`].
+    pub order: ModuleOrder,
+    /// Called with the merged module once all content is copied, before it
+    /// is emitted. Eg. to run optimisation passes over the merged module.
+    pub post_process: Option<PostProcess>,
+    /// The features the input modules and the merged module may make use
+    /// of, eg. to target MVP-only runtimes. See [`crate::MergeConfiguration::check_target`]
+    /// to merely check against a set of features.
+    pub feature_profile: FeatureProfile,
+    /// Rewrite calls in tail position to `return_call`, so that recursion
+    /// across the merged modules no longer grows the stack. The merged module
+    /// then requires the tail call proposal.
+    pub tailcall_optimize: bool,
+    /// The memory export, as `(module, name)`, to export as
+    /// [`MAIN_MEMORY_NAME`], eg. for hosts that expect a single canonical
+    /// memory. Other memory exports by that name are prefixed by their
+    /// module, eg. `B.memory`.
+    pub main_memory: Option<(String, String)>,
+    /// Arrange the merged module as the core module of a component: its
+    /// single memory is exported as [`MAIN_MEMORY_NAME`] and the
+    /// `cabi_realloc` & `cabi_free` exports are preserved. Merging fails if
+    /// the inputs cannot follow these conventions.
+    pub component_core: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            name_policy: NamePolicy::default(),
+            link_type_mismatch: LinkTypeMismatch::default(),
+            resolved_exports: ResolvedExports::default(),
+            keep_exports: None,
+            on_parse_error: None,
+            internal_exports: Set::new(),
+            collect_all_errors: false,
+            strip_custom_sections: false,
+            emit_producers: true,
+            start_trap_policy: StartTrapPolicy::Abort,
+            require_self_contained: false,
+            inline_const_globals: false,
+            max_output_bytes: None,
+            tree_shake: false,
+            export_renames: Map::new(),
+            keep_unused_imports: false,
+            export_function_table: None,
+            embed_input_hashes: false,
+            generate_memory_accessors: false,
+            preserve_function_names: false,
+            order: ModuleOrder::Inclusion,
+            post_process: None,
+            feature_profile: FeatureProfile::All,
+            tailcall_optimize: false,
+            main_memory: None,
+            component_core: false,
+        }
+    }
+}
+
+/// Hooks are closures, only whether one is present is shown.
+struct Hook(bool);
+
+impl Debug for Hook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0 { "Some(..)" } else { "None" })
+    }
+}
+
+impl Debug for MergeOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MergeOptions")
+            .field("name_policy", &self.name_policy)
+            .field("link_type_mismatch", &self.link_type_mismatch)
+            .field("resolved_exports", &self.resolved_exports)
+            .field("keep_exports", &self.keep_exports)
+            .field("on_parse_error", &Hook(self.on_parse_error.is_some()))
+            .field("internal_exports", &self.internal_exports)
+            .field("collect_all_errors", &self.collect_all_errors)
+            .field("strip_custom_sections", &self.strip_custom_sections)
+            .field("emit_producers", &self.emit_producers)
+            .field("start_trap_policy", &self.start_trap_policy)
+            .field("require_self_contained", &self.require_self_contained)
+            .field("inline_const_globals", &self.inline_const_globals)
+            .field("max_output_bytes", &self.max_output_bytes)
+            .field("tree_shake", &self.tree_shake)
+            .field("export_renames", &self.export_renames)
+            .field("keep_unused_imports", &self.keep_unused_imports)
+            .field("export_function_table", &self.export_function_table)
+            .field("embed_input_hashes", &self.embed_input_hashes)
+            .field("generate_memory_accessors", &self.generate_memory_accessors)
+            .field("preserve_function_names", &self.preserve_function_names)
+            .field("order", &self.order)
+            .field("post_process", &Hook(self.post_process.is_some()))
+            .field("feature_profile", &self.feature_profile)
+            .field("tailcall_optimize", &self.tailcall_optimize)
+            .field("main_memory", &self.main_memory)
+            .field("component_core", &self.component_core)
+            .finish()
+    }
 }
 
+/// The name under which [`MergeOptions::main_memory`] is exported.
+pub const MAIN_MEMORY_NAME: &str = "memory";
+
 /// Default rename strategy provided by this library is to rename each duplicate
 /// items by joining the namespace with the export name with `:` inbetween.
 /// See [`default_rename`](default_rename).
@@ -152,6 +511,7 @@ pub const DEFAULT_RENAMER: RenameStrategy = RenameStrategy {
     memories: default_rename,
     globals: default_rename,
     tags: default_rename,
+    numbered: None,
 };
 
 /// Default rename strategy provided by this library is to rename duplicate
@@ -171,3 +531,73 @@ pub fn default_rename<T: Into<String> + From<String>>(m: &IdentifierModule, v: T
     let v = v.into();
     format!("{m}:{v}").into()
 }
+
+/// Rename strategy joining the namespace with the export name with `.`
+/// inbetween, following the JavaScript namespace object convention.
+/// See [`namespace_dot_rename`](namespace_dot_rename).
+pub const NAMESPACE_DOT_RENAMER: RenameStrategy = RenameStrategy {
+    first_occurrence: true,
+    functions: namespace_dot_rename,
+    tables: namespace_dot_rename,
+    memories: namespace_dot_rename,
+    globals: namespace_dot_rename,
+    tags: namespace_dot_rename,
+    numbered: None,
+};
+
+/// Rename items by joining the namespace with the export name using `.`,
+/// dots present in either are preserved verbatim.
+///
+/// Eg. merging the following with [`ClashingExports::AlwaysPrefix`]:
+/// ```text
+/// (mod "A" (export "f" x))
+/// (mod "B" (export "g" y))
+/// ```
+/// yields:
+/// ```text
+/// (mod (export "A.f" x)
+///      (export "B.g" y))
+/// ```
+pub fn namespace_dot_rename<T: Into<String> + From<String>>(m: &IdentifierModule, v: T) -> T {
+    let v = v.into();
+    format!("{m}.{v}").into()
+}
+
+/// Keep the export name as is, eg. for the kinds left alone by
+/// [`RenameStrategy::functions_only`].
+pub fn identity_rename<T>(_m: &IdentifierModule, v: T) -> T {
+    v
+}
+
+/// Rename strategy keeping the first occurrence of a clashing export name,
+/// suffixing the subsequent occurrences by their count.
+/// See [`numeric_suffix_rename`](numeric_suffix_rename).
+pub const NUMERIC_SUFFIX_RENAMER: RenameStrategy = RenameStrategy {
+    first_occurrence: false,
+    functions: default_rename,
+    tables: default_rename,
+    memories: default_rename,
+    globals: default_rename,
+    tags: default_rename,
+    numbered: Some(numeric_suffix_rename),
+};
+
+/// Rename the `n`-th renamed occurrence of an export name by suffixing it
+/// with `_n`. A count whose name is taken by another export is skipped.
+///
+/// Eg. merging the following with [`NUMERIC_SUFFIX_RENAMER`]:
+/// ```text
+/// (mod "A" (export "f" x))
+/// (mod "B" (export "f" y))
+/// (mod "C" (export "f" z))
+/// ```
+/// yields:
+/// ```text
+/// (mod (export "f" x)
+///      (export "f_1" y)
+///      (export "f_2" z))
+/// ```
+#[must_use]
+pub fn numeric_suffix_rename(name: &str, n: usize) -> String {
+    format!("{name}_{n}")
+}
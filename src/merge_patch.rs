@@ -0,0 +1,141 @@
+use walrus::{FunctionKind, GlobalKind, ImportKind, Module, TagKind};
+
+use crate::kinds::{ExportKind, export_kind, import_kind};
+use crate::named_module::NamedParsedModule;
+use crate::{IdMapping, NamedModule};
+
+/// What merging additional modules into a base module adds over the base.
+///
+/// Added items are identified by their input module and their index in that
+/// module, as numbered by walrus (ie. imports first), the same keys as an
+/// [`IdMapping`]. Items that do not make it into the merged module, eg. by
+/// tree shaking, are left out.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct MergePatch {
+    /// Functions defined by the other modules, as `(module, index)`.
+    pub added_functions: Vec<(String, usize)>,
+    /// Globals defined by the other modules, as `(module, index)`.
+    pub added_globals: Vec<(String, usize)>,
+    /// Data segments of the other modules, as `(module, index)`.
+    pub added_data_segments: Vec<(String, usize)>,
+    /// Imports of the base that the other modules provide, and hence are no
+    /// longer imports of the merged module, as `(module, name, kind)`.
+    pub resolved_imports: Vec<(String, String, ExportKind)>,
+    /// Exports of the merged module that the base does not export,
+    /// as `(name, kind)`. An export of the base that is renamed due to a
+    /// clash is reported as added under its new name.
+    pub added_exports: Vec<(String, ExportKind)>,
+    /// Exports of the base that are no longer present, as `(name, kind)`.
+    /// Eg. exports that only resolve imports of the other modules.
+    pub removed_exports: Vec<(String, ExportKind)>,
+}
+
+impl MergePatch {
+    /// Whether the merge adds nothing over the base.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The patch of `merged`, with `mapping` as its [`IdMapping`], over the
+    /// `base` out of the merged `parsed_modules`.
+    pub(crate) fn between(
+        base: &NamedParsedModule<'_>,
+        parsed_modules: &[NamedParsedModule<'_>],
+        merged: &Module,
+        mapping: &IdMapping,
+    ) -> Self {
+        let mut patch = Self::default();
+        for NamedModule { name, module } in parsed_modules {
+            if name == &base.name {
+                continue;
+            }
+            let key = |index: usize| (name.to_string(), index);
+            patch.added_functions.extend(
+                module
+                    .funcs
+                    .iter_local()
+                    .map(|(id, _)| key(id.index()))
+                    .filter(|key| mapping.functions.contains_key(key)),
+            );
+            patch.added_globals.extend(
+                module
+                    .globals
+                    .iter()
+                    .filter(|global| matches!(global.kind, GlobalKind::Local(_)))
+                    .map(|global| key(global.id().index()))
+                    .filter(|key| mapping.globals.contains_key(key)),
+            );
+            patch.added_data_segments.extend(
+                module
+                    .data
+                    .iter()
+                    .map(|data| key(data.id().index()))
+                    .filter(|key| mapping.data.contains_key(key)),
+            );
+        }
+
+        patch.resolved_imports = base
+            .module
+            .imports
+            .iter()
+            .filter(|import| is_resolved(&base.name, &import.kind, merged, mapping))
+            .map(|import| {
+                (
+                    import.module.clone(),
+                    import.name.clone(),
+                    import_kind(&import.kind),
+                )
+            })
+            .collect();
+
+        let base_exports = exports_of(&base.module);
+        let merged_exports = exports_of(merged);
+        patch.added_exports = merged_exports
+            .iter()
+            .filter(|export| !base_exports.contains(export))
+            .cloned()
+            .collect();
+        patch.removed_exports = base_exports
+            .iter()
+            .filter(|export| !merged_exports.contains(export))
+            .cloned()
+            .collect();
+        patch
+    }
+}
+
+/// Whether the base's import of `kind` is a locally defined item in `merged`.
+fn is_resolved(base: &str, kind: &ImportKind, merged: &Module, mapping: &IdMapping) -> bool {
+    let key = |index: usize| (base.to_string(), index);
+    match kind {
+        ImportKind::Function(id) => mapping
+            .functions
+            .get(&key(id.index()))
+            .is_some_and(|&id| matches!(merged.funcs.get(id).kind, FunctionKind::Local(_))),
+        ImportKind::Table(id) => mapping
+            .tables
+            .get(&key(id.index()))
+            .is_some_and(|&id| merged.tables.get(id).import.is_none()),
+        ImportKind::Memory(id) => mapping
+            .memories
+            .get(&key(id.index()))
+            .is_some_and(|&id| merged.memories.get(id).import.is_none()),
+        ImportKind::Global(id) => mapping
+            .globals
+            .get(&key(id.index()))
+            .is_some_and(|&id| matches!(merged.globals.get(id).kind, GlobalKind::Local(_))),
+        ImportKind::Tag(id) => mapping
+            .tags
+            .get(&key(id.index()))
+            .is_some_and(|&id| matches!(merged.tags.get(id).kind, TagKind::Local)),
+    }
+}
+
+fn exports_of(module: &Module) -> Vec<(String, ExportKind)> {
+    module
+        .exports
+        .iter()
+        .map(|export| (export.name.clone(), export_kind(export.item)))
+        .collect()
+}
@@ -0,0 +1,243 @@
+use std::collections::{HashMap as Map, HashSet as Set};
+
+use walrus::ValType;
+use walrus::ir::{AtomicWait, Visitor, dfs_in_order};
+
+use crate::kinds::{FuncType, export_kind, import_kind};
+use crate::merge_builder::builder_instantiated::ReducedDependenciesFunction;
+use crate::merge_options::{KeepExports, MergeOptions, ResolvedExports};
+use crate::named_module::NamedParsedModule;
+use crate::resolver::Node;
+
+/// Information on a performed merge, next to the merged module itself.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct MergeReport {
+    /// Whether the merged module has no remaining imports,
+    /// ie. whether it can be instantiated without providing any imports.
+    pub is_self_contained: bool,
+    /// The signature of each remaining function import, by `(module, name)`.
+    pub function_imports: Map<(String, String), FuncType>,
+    /// The signature of each function export, by export name.
+    pub function_exports: Map<String, FuncType>,
+    /// The value type of each remaining global import, by `(module, name)`.
+    pub global_imports: Map<(String, String), ValType>,
+    /// The value type of each global export, by export name.
+    pub global_exports: Map<String, ValType>,
+    /// What each function import resolved to, by `(importing module, name)`.
+    /// See [`MergeReport::resolves_to`].
+    pub function_resolutions: Map<(String, String), ResolutionTarget>,
+    /// The slot of each exported function in the exported function table,
+    /// by export name. See [`MergeOptions::export_function_table`].
+    ///
+    /// [`MergeOptions::export_function_table`]: crate::merge_options::MergeOptions::export_function_table
+    pub function_table: Map<String, u32>,
+    /// The modules whose start function is called by the merged start
+    /// function, in the order they are called.
+    pub start_order: Vec<String>,
+    /// The exports of the input modules that are left out of the merged
+    /// module, as `(module, name, reason)`, in input order.
+    pub pruned_exports: Vec<(String, String, PruneReason)>,
+    /// The final names of the exports each input module contributes to the
+    /// merged module, by module name. Exports generated by the merge, eg.
+    /// memory accessors, belong to no module.
+    pub export_groups: Map<String, Vec<String>>,
+    /// Noteworthy outcomes of the merge that did not prevent it.
+    pub warnings: Vec<MergeWarning>,
+}
+
+/// A noteworthy outcome of a merge, see [`MergeReport::warnings`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MergeWarning {
+    /// A kept export of `module` was renamed from `original` to `new`,
+    /// eg. due to a clash. Hosts binding to `original` no longer find it.
+    KeptExportRenamed {
+        module: String,
+        original: String,
+        new: String,
+    },
+    /// The module neither imports nor exports anything, hence its content
+    /// can only be reached by its start function, if any.
+    IsolatedModule(String),
+    /// The module waits on a memory that is not shared. Although valid,
+    /// such a wait always traps. Notifying such a memory is harmless.
+    AtomicWaitOnUnshared(String),
+}
+
+/// Why an export is left out of the merged module, see
+/// [`MergeReport::pruned_exports`]. Tree shaking never prunes exports, as
+/// they are what it starts from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PruneReason {
+    /// The export resolves an import of an input module and is not kept,
+    /// see [`ResolvedExports::Remove`].
+    ///
+    /// [`ResolvedExports::Remove`]: crate::merge_options::ResolvedExports::Remove
+    ResolvedInternally,
+    /// The export is listed by [`MergeOptions::internal_exports`].
+    DenyListed,
+}
+
+/// The source a function import is reduced to by the merge.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ResolutionTarget {
+    /// A function defined by `module`, at `index` in its function index space.
+    Local { module: String, index: usize },
+    /// An import that remains in the merged module.
+    RemainingImport { module: String, name: String },
+}
+
+impl MergeReport {
+    /// What the function import `name` of `module` resolved to.
+    ///
+    /// Eg. merging the following:
+    /// ```text
+    /// (mod "A" (func $f) (export "f" $f))
+    /// (mod "B" (import "A" "f"))
+    /// ```
+    /// `resolves_to("B", "f")` is the local function `$f` of `A`.
+    #[must_use]
+    pub fn resolves_to(&self, module: &str, name: &str) -> Option<&ResolutionTarget> {
+        self.function_resolutions
+            .get(&(module.to_string(), name.to_string()))
+    }
+
+    /// Map each function import onto the source it reduced to.
+    pub(crate) fn resolutions_of(
+        functions: &ReducedDependenciesFunction,
+    ) -> Map<(String, String), ResolutionTarget> {
+        let mut resolutions = Map::new();
+        for (node, source) in &functions.reduction_map {
+            let Node::Import(import) = node else {
+                continue;
+            };
+            let target = match source {
+                Node::Local(local) => ResolutionTarget::Local {
+                    module: local.module().identifier().to_string(),
+                    index: local.index().index(),
+                },
+                Node::Import(remaining) => ResolutionTarget::RemainingImport {
+                    module: remaining.exporting_module().identifier().to_string(),
+                    name: remaining.exporting_identifier().identifier().to_string(),
+                },
+                Node::Export(_) => unreachable!("exports always reduce to a source"),
+            };
+            let key = (
+                import.importing_module().identifier().to_string(),
+                import.exporting_identifier().identifier().to_string(),
+            );
+            resolutions.insert(key, target);
+        }
+        resolutions
+    }
+
+    /// A warning for each of the `parsed` modules that waits on a memory
+    /// that is not shared.
+    pub(crate) fn unshared_waits_of(parsed: &[NamedParsedModule<'_>]) -> Vec<MergeWarning> {
+        struct UnsharedWait<'a> {
+            module: &'a walrus::Module,
+            found: bool,
+        }
+
+        impl<'instr> Visitor<'instr> for UnsharedWait<'_> {
+            fn visit_atomic_wait(&mut self, wait: &AtomicWait) {
+                self.found |= !self.module.memories.get(wait.memory).shared;
+            }
+        }
+
+        parsed
+            .iter()
+            .filter(|NamedParsedModule { module, .. }| {
+                let mut wait = UnsharedWait {
+                    module,
+                    found: false,
+                };
+                for (_, function) in module.funcs.iter_local() {
+                    dfs_in_order(&mut wait, function, function.entry_block());
+                }
+                wait.found
+            })
+            .map(|parsed| MergeWarning::AtomicWaitOnUnshared(parsed.name.to_string()))
+            .collect()
+    }
+
+    /// The exports of the `parsed` input modules that the merge under
+    /// `options` leaves out, along with the reason why.
+    pub(crate) fn pruned_exports_of(
+        parsed: &[NamedParsedModule<'_>],
+        options: &MergeOptions,
+    ) -> Vec<(String, String, PruneReason)> {
+        let imported: Set<_> = parsed
+            .iter()
+            .flat_map(|parsed| parsed.module.imports.iter())
+            .map(|import| (&import.module, &import.name, import_kind(&import.kind)))
+            .collect();
+        let kept = options
+            .keep_exports
+            .as_ref()
+            .map(KeepExports::names)
+            .unwrap_or_default();
+
+        let mut pruned = vec![];
+        for NamedParsedModule { name, module } in parsed {
+            let module_name = name.to_string();
+            for export in module.exports.iter() {
+                let export_id = (module_name.clone(), export.name.clone());
+                let reason = if options.internal_exports.contains(&export_id) {
+                    PruneReason::DenyListed
+                } else if options.resolved_exports == ResolvedExports::Remove
+                    && imported.contains(&(&module_name, &export.name, export_kind(export.item)))
+                    && !kept.contains(&export_id)
+                {
+                    PruneReason::ResolvedInternally
+                } else {
+                    continue;
+                };
+                let (module, name) = export_id;
+                pruned.push((module, name, reason));
+            }
+        }
+        pruned
+    }
+
+    /// Describe the remaining imports & exports of the merged module.
+    pub(crate) fn of(module: &walrus::Module) -> Self {
+        let mut report = Self {
+            is_self_contained: module.imports.iter().next().is_none(),
+            ..Self::default()
+        };
+
+        for import in module.imports.iter() {
+            let key = (import.module.clone(), import.name.clone());
+            match import.kind {
+                walrus::ImportKind::Function(id) => {
+                    let ty = FuncType::from_types(module.funcs.get(id).ty(), &module.types);
+                    report.function_imports.insert(key, ty);
+                }
+                walrus::ImportKind::Global(id) => {
+                    report.global_imports.insert(key, module.globals.get(id).ty);
+                }
+                walrus::ImportKind::Table(_)
+                | walrus::ImportKind::Memory(_)
+                | walrus::ImportKind::Tag(_) => {}
+            }
+        }
+
+        for export in module.exports.iter() {
+            let key = export.name.clone();
+            match export.item {
+                walrus::ExportItem::Function(id) => {
+                    let ty = FuncType::from_types(module.funcs.get(id).ty(), &module.types);
+                    report.function_exports.insert(key, ty);
+                }
+                walrus::ExportItem::Global(id) => {
+                    report.global_exports.insert(key, module.globals.get(id).ty);
+                }
+                walrus::ExportItem::Table(_)
+                | walrus::ExportItem::Memory(_)
+                | walrus::ExportItem::Tag(_) => {}
+            }
+        }
+
+        report
+    }
+}
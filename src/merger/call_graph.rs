@@ -0,0 +1,158 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use walrus::ir::Visitor;
+use walrus::{CustomSection, FunctionId, FunctionKind, IdsToIndices};
+
+use super::Merged;
+use super::dylink::{CUSTOM_SECTION_ID, write_length, write_string};
+use crate::named_module::NamedParsedModule;
+
+/// The calls between the functions of a merged module, including the calls
+/// across the merged modules. Functions are identified by their index in the
+/// merged module.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct CallGraph {
+    /// Each function of the merged module, by index.
+    pub nodes: BTreeMap<u32, CallGraphNode>,
+}
+
+/// A function of the merged module, see [`CallGraph`].
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct CallGraphNode {
+    /// The input module that defines the function. Absent for imports and
+    /// for functions added by the merge, eg. the merged start function.
+    pub module: Option<String>,
+    /// The functions called (or referred to by `ref.func`), by index.
+    pub callees: BTreeSet<u32>,
+}
+
+impl CallGraph {
+    /// Whether the function at index `caller` calls the one at `callee`.
+    #[must_use]
+    pub fn calls(&self, caller: u32, callee: u32) -> bool {
+        self.nodes
+            .get(&caller)
+            .is_some_and(|node| node.callees.contains(&callee))
+    }
+}
+
+impl Merged {
+    /// The call graph of the merged module, originating from `parsed`.
+    ///
+    /// Functions are only indexed upon emission, hence the call graph is
+    /// complete once the merged module is emitted, see [`PendingCallGraph`].
+    pub(crate) fn call_graph(&mut self, parsed: &[NamedParsedModule<'_>]) -> PendingCallGraph {
+        let module = &self.module;
+
+        let mut origins: HashMap<FunctionId, String> = HashMap::new();
+        for ((name, old_id), new_id) in &self.mapping.funcs {
+            let input = parsed
+                .iter()
+                .find(|parsed| parsed.name == name.identifier());
+            let defined = input.is_some_and(|input| {
+                matches!(
+                    input.module.funcs.get(**old_id).kind,
+                    FunctionKind::Local(_)
+                )
+            });
+            if defined {
+                origins.insert(**new_id, name.identifier().to_string());
+            }
+        }
+
+        let mut nodes = vec![];
+        for function in module.funcs.iter() {
+            let mut callees = HashSet::new();
+            if let FunctionKind::Local(local) = &function.kind {
+                let mut references = References(&mut callees);
+                walrus::ir::dfs_in_order(&mut references, local, local.entry_block());
+            }
+            let origin = origins.get(&function.id()).cloned();
+            nodes.push((function.id(), origin, callees));
+        }
+
+        let recorder = FunctionIndices {
+            functions: nodes.iter().map(|(id, _, _)| *id).collect(),
+            indices: Arc::default(),
+        };
+        let indices = Arc::clone(&recorder.indices);
+        self.module.customs.add(recorder);
+        self.records_function_indices = true;
+        PendingCallGraph { nodes, indices }
+    }
+}
+
+/// The call graph of a merged module by walrus id, awaiting the indices the
+/// functions get upon emission.
+pub(crate) struct PendingCallGraph {
+    nodes: Vec<(FunctionId, Option<String>, HashSet<FunctionId>)>,
+    indices: Arc<Mutex<HashMap<FunctionId, u32>>>,
+}
+
+impl PendingCallGraph {
+    /// The call graph, once the merged module is emitted.
+    pub(crate) fn emitted(self) -> CallGraph {
+        let indices = self.indices.lock().unwrap();
+        let nodes = self
+            .nodes
+            .into_iter()
+            .map(|(id, module, callees)| {
+                let callees = callees.iter().map(|callee| indices[callee]).collect();
+                (indices[&id], CallGraphNode { module, callees })
+            })
+            .collect();
+        CallGraph { nodes }
+    }
+}
+
+const FUNCTION_INDICES_SECTION_NAME: &str = "wasm-mergers.function-indices";
+
+/// Records the index of each of the `functions` from the [`IdsToIndices`] of
+/// the emission. Custom sections are emitted last, in the order they are
+/// added, so this empty section ends the emitted module and is stripped from
+/// it again, see [`strip_function_indices`].
+#[derive(Debug)]
+struct FunctionIndices {
+    functions: Vec<FunctionId>,
+    indices: Arc<Mutex<HashMap<FunctionId, u32>>>,
+}
+
+impl CustomSection for FunctionIndices {
+    fn name(&self) -> &str {
+        FUNCTION_INDICES_SECTION_NAME
+    }
+
+    fn data(&self, ids_to_indices: &IdsToIndices) -> Cow<'_, [u8]> {
+        let mut indices = self.indices.lock().unwrap();
+        for &function in &self.functions {
+            indices.insert(function, ids_to_indices.get_func_index(function));
+        }
+        Cow::Borrowed(&[])
+    }
+}
+
+/// Removes the trailing [`FunctionIndices`] section from the emitted `wasm`.
+pub(super) fn strip_function_indices(mut wasm: Vec<u8>) -> Vec<u8> {
+    let mut contents = vec![];
+    write_string(&mut contents, FUNCTION_INDICES_SECTION_NAME);
+    let mut section = vec![CUSTOM_SECTION_ID];
+    write_length(&mut section, contents.len());
+    section.extend(contents);
+    assert!(
+        wasm.ends_with(&section),
+        "the function indices are emitted last"
+    );
+    wasm.truncate(wasm.len() - section.len());
+    wasm
+}
+
+/// Collects the functions referred to by the visited instructions.
+struct References<'a>(&'a mut HashSet<FunctionId>);
+
+impl Visitor<'_> for References<'_> {
+    fn visit_function_id(&mut self, function: &FunctionId) {
+        self.0.insert(*function);
+    }
+}
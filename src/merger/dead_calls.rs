@@ -0,0 +1,62 @@
+use wasmparser::{Operator, Parser, Payload};
+
+/// The calls of `module` that follow an `unreachable`, `br`, `br_table`,
+/// `return` or `throw` within their block, as `(caller, callee)` function
+/// indices. The parser drops such code, these calls are hence absent from
+/// the parsed module.
+pub(crate) fn dead_calls(module: &[u8]) -> Vec<(u32, u32)> {
+    let mut calls = vec![];
+    let mut imported_functions = 0;
+    let mut caller = 0;
+    for payload in Parser::new(0).parse_all(module) {
+        match payload {
+            Ok(Payload::ImportSection(imports)) => {
+                for import in imports.into_imports().flatten() {
+                    if matches!(import.ty, wasmparser::TypeRef::Func(_)) {
+                        imported_functions += 1;
+                    }
+                }
+            }
+            Ok(Payload::CodeSectionEntry(body)) => {
+                let Ok(operators) = body.get_operators_reader() else {
+                    continue;
+                };
+                // Whether each enclosing block was entered from dead code
+                let mut entered_dead = vec![];
+                let mut dead = false;
+                for operator in operators.into_iter().flatten() {
+                    match operator {
+                        Operator::Unreachable
+                        | Operator::Return
+                        | Operator::Br { .. }
+                        | Operator::BrTable { .. }
+                        | Operator::Throw { .. }
+                        | Operator::ThrowRef
+                        | Operator::Rethrow { .. } => dead = true,
+                        Operator::Block { .. }
+                        | Operator::Loop { .. }
+                        | Operator::If { .. }
+                        | Operator::Try { .. }
+                        | Operator::TryTable { .. } => entered_dead.push(dead),
+                        Operator::Else | Operator::Catch { .. } | Operator::CatchAll => {
+                            dead = entered_dead.last().copied().unwrap_or(false);
+                        }
+                        Operator::End | Operator::Delegate { .. } => {
+                            dead = entered_dead.pop().unwrap_or(false);
+                        }
+                        Operator::Call { function_index }
+                        | Operator::ReturnCall { function_index }
+                            if dead =>
+                        {
+                            calls.push((imported_functions + caller, function_index));
+                        }
+                        _ => {}
+                    }
+                }
+                caller += 1;
+            }
+            _ => {}
+        }
+    }
+    calls
+}
@@ -0,0 +1,262 @@
+//! Support for the `dylink.0` custom section of Emscripten side modules.
+//!
+//! ref: <https://github.com/WebAssembly/tool-conventions/blob/main/DynamicLinking.md>
+
+use anyhow::{anyhow, bail};
+
+pub(crate) const DYLINK_SECTION_NAME: &str = "dylink.0";
+
+const WASM_DYLINK_MEM_INFO: u8 = 1;
+const WASM_DYLINK_NEEDED: u8 = 2;
+const WASM_DYLINK_EXPORT_INFO: u8 = 3;
+const WASM_DYLINK_IMPORT_INFO: u8 = 4;
+const WASM_DYLINK_RUNTIME_PATH: u8 = 5;
+
+const WASM_HEADER_LENGTH: usize = 8;
+pub(super) const CUSTOM_SECTION_ID: u8 = 0;
+
+/// The parsed contents of a `dylink.0` section.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Dylink {
+    pub(crate) memory_size: u32,
+    /// Memory alignment, expressed as a power of 2.
+    pub(crate) memory_alignment: u32,
+    pub(crate) table_size: u32,
+    /// Table alignment, expressed as a power of 2.
+    pub(crate) table_alignment: u32,
+    pub(crate) needed: Vec<String>,
+    pub(crate) export_info: Vec<(String, u32)>,
+    pub(crate) import_info: Vec<(String, String, u32)>,
+    pub(crate) runtime_path: Vec<String>,
+}
+
+impl Dylink {
+    pub(crate) fn parse(data: &[u8]) -> anyhow::Result<Self> {
+        let mut dylink = Self::default();
+        let mut reader = Reader { data, position: 0 };
+        while !reader.is_empty() {
+            let id = reader.byte()?;
+            let size = reader.length()?;
+            let mut subsection = Reader {
+                data: reader.take(size)?,
+                position: 0,
+            };
+            match id {
+                WASM_DYLINK_MEM_INFO => {
+                    dylink.memory_size = subsection.u32()?;
+                    dylink.memory_alignment = subsection.u32()?;
+                    dylink.table_size = subsection.u32()?;
+                    dylink.table_alignment = subsection.u32()?;
+                }
+                WASM_DYLINK_NEEDED => dylink.needed = subsection.strings()?,
+                WASM_DYLINK_EXPORT_INFO => {
+                    for _ in 0..subsection.u32()? {
+                        let name = subsection.string()?;
+                        let flags = subsection.u32()?;
+                        dylink.export_info.push((name, flags));
+                    }
+                }
+                WASM_DYLINK_IMPORT_INFO => {
+                    for _ in 0..subsection.u32()? {
+                        let module = subsection.string()?;
+                        let field = subsection.string()?;
+                        let flags = subsection.u32()?;
+                        dylink.import_info.push((module, field, flags));
+                    }
+                }
+                WASM_DYLINK_RUNTIME_PATH => dylink.runtime_path = subsection.strings()?,
+                // Readers skip the subsections they do not recognise
+                _ => {}
+            }
+        }
+        Ok(dylink)
+    }
+
+    /// Combine the requirements of two side modules into one.
+    ///
+    /// Each module's memory & table requirements are laid out after one
+    /// another, respecting the strictest alignment. The needed dynamic
+    /// libraries and symbol info are unioned.
+    pub(crate) fn combine(&mut self, other: Self) -> anyhow::Result<()> {
+        let memory_alignment = self.memory_alignment.max(other.memory_alignment);
+        let table_alignment = self.table_alignment.max(other.table_alignment);
+        self.memory_size = align_up(self.memory_size, memory_alignment)?
+            .checked_add(align_up(other.memory_size, memory_alignment)?)
+            .ok_or_else(|| anyhow!("combined {DYLINK_SECTION_NAME} memory size overflows"))?;
+        self.table_size = align_up(self.table_size, table_alignment)?
+            .checked_add(align_up(other.table_size, table_alignment)?)
+            .ok_or_else(|| anyhow!("combined {DYLINK_SECTION_NAME} table size overflows"))?;
+        self.memory_alignment = memory_alignment;
+        self.table_alignment = table_alignment;
+        union_into(&mut self.needed, other.needed);
+        union_into(&mut self.export_info, other.export_info);
+        union_into(&mut self.import_info, other.import_info);
+        union_into(&mut self.runtime_path, other.runtime_path);
+        Ok(())
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut encoded = vec![];
+
+        let mut memory_info = vec![];
+        write_u32(&mut memory_info, self.memory_size);
+        write_u32(&mut memory_info, self.memory_alignment);
+        write_u32(&mut memory_info, self.table_size);
+        write_u32(&mut memory_info, self.table_alignment);
+        write_subsection(&mut encoded, WASM_DYLINK_MEM_INFO, &memory_info);
+
+        if !self.needed.is_empty() {
+            let mut needed = vec![];
+            write_strings(&mut needed, &self.needed);
+            write_subsection(&mut encoded, WASM_DYLINK_NEEDED, &needed);
+        }
+
+        if !self.export_info.is_empty() {
+            let mut export_info = vec![];
+            write_length(&mut export_info, self.export_info.len());
+            for (name, flags) in &self.export_info {
+                write_string(&mut export_info, name);
+                write_u32(&mut export_info, *flags);
+            }
+            write_subsection(&mut encoded, WASM_DYLINK_EXPORT_INFO, &export_info);
+        }
+
+        if !self.import_info.is_empty() {
+            let mut import_info = vec![];
+            write_length(&mut import_info, self.import_info.len());
+            for (module, field, flags) in &self.import_info {
+                write_string(&mut import_info, module);
+                write_string(&mut import_info, field);
+                write_u32(&mut import_info, *flags);
+            }
+            write_subsection(&mut encoded, WASM_DYLINK_IMPORT_INFO, &import_info);
+        }
+
+        if !self.runtime_path.is_empty() {
+            let mut runtime_path = vec![];
+            write_strings(&mut runtime_path, &self.runtime_path);
+            write_subsection(&mut encoded, WASM_DYLINK_RUNTIME_PATH, &runtime_path);
+        }
+
+        encoded
+    }
+
+    /// The `dylink.0` section must be the very first section of a module,
+    /// walrus emits custom sections last, hence it is spliced in post-emission.
+    pub(crate) fn prepend_to(&self, wasm: &[u8]) -> Vec<u8> {
+        let (header, sections) = wasm.split_at(WASM_HEADER_LENGTH);
+        let mut payload = vec![];
+        write_string(&mut payload, DYLINK_SECTION_NAME);
+        payload.extend(self.encode());
+
+        let mut out = Vec::with_capacity(wasm.len() + payload.len() + 6);
+        out.extend_from_slice(header);
+        write_subsection(&mut out, CUSTOM_SECTION_ID, &payload);
+        out.extend_from_slice(sections);
+        out
+    }
+}
+
+fn align_up(size: u32, alignment: u32) -> anyhow::Result<u32> {
+    1_u32
+        .checked_shl(alignment)
+        .and_then(|alignment| size.div_ceil(alignment).checked_mul(alignment))
+        .ok_or_else(|| anyhow!("{DYLINK_SECTION_NAME} alignment 2^{alignment} overflows"))
+}
+
+fn union_into<T: PartialEq>(into: &mut Vec<T>, from: Vec<T>) {
+    for item in from {
+        if !into.contains(&item) {
+            into.push(item);
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .ok_or_else(|| anyhow!("unexpected end of {DYLINK_SECTION_NAME} section"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        let mut result: u32 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = self.byte()?;
+            result |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        bail!("malformed LEB128 in {DYLINK_SECTION_NAME} section")
+    }
+
+    fn length(&mut self) -> anyhow::Result<usize> {
+        Ok(usize::try_from(self.u32()?)?)
+    }
+
+    fn take(&mut self, length: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self.position + length;
+        let taken = self
+            .data
+            .get(self.position..end)
+            .ok_or_else(|| anyhow!("unexpected end of {DYLINK_SECTION_NAME} section"))?;
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        let length = self.length()?;
+        Ok(String::from_utf8(self.take(length)?.to_vec())?)
+    }
+
+    fn strings(&mut self) -> anyhow::Result<Vec<String>> {
+        (0..self.u32()?).map(|_| self.string()).collect()
+    }
+}
+
+fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = u8::try_from(value & 0x7f).unwrap();
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub(super) fn write_length(out: &mut Vec<u8>, length: usize) {
+    write_u32(out, u32::try_from(length).unwrap());
+}
+
+pub(super) fn write_string(out: &mut Vec<u8>, string: &str) {
+    write_length(out, string.len());
+    out.extend_from_slice(string.as_bytes());
+}
+
+fn write_strings(out: &mut Vec<u8>, strings: &[String]) {
+    write_length(out, strings.len());
+    for string in strings {
+        write_string(out, string);
+    }
+}
+
+fn write_subsection(out: &mut Vec<u8>, id: u8, payload: &[u8]) {
+    out.push(id);
+    write_length(out, payload.len());
+    out.extend_from_slice(payload);
+}
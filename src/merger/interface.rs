@@ -0,0 +1,43 @@
+use walrus::Module;
+
+use super::{Merged, Merger};
+
+impl Merged {
+    /// The interface of the merged module: its remaining imports & its
+    /// exports, with their types. Exported functions become trapping stubs,
+    /// all initialisation (start, data & element segments) is left out.
+    pub(crate) fn into_interface(self) -> Module {
+        let mut interface = self.module;
+
+        for (_, function) in interface.funcs.iter_local_mut() {
+            let mut body = function.builder_mut().func_body();
+            body.instrs_mut().clear();
+            body.unreachable();
+        }
+        interface.start = None;
+
+        for memory in interface.memories.iter_mut() {
+            memory.data_segments.clear();
+        }
+        let data: Vec<_> = interface.data.iter().map(walrus::Data::id).collect();
+        for data in data {
+            interface.data.delete(data);
+        }
+        for table in interface.tables.iter_mut() {
+            table.elem_segments.clear();
+        }
+        let elements: Vec<_> = interface.elements.iter().map(walrus::Element::id).collect();
+        for element in elements {
+            interface.elements.delete(element);
+        }
+
+        let customs: Vec<_> = interface.customs.iter().map(|(id, _)| id).collect();
+        for custom in customs {
+            let _ = interface.customs.delete(custom);
+        }
+        interface.producers.clear();
+
+        Merger::tree_shake(&mut interface, true, &[]);
+        interface
+    }
+}
@@ -1,45 +1,121 @@
 use core::convert::From;
 
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 
 use anyhow::anyhow;
+use sha2::{Digest, Sha256};
 use walrus::ConstOp;
+use walrus::ExportId;
 use walrus::IdsToIndices;
+use walrus::MemoryId;
 use walrus::Module;
+use walrus::RefType;
 use walrus::ValType;
+use walrus::ir::{ExtendedLoad, LoadKind, MemArg, StoreKind};
 use walrus::{ConstExpr, ElementItems, ExportItem, FunctionBuilder, FunctionId};
 use walrus::{DataKind, ElementKind, FunctionKind, GlobalKind, ImportKind};
 
+pub(crate) mod call_graph;
+mod dead_calls;
+mod dylink;
+mod interface;
 pub(crate) mod old_to_new_mapping;
 pub(crate) mod provenance_identifier;
-mod walrus_copy;
+mod tail_calls;
+pub(crate) mod walrus_copy;
 
+use crate::component_core;
 use crate::error::Error;
-use crate::kinds::{FuncType, IdentifierModule};
+use crate::kinds::{ExportKind, FuncType, IdentifierModule};
 use crate::merge_builder::AllResolved;
 use crate::merge_builder::MergeRenamer;
 use crate::merge_builder::builder_instantiated::ReducedDependenciesFunction;
 use crate::merge_builder::builder_instantiated::ReducedDependenciesGlobal;
-use crate::merge_options::{IdentifierFunction, RenameStrategy};
+use crate::merge_builder::builder_instantiated::ReducedDependenciesMemory;
+use crate::merge_builder::builder_instantiated::ReducedDependenciesTable;
+use crate::merge_options::{IdentifierFunction, MergeOptions};
+use crate::merge_options::{START_EXPORT_PREFIX, StartTrapPolicy};
+use crate::merge_report::MergeReport;
+use crate::merger::dylink::{DYLINK_SECTION_NAME, Dylink};
 use crate::merger::old_to_new_mapping::NewIdGlobal;
 use crate::merger::old_to_new_mapping::OldIdGlobal;
+use crate::merger::old_to_new_mapping::{NewIdMemory, OldIdMemory};
+use crate::merger::old_to_new_mapping::{NewIdTable, OldIdTable};
 use crate::named_module::NamedParsedModule;
 use crate::resolver::Local;
 use crate::resolver::instantiated::ImportDataFunction;
-use crate::resolver::instantiated::ImportGlobal;
+use crate::resolver::instantiated::LocalDataGlobal;
+use crate::resolver::instantiated::{ImportGlobal, LocalGlobal};
+use crate::resolver::instantiated::{ImportMemory, LocalMemory, MemoryLimits};
+use crate::resolver::instantiated::{ImportTable, LocalTable, TableLimits};
 use crate::resolver::{Export, Import, Node};
 
 use old_to_new_mapping::{Mapping, NewIdFunction, OldIdFunction};
 use provenance_identifier::{Identifier, New, Old};
 
+/// Name of the custom section listing the input modules by name & hash.
+const INPUT_HASHES_SECTION: &str = "merge.inputs";
+
 pub(crate) struct Merger {
     merged: Module,
     mapping: Mapping,
     names: Vec<(String, String)>,
-    starts: Vec<FunctionId>,
+    /// The names given to functions of the merged module, see [`Merger::unique_function_name`].
+    function_names: HashSet<String>,
+    /// The start function of each module, by module name, in input order.
+    starts: Vec<(String, FunctionId)>,
+    dylink: Option<Dylink>,
+    input_hashes: Vec<(String, [u8; 32])>,
+    /// The calls dropped as dead code upon parsing, as `(caller, callee)`,
+    /// see [`Merger::keep_dead_calls`].
+    dead_calls: Vec<(FunctionId, FunctionId)>,
     all_resolved: AllResolved,
 }
 
+/// The merged module, along with content that must be added after emission.
+pub(crate) struct Merged {
+    pub(crate) module: Module,
+    pub(crate) report: MergeReport,
+    pub(crate) mapping: Mapping,
+    dylink: Option<Dylink>,
+    /// Whether emission records the function indices for the call graph,
+    /// see [`Merged::call_graph`].
+    records_function_indices: bool,
+}
+
+impl Merged {
+    /// The imports that remain in the merged module, as `(module, name, kind)`.
+    pub(crate) fn unresolved_imports(&self) -> Vec<(String, String, ExportKind)> {
+        self.module
+            .imports
+            .iter()
+            .map(|import| {
+                let kind = match import.kind {
+                    ImportKind::Function(_) => ExportKind::Function,
+                    ImportKind::Table(_) => ExportKind::Table,
+                    ImportKind::Memory(_) => ExportKind::Memory,
+                    ImportKind::Global(_) => ExportKind::Global,
+                    ImportKind::Tag(_) => ExportKind::Tag,
+                };
+                (import.module.clone(), import.name.clone(), kind)
+            })
+            .collect()
+    }
+
+    pub(crate) fn emit_wasm(mut self) -> (Vec<u8>, MergeReport) {
+        let mut wasm = self.module.emit_wasm();
+        if self.records_function_indices {
+            wasm = call_graph::strip_function_indices(wasm);
+        }
+        let wasm = match self.dylink {
+            Some(dylink) => dylink.prepend_to(&wasm),
+            None => wasm,
+        };
+        (wasm, self.report)
+    }
+}
+
 trait AsOldToNewMapIndex<KindIdentifier> {
     fn to_mapping_ref(&self) -> (IdentifierModule, Identifier<Old, KindIdentifier>);
 }
@@ -93,11 +169,12 @@ use super::resolver::instantiated::{ImportFunction, LocalFunction};
 impl Merger {
     fn add_new_import_function(
         module: &mut Module,
+        mapping: &mut Mapping,
         old_import: &ImportFunction<OldIdFunction>,
+        name: &str,
     ) -> NewIdFunction {
         let module_identifier = old_import.exporting_module().identifier();
-        let name = old_import.exporting_identifier().identifier();
-        let ty = old_import.ty().add_to_module(module);
+        let ty = *mapping.interned_types.intern(module, old_import.ty());
         // The particular ID is not relevant post merge
         let (new_id, _new_id_import) = module.add_import_func(module_identifier, name, ty);
         new_id.into() // Consider it as a new function
@@ -120,6 +197,88 @@ impl Merger {
         new_id.into() // Consider it as a new id
     }
 
+    fn add_new_import_table(
+        module: &mut Module,
+        old_import: &ImportTable<OldIdTable>,
+    ) -> NewIdTable {
+        let module_identifier = old_import.exporting_module().identifier();
+        let name = old_import.exporting_identifier().identifier();
+        let TableLimits {
+            table64,
+            initial,
+            maximum,
+        } = *old_import.limits();
+        let element_ty = *old_import.ty();
+        // The particular ID is not relevant post merge
+        let (new_id, _new_id_import) = module.add_import_table(
+            module_identifier,
+            name,
+            table64,
+            initial,
+            maximum,
+            element_ty,
+        );
+        new_id.into()
+    }
+
+    fn add_new_local_table(module: &mut Module, old_local: &LocalTable<OldIdTable>) -> NewIdTable {
+        let TableLimits {
+            table64,
+            initial,
+            maximum,
+        } = *old_local.data();
+        let element_ty = *old_local.ty();
+        // The elements are attached once the module is included
+        module
+            .tables
+            .add_local(table64, initial, maximum, element_ty)
+            .into()
+    }
+
+    fn add_new_import_memory(
+        module: &mut Module,
+        old_import: &ImportMemory<OldIdMemory>,
+    ) -> NewIdMemory {
+        let module_identifier = old_import.exporting_module().identifier();
+        let name = old_import.exporting_identifier().identifier();
+        let MemoryLimits {
+            shared,
+            memory64,
+            initial,
+            maximum,
+            page_size_log2,
+        } = *old_import.limits();
+        // The particular ID is not relevant post merge
+        let (new_id, _new_id_import) = module.add_import_memory(
+            module_identifier,
+            name,
+            shared,
+            memory64,
+            initial,
+            maximum,
+            page_size_log2,
+        );
+        new_id.into()
+    }
+
+    fn add_new_local_memory(
+        module: &mut Module,
+        old_local: &LocalMemory<OldIdMemory>,
+    ) -> NewIdMemory {
+        let MemoryLimits {
+            shared,
+            memory64,
+            initial,
+            maximum,
+            page_size_log2,
+        } = *old_local.data();
+        // The data segments are attached once the module is included
+        module
+            .memories
+            .add_local(shared, memory64, initial, maximum, page_size_log2)
+            .into()
+    }
+
     fn add_new_local_function(
         module: &mut Module,
         mapping: &mut Mapping,
@@ -142,6 +301,21 @@ impl Merger {
         new_index.into()
     }
 
+    fn add_new_local_global(
+        module: &mut Module,
+        old_local: &LocalGlobal<OldIdGlobal>,
+    ) -> NewIdGlobal {
+        let ty = *old_local.ty();
+        let LocalDataGlobal { mutable, shared } = *old_local.data();
+        // The initialiser is copied over once the module is included, it
+        // might refer to globals or functions that are yet to be mapped.
+        let placeholder = ConstExpr::Extended(vec![]);
+        module
+            .globals
+            .add_local(ty, mutable, shared, placeholder)
+            .into()
+    }
+
     fn add_new_export_function(
         module: &mut Module,
         new_export_identifier: &IdentifierFunction,
@@ -154,138 +328,189 @@ impl Merger {
         let _ = export_index; // The particular ID is not relevant post merge
     }
 
+    /// The name of function `name` of `module` in the merged module, prefixed
+    /// by the module name. A suffix `#n` is added when the prefixed name is
+    /// taken, eg. when the function name itself contains the separator.
+    fn unique_function_name(&mut self, module: &str, name: &str) -> String {
+        let prefixed = format!("{module}::{name}");
+        let mut unique = prefixed.clone();
+        let mut suffix = 1;
+        while self.function_names.contains(&unique) {
+            unique = format!("{prefixed}#{suffix}");
+            suffix += 1;
+        }
+        self.function_names.insert(unique.clone());
+        unique
+    }
+
+    /// Records the SHA-256 hash of an input module, to be embedded when
+    /// [`MergeOptions::embed_input_hashes`] is set.
+    pub(crate) fn hash_input(&mut self, name: &str, module: &[u8]) {
+        let hash = Sha256::digest(module).into();
+        self.input_hashes.push((name.to_string(), hash));
+    }
+
+    /// Records the calls of `module` that the parser dropped as dead code,
+    /// such that tree shaking keeps their callees as long as the caller is
+    /// kept. `wasm` is the binary that `module` was parsed from.
+    pub(crate) fn keep_dead_calls(&mut self, module: &NamedParsedModule<'_>, wasm: &[u8]) {
+        let module_name: IdentifierModule = module.name.as_ref().into();
+        // The parser adds the functions in the order of the index space
+        let functions: Vec<FunctionId> = module.module.funcs.iter().map(|f| f.id()).collect();
+        let new_id = |index: u32| {
+            let old_id: Identifier<Old, _> = (*functions.get(index as usize)?).into();
+            let new_id = self.mapping.funcs.get(&(module_name.clone(), old_id))?;
+            Some(**new_id)
+        };
+        let dead_calls: Vec<_> = dead_calls::dead_calls(wasm)
+            .into_iter()
+            .filter_map(|(caller, callee)| Some((new_id(caller)?, new_id(callee)?)))
+            .collect();
+        self.dead_calls.extend(dead_calls);
+    }
+
     #[must_use]
     pub(crate) fn new(mut resolved: AllResolved) -> Self {
         // Create new empty Wasm module
         let mut merged = Module::default();
         let mut mapping = Mapping::default();
 
-        let _ = resolved.all_reduced.memories; // TODO: cover in this pass
-        let _ = resolved.all_reduced.tables; // TODO: cover in this pass
+        resolved.all_reduced.functions.join(
+            &mut merged,
+            &mut mapping,
+            &mut resolved.rename_map,
+            resolved.dedupe_imports,
+            &resolved.modules,
+        );
 
-        resolved
-            .all_reduced
-            .functions
-            .join(&mut merged, &mut mapping, &mut resolved.rename_map);
+        resolved.all_reduced.globals.join(
+            &mut merged,
+            &mut mapping,
+            &mut resolved.rename_map,
+            resolved.dedupe_imports,
+            &resolved.modules,
+        );
 
-        resolved
-            .all_reduced
-            .globals
-            .join(&mut merged, &mut mapping, &mut resolved.rename_map);
+        resolved.all_reduced.tables.join(
+            &mut merged,
+            &mut mapping,
+            &mut resolved.rename_map,
+            resolved.dedupe_imports,
+            &resolved.modules,
+        );
+
+        resolved.all_reduced.memories.join(
+            &mut merged,
+            &mut mapping,
+            &mut resolved.rename_map,
+            resolved.dedupe_imports,
+            &resolved.modules,
+        );
+
+        // Memories imported with a larger initial size grow their source
+        for (memory, initial) in &resolved.memory_minimums {
+            if let Some(new_memory_id) = mapping.memories.get(memory) {
+                merged.memories.get_mut(**new_memory_id).initial = *initial;
+            }
+        }
 
         Self {
             merged,
             mapping,
             names: vec![],
+            function_names: HashSet::new(),
             starts: vec![],
+            dylink: None,
+            input_hashes: vec![],
+            dead_calls: vec![],
             all_resolved: resolved,
         }
     }
 
+    /// Copy over the initialisers of the local globals of `module`.
+    ///
+    /// All globals are added upon resolution, see `MergedJoinable::join`,
+    /// only their initialisers remain to be copied over. This happens for all
+    /// modules before any code is included, such that reads of constant
+    /// globals can be inlined regardless of the module order.
+    pub(crate) fn include_global_initialisers(&mut self, module: &NamedParsedModule<'_>) {
+        let considering_module_name: IdentifierModule = module.name.as_ref().into();
+        for global in module.module.globals.iter() {
+            let old_global_id: Identifier<Old, _> = global.id().into();
+            let new_global_id = self
+                .mapping
+                .globals
+                .get(&(considering_module_name.clone(), old_global_id))
+                .copied();
+
+            // The global should be present in the new mapping
+            #[cfg(debug_assertions)]
+            debug_assert!(new_global_id.is_some());
+
+            if let (GlobalKind::Local(const_expr), Some(new_global_id)) =
+                (&global.kind, new_global_id)
+            {
+                let const_expr = const_expr.copy_for(self, considering_module_name.clone());
+                self.merged.globals.get_mut(*new_global_id).kind = GlobalKind::Local(const_expr);
+            }
+        }
+    }
+
     #[allow(clippy::too_many_lines)] // TODO: fix / remove
-    pub(crate) fn include(&mut self, module: NamedParsedModule<'_>) -> Result<(), Error> {
+    pub(crate) fn include(&mut self, module: &NamedParsedModule<'_>) -> Result<(), Error> {
         let NamedParsedModule {
             name: considering_module_name_str,
             module: considering_module,
         } = module;
+        let considering_module_name_str: &str = considering_module_name_str;
         let Module {
-            ref imports,
-            ref tables,
-            ref types,
-            ref funcs,
-            ref globals,
-            ref locals,
-            ref exports,
-            ref memories,
-            ref data,
-            ref elements,
-            ref start,
-            ref producers,
-            ref customs,
-            ref debug,
-            ref name,
-            ref tags,
+            imports,
+            tables,
+            types,
+            funcs,
+            globals,
+            locals,
+            exports,
+            memories,
+            data,
+            elements,
+            start,
+            producers,
+            customs,
+            debug,
+            name,
+            tags,
             ..
         } = considering_module;
 
         // let mut import_covered = HashSet::new();
         let considering_module_name: IdentifierModule = considering_module_name_str.into();
 
-        for ty in types.iter() {
-            self.merged.types.add(ty.params(), ty.results());
-        }
+        let _ = types; // Added upon their use, see `Mapping::type_id`
 
-        for global in globals.iter() {
-            let new_global_id = match &global.kind {
-                GlobalKind::Import(id) => {
-                    let import = imports.get(*id);
-                    let (new_global_id, new_import_id) = self.merged.add_import_global(
-                        &import.module,
-                        &import.name,
-                        global.ty,
-                        global.mutable,
-                        global.shared,
-                    );
-                    let _ = new_import_id;
-                    new_global_id
-                }
-                GlobalKind::Local(const_expr) => self.merged.globals.add_local(
-                    global.ty,
-                    global.mutable,
-                    global.shared,
-                    const_expr.copy_for(self, considering_module_name.clone()),
-                ),
-            };
-            let old_global_id: Identifier<Old, _> = global.id().into();
-            let new_global_id: Identifier<New, _> = new_global_id.into();
-            self.mapping.globals.insert(
-                (considering_module_name.clone(), old_global_id),
-                new_global_id,
-            );
-        }
+        let _ = globals; // Initialised beforehand, see `include_global_initialisers`
 
-        for memory in memories.iter() {
-            let new_memory_id = match memory.import {
-                Some(id) => {
-                    let import = imports.get(id);
-                    let (new_memory_id, new_import_id) = self.merged.add_import_memory(
-                        &import.module,
-                        &import.name,
-                        memory.shared,
-                        memory.memory64,
-                        memory.initial,
-                        memory.maximum,
-                        memory.page_size_log2,
-                    );
-                    let _ = new_import_id;
-                    new_memory_id
-                }
-                None => self.merged.memories.add_local(
-                    memory.shared,
-                    memory.memory64,
-                    memory.initial,
-                    memory.maximum,
-                    memory.page_size_log2,
-                ),
-            };
-            let old_memory_id: Identifier<Old, _> = memory.id().into();
-            let new_memory_id: Identifier<New, _> = new_memory_id.into();
-            self.mapping.memories.insert(
-                (considering_module_name.clone(), old_memory_id),
-                new_memory_id,
-            );
-        }
+        let _ = memories; // Added upon resolution, see `MergedJoinable::join`
 
         for data in data.iter() {
             let old_data_id: Identifier<Old, _> = data.id().into();
             let kind = match &data.kind {
                 DataKind::Active { memory, offset } => {
                     let old_memory_id: Identifier<Old, _> = (*memory).into();
-                    let new_memory_id: Identifier<New, _> = *self
+                    let Some(new_memory_id) = self
                         .mapping
                         .memories
                         .get(&(considering_module_name.clone(), old_memory_id))
-                        .unwrap();
+                        .copied()
+                    else {
+                        let module = considering_module_name_str.to_string();
+                        return Err(Error::OrphanedDataSegment(module));
+                    };
+                    let memory = self.merged.memories.get(*new_memory_id);
+                    if !fits_in_memory(memory, offset, data.value.len()) {
+                        let module = considering_module_name_str.to_string();
+                        return Err(Error::DataOffsetOverflow(module));
+                    }
                     let new_offset = offset.copy_for(self, considering_module_name.clone());
                     DataKind::Active {
                         memory: *new_memory_id,
@@ -294,6 +519,8 @@ impl Merger {
                 }
                 DataKind::Passive => DataKind::Passive,
             };
+            // Each segment is copied, never shared with an identical segment of
+            // another module: a `data.drop` of one module must not affect another.
             let new_data_id: Identifier<New, _> =
                 self.merged.data.add(kind, data.value.clone()).into();
             self.mapping
@@ -301,45 +528,35 @@ impl Merger {
                 .insert((considering_module_name.clone(), old_data_id), new_data_id);
         }
 
+        // All tables are added upon resolution, see `MergedJoinable::join`
         for table in tables.iter() {
             let walrus::Table {
-                table64,
-                initial,
-                maximum,
-                element_ty,
                 import,
                 elem_segments,
                 name,
                 ..
             } = table;
-            let new_table_id = match import {
-                Some(import_id) => {
-                    let import = imports.get(*import_id);
-                    let (new_table_id, new_import_id) = self.merged.add_import_table(
-                        considering_module_name_str,
-                        &import.name,
-                        *table64,
-                        *initial,
-                        *maximum,
-                        *element_ty,
-                    );
-                    let _ = new_import_id;
-                    new_table_id
-                }
-                None => self
-                    .merged
-                    .tables
-                    .add_local(*table64, *initial, *maximum, *element_ty),
-            };
-            let new_table_id: Identifier<New, _> = new_table_id.into();
+            if import.is_some() {
+                continue; // Named after the table it resolves to
+            }
             let old_table_id: Identifier<Old, _> = table.id().into();
-            self.mapping.tables.insert(
-                (considering_module_name.clone(), old_table_id),
-                new_table_id,
-            );
-            let new_table = self.merged.tables.get_mut(*new_table_id);
-            new_table.name.clone_from(name);
-            let _ = elem_segments; // Will be copied over after all elements have been set
+            let new_table_id = self
+                .mapping
+                .tables
+                .get(&(considering_module_name.clone(), old_table_id))
+                .copied();
+
+            // The table should be present in the new mapping
+            #[cfg(debug_assertions)]
+            debug_assert!(new_table_id.is_some());
+
+            if let Some(new_table_id) = new_table_id {
+                let new_table = self.merged.tables.get_mut(*new_table_id);
+                new_table.name.clone_from(name);
+            }
+            // Copied over after all elements have been set, as elements
+            // refer to tables and tables to elements
+            let _ = elem_segments;
         }
 
         for element in elements.iter() {
@@ -358,8 +575,8 @@ impl Merger {
                         })
                         .collect(),
                 ),
-                ElementItems::Expressions(refttype, const_expression) => ElementItems::Expressions(
-                    *refttype,
+                ElementItems::Expressions(ref_type, const_expression) => ElementItems::Expressions(
+                    *ref_type,
                     const_expression
                         .iter()
                         .map(|ce| ce.copy_for(self, considering_module_name.clone()))
@@ -413,23 +630,25 @@ impl Merger {
 
         for tag in tags.iter() {
             let walrus::Tag { id, ty, kind, name } = tag;
-            let old_ty_id = considering_module.types.get(*ty);
-            let new_ty_id = self
-                .merged
-                .types
-                .add(old_ty_id.params(), old_ty_id.results());
+            let old_ty_id: Identifier<Old, _> = (*ty).into();
+            let new_ty_id = self.mapping.type_id(
+                &considering_module_name,
+                considering_module,
+                &mut self.merged,
+                old_ty_id,
+            );
             let new_tag_id = match kind {
                 walrus::TagKind::Import(import_id) => {
                     let import = imports.get(*import_id);
                     let (new_table_id, new_import_id) = self.merged.add_import_tag(
                         considering_module_name_str,
                         &import.name,
-                        new_ty_id,
+                        *new_ty_id,
                     );
                     let _ = new_import_id;
                     new_table_id
                 }
-                walrus::TagKind::Local => self.merged.tags.add(new_ty_id),
+                walrus::TagKind::Local => self.merged.tags.add(*new_ty_id),
             };
 
             let tag = self.merged.tags.get_mut(new_tag_id);
@@ -450,7 +669,7 @@ impl Merger {
 
                     let import = Import {
                         exporting_module: import.module.clone().into(),
-                        importing_module: module.name.into(),
+                        importing_module: considering_module_name.clone(),
                         exporting_identifier: import.name.clone().into(),
                         imported_index: Identifier::<Old, _>::from(*before_id),
                         kind: PhantomData,
@@ -472,7 +691,7 @@ impl Merger {
                                 .imports
                                 .get_func(
                                     import.exporting_module.identifier(),
-                                    import.exporting_identifier.identifier()
+                                    self.all_resolved.rename_map.import_name(&import)
                                 )
                                 .is_ok(),
                             "Function import should exist: {import:?}",
@@ -486,38 +705,14 @@ impl Merger {
                         );
                     }
                 }
-                ImportKind::Table(id) => {
-                    let table = tables.get(*id);
-                    self.merged.add_import_table(
-                        &import.module,
-                        &import.name,
-                        table.table64,
-                        table.initial,
-                        table.maximum,
-                        table.element_ty,
-                    );
+                ImportKind::Table(_) => {
+                    // Added upon resolution, see `MergedJoinable::join`
                 }
-                ImportKind::Memory(id) => {
-                    let memory = memories.get(*id);
-                    self.merged.add_import_memory(
-                        &import.module,
-                        &import.name,
-                        memory.shared,
-                        memory.memory64,
-                        memory.initial,
-                        memory.maximum,
-                        memory.page_size_log2,
-                    );
+                ImportKind::Memory(_) => {
+                    // Added upon resolution, see `MergedJoinable::join`
                 }
-                ImportKind::Global(id) => {
-                    let global = globals.get(*id);
-                    self.merged.add_import_global(
-                        &import.module,
-                        &import.name,
-                        global.ty,
-                        global.mutable,
-                        global.shared,
-                    );
+                ImportKind::Global(_) => {
+                    // Added upon resolution, see `MergedJoinable::join`
                 }
                 ImportKind::Tag(id) => {
                     let tag = tags.get(*id);
@@ -540,15 +735,21 @@ impl Merger {
                         .get(&(considering_module_name.clone(), old_function_index))
                         .unwrap();
 
+                    if let Some(name) = &function.name {
+                        let name = self.unique_function_name(considering_module_name_str, name);
+                        self.merged.funcs.get_mut(*new_function_index).name = Some(name);
+                    }
+
                     let mut visitor = walrus_copy::WasmFunctionCopy::new(
-                        &considering_module,
+                        considering_module,
                         &mut self.merged,
                         local_function,
                         considering_module_name.clone(),
                         &mut self.mapping,
                         new_function_index,
                         old_function_index,
-                    );
+                    )
+                    .inlining_const_globals(self.all_resolved.inline_const_globals);
 
                     walrus::ir::dfs_in_order(
                         &mut visitor,
@@ -610,7 +811,10 @@ impl Merger {
 
                     let mut old_export = Export {
                         module: considering_module_name.clone(),
-                        identifier: export.name.clone().into(),
+                        identifier: self
+                            .all_resolved
+                            .final_export_name(&considering_module_name, &export.name)
+                            .into(),
                         index: old_id,
                         kind: PhantomData,
                         ty: new.element_ty,
@@ -624,7 +828,7 @@ impl Merger {
                     if remaining {
                         self.all_resolved
                             .rename_map
-                            .compute_export_name(&mut old_export, RenameStrategy::tables);
+                            .compute_export_name(&mut old_export);
                         self.merged.exports.add(
                             old_export.identifier().identifier(),
                             ExportItem::Table(*new_id),
@@ -651,7 +855,10 @@ impl Merger {
 
                     let mut old_export = Export {
                         module: considering_module_name.clone(),
-                        identifier: export.name.clone().into(),
+                        identifier: self
+                            .all_resolved
+                            .final_export_name(&considering_module_name, &export.name)
+                            .into(),
                         index: old_id,
                         kind: PhantomData,
                         ty: (),
@@ -665,7 +872,7 @@ impl Merger {
                     if remaining {
                         self.all_resolved
                             .rename_map
-                            .compute_export_name(&mut old_export, RenameStrategy::memories);
+                            .compute_export_name(&mut old_export);
                         self.merged.exports.add(
                             old_export.identifier().identifier(),
                             ExportItem::Memory(*new_id),
@@ -691,7 +898,10 @@ impl Merger {
 
                     let mut old_export = Export {
                         module: considering_module_name.clone(),
-                        identifier: export.name.clone().into(),
+                        identifier: self
+                            .all_resolved
+                            .final_export_name(&considering_module_name, &export.name)
+                            .into(),
                         index: old_id,
                         kind: PhantomData,
                         ty: new.ty,
@@ -705,7 +915,7 @@ impl Merger {
                     if remaining {
                         self.all_resolved
                             .rename_map
-                            .compute_export_name(&mut old_export, RenameStrategy::globals);
+                            .compute_export_name(&mut old_export);
                         self.merged.exports.add(
                             old_export.identifier().identifier(),
                             ExportItem::Global(*new_id),
@@ -732,7 +942,10 @@ impl Merger {
 
                     let mut old_export = Export {
                         module: considering_module_name.clone(),
-                        identifier: export.name.clone().into(),
+                        identifier: self
+                            .all_resolved
+                            .final_export_name(&considering_module_name, &export.name)
+                            .into(),
                         index: old_id,
                         kind: PhantomData,
                         ty,
@@ -748,7 +961,7 @@ impl Merger {
                     if remaining {
                         self.all_resolved
                             .rename_map
-                            .compute_export_name(&mut old_export, RenameStrategy::tags);
+                            .compute_export_name(&mut old_export);
                         self.merged.exports.add(
                             old_export.identifier().identifier(),
                             ExportItem::Tag(*new_id),
@@ -773,7 +986,18 @@ impl Merger {
                 .funcs
                 .get(&(considering_module_name, old_start_id))
                 .unwrap();
-            self.starts.push(*new_start_id);
+            // The parser validates the start to take & return nothing, such
+            // that it can be called from the merged start
+            #[cfg(debug_assertions)]
+            {
+                let ty = self
+                    .merged
+                    .types
+                    .get(self.merged.funcs.get(*new_start_id).ty());
+                debug_assert!(ty.params().is_empty() && ty.results().is_empty());
+            }
+            let module = considering_module_name_str.to_string();
+            self.starts.push((module, *new_start_id));
         }
 
         let _ = producers; // Handled when build is called
@@ -781,9 +1005,18 @@ impl Merger {
 
         for (custom_id, custom_section) in customs.iter() {
             let _ = custom_id;
-            let name = custom_section.name().into();
+            let name: String = custom_section.name().into();
             let ids_to_idcs: IdsToIndices = walrus::IdsToIndices::default();
             let data = custom_section.data(&ids_to_idcs).to_vec();
+            if name == DYLINK_SECTION_NAME {
+                // Combined into a single section, emitted upon build
+                let dylink = Dylink::parse(&data).map_err(Error::Parse)?;
+                match &mut self.dylink {
+                    Some(combined) => combined.combine(dylink).map_err(Error::Parse)?,
+                    None => self.dylink = Some(dylink),
+                }
+                continue;
+            }
             let raw_custom_section = walrus::RawCustomSection { name, data };
             self.merged.customs.add(raw_custom_section);
         }
@@ -798,32 +1031,288 @@ impl Merger {
         Ok(())
     }
 
-    pub(crate) fn build(mut self) -> Module {
-        self.merged
-            .producers
-            .add_processed_by("webassembly-mergers", env!("CARGO_PKG_VERSION"));
+    pub(crate) fn build(mut self, options: &MergeOptions) -> Result<Merged, Error> {
+        if options.emit_producers {
+            self.merged
+                .producers
+                .add_processed_by("webassembly-mergers", env!("CARGO_PKG_VERSION"));
+        }
+        if !options.preserve_function_names || options.strip_custom_sections {
+            for function in self.merged.funcs.iter_mut() {
+                function.name = None;
+            }
+        }
+        if options.strip_custom_sections {
+            let custom_ids: Vec<_> = self.merged.customs.iter().map(|(id, _)| id).collect();
+            for custom_id in custom_ids {
+                let _ = self.merged.customs.delete(custom_id);
+            }
+        }
+        if options.embed_input_hashes {
+            let mut data = vec![];
+            dylink::write_length(&mut data, self.input_hashes.len());
+            for (name, hash) in &self.input_hashes {
+                dylink::write_string(&mut data, name);
+                data.extend_from_slice(hash);
+            }
+            let name = INPUT_HASHES_SECTION.to_string();
+            self.merged
+                .customs
+                .add(walrus::RawCustomSection { name, data });
+        }
         let formatted: Vec<_> = self
             .names
             .iter()
             .map(|(module, name)| format!("{module}::{name}"))
             .collect();
 
-        if !self.starts.is_empty() {
+        // The starts are called in input order, a trapping start aborts the
+        // instantiation and hence all starts after it. There is no recovering
+        // from this within the merged module: traps cannot be caught from
+        // within WebAssembly, the exception handling proposal only catches
+        // thrown exceptions. Best-effort initialisation is left to the host,
+        // by exporting the starts rather than combining them.
+        let start_order = self
+            .starts
+            .iter()
+            .map(|(module, _)| module.clone())
+            .collect();
+        if options.start_trap_policy == StartTrapPolicy::Continue {
+            for (module, start) in &self.starts {
+                let name = format!("{START_EXPORT_PREFIX}{module}");
+                if self.merged.exports.iter().any(|export| export.name == name) {
+                    return Err(Error::ReservedExportName(name));
+                }
+                let _ = self.merged.exports.add(&name, ExportItem::Function(*start));
+            }
+        } else if !self.starts.is_empty() {
             const EMPTY_PARAMS: &[ValType] = &[];
             const EMPTY_RESULTS: &[ValType] = &[];
 
             let mut builder =
                 FunctionBuilder::new(&mut self.merged.types, EMPTY_PARAMS, EMPTY_RESULTS);
 
-            for start in self.starts {
+            for (_, start) in self.starts {
                 builder.func_body().call(start);
             }
             let merged_start = builder.finish(vec![], &mut self.merged.funcs);
+            // The merged module is built from scratch, the start of a base
+            // module is part of `self.starts` like any other input module
+            debug_assert!(self.merged.start.is_none());
             self.merged.start = Some(merged_start);
         }
 
-        self.merged.name = Some(formatted.join("-"));
-        self.merged
+        if !options.strip_custom_sections {
+            self.merged.name = Some(formatted.join("-"));
+        }
+
+        if options.generate_memory_accessors {
+            Merger::add_memory_accessors(&mut self.merged)?;
+        }
+
+        // The merged start is in place, rooting all that is reachable from the
+        // starts, also of modules without any exports.
+        if options.tree_shake {
+            let keep_imports = options.keep_unused_imports;
+            Merger::tree_shake(&mut self.merged, keep_imports, &self.dead_calls);
+        }
+
+        let function_table = match &options.export_function_table {
+            Some(table_name) => Merger::add_function_table(&mut self.merged, table_name)?,
+            None => HashMap::new(),
+        };
+
+        if options.tailcall_optimize {
+            tail_calls::optimize(&mut self.merged);
+        }
+
+        if options.component_core {
+            component_core::export_single_memory(&mut self.merged)?;
+        }
+
+        if let Some(post_process) = &options.post_process {
+            post_process(&mut self.merged);
+        }
+
+        let mut report = MergeReport::of(&self.merged);
+        report.warnings = std::mem::take(&mut self.all_resolved.rename_map.kept_renamed);
+        // Exports may since have been replaced, eg. by memory accessors
+        let mut export_groups = std::mem::take(&mut self.all_resolved.rename_map.export_groups);
+        for names in export_groups.values_mut() {
+            names.retain(|name| {
+                self.merged
+                    .exports
+                    .iter()
+                    .any(|export| &export.name == name)
+            });
+        }
+        export_groups.retain(|_, names| !names.is_empty());
+        report.export_groups = export_groups;
+        report.function_table = function_table;
+        report.start_order = start_order;
+
+        // A self-contained module is no longer a side module
+        let dylink = self.dylink.filter(|_| !report.is_self_contained);
+
+        Ok(Merged {
+            module: self.merged,
+            report,
+            mapping: self.mapping,
+            dylink,
+            records_function_indices: false,
+        })
+    }
+
+    /// Replaces each exported memory `name` by the exported functions
+    /// `read_name(offset) -> i32` and `write_name(offset, byte: i32)`,
+    /// which load and store a single byte of that memory.
+    fn add_memory_accessors(module: &mut Module) -> Result<(), Error> {
+        let memories: Vec<(ExportId, String, MemoryId)> = module
+            .exports
+            .iter()
+            .filter_map(|export| match export.item {
+                ExportItem::Memory(id) => Some((export.id(), export.name.clone(), id)),
+                _ => None,
+            })
+            .collect();
+
+        for (export_id, name, memory) in memories {
+            let (read_name, write_name) = (format!("read_{name}"), format!("write_{name}"));
+            for accessor_name in [&read_name, &write_name] {
+                if module.exports.iter().any(|e| &e.name == accessor_name) {
+                    return Err(Error::ReservedExportName(accessor_name.clone()));
+                }
+            }
+
+            let offset_ty = if module.memories.get(memory).memory64 {
+                ValType::I64
+            } else {
+                ValType::I32
+            };
+            let arg = MemArg {
+                align: 1,
+                offset: 0,
+            };
+
+            let offset = module.locals.add(offset_ty);
+            let mut read = FunctionBuilder::new(&mut module.types, &[offset_ty], &[ValType::I32]);
+            let kind = ExtendedLoad::ZeroExtend;
+            read.func_body()
+                .local_get(offset)
+                .load(memory, LoadKind::I32_8 { kind }, arg);
+            let read = read.finish(vec![offset], &mut module.funcs);
+
+            let offset = module.locals.add(offset_ty);
+            let byte = module.locals.add(ValType::I32);
+            let params = [offset_ty, ValType::I32];
+            let mut write = FunctionBuilder::new(&mut module.types, &params, &[]);
+            write.func_body().local_get(offset).local_get(byte).store(
+                memory,
+                StoreKind::I32_8 { atomic: false },
+                arg,
+            );
+            let write = write.finish(vec![offset, byte], &mut module.funcs);
+
+            module.exports.delete(export_id);
+            let _ = module.exports.add(&read_name, ExportItem::Function(read));
+            let _ = module.exports.add(&write_name, ExportItem::Function(write));
+        }
+        Ok(())
+    }
+
+    /// Removes all items that are unreachable from the roots of the module:
+    /// its exports, its start function and, when `keep_imports` is set, its
+    /// imports. The callees of `dead_calls` are reachable from their caller.
+    fn tree_shake(
+        module: &mut Module,
+        keep_imports: bool,
+        dead_calls: &[(FunctionId, FunctionId)],
+    ) {
+        let exports = module.exports.iter().count();
+
+        // Imports are no roots of reachability, root them by temporarily exporting them
+        let import_roots: Vec<ExportId> = if keep_imports {
+            let kinds: Vec<_> = module.imports.iter().map(|i| i.kind.clone()).collect();
+            kinds
+                .into_iter()
+                .enumerate()
+                .map(|(index, kind)| {
+                    let item = match kind {
+                        ImportKind::Function(id) => ExportItem::Function(id),
+                        ImportKind::Table(id) => ExportItem::Table(id),
+                        ImportKind::Memory(id) => ExportItem::Memory(id),
+                        ImportKind::Global(id) => ExportItem::Global(id),
+                        ImportKind::Tag(id) => ExportItem::Tag(id),
+                    };
+                    module.exports.add(&format!("\0import-root-{index}"), item)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        // Dead calls are no edges of reachability, restore them temporarily at
+        // the end of their caller, where these never execute
+        let mut body_lengths = HashMap::new();
+        for &(caller, callee) in dead_calls {
+            if let FunctionKind::Local(local) = &mut module.funcs.get_mut(caller).kind {
+                let entry = local.entry_block();
+                let length = local.block(entry).instrs.len();
+                let _ = body_lengths.entry(caller).or_insert(length);
+                local.builder_mut().func_body().call(callee);
+            }
+        }
+
+        walrus::passes::gc::run(module);
+
+        for (id, function) in module.funcs.iter_local_mut() {
+            if let Some(length) = body_lengths.get(&id) {
+                let entry = function.entry_block();
+                function.block_mut(entry).instrs.truncate(*length);
+            }
+        }
+        for root in import_roots {
+            module.exports.delete(root);
+        }
+        // Exports are the roots of reachability, hence all (kept) exports survive
+        debug_assert_eq!(exports, module.exports.iter().count());
+    }
+
+    /// Adds a table holding each exported function, ordered by export name,
+    /// and exports it as `table_name`. Returns the slot of each function by
+    /// its export name.
+    fn add_function_table(
+        module: &mut Module,
+        table_name: &str,
+    ) -> Result<HashMap<String, u32>, Error> {
+        if module.exports.iter().any(|e| e.name == table_name) {
+            return Err(Error::ReservedExportName(table_name.to_string()));
+        }
+
+        let mut functions: Vec<(String, FunctionId)> = module
+            .exports
+            .iter()
+            .filter_map(|export| match export.item {
+                ExportItem::Function(id) => Some((export.name.clone(), id)),
+                _ => None,
+            })
+            .collect();
+        functions.sort();
+
+        let size = u64::try_from(functions.len()).expect("function count fits in u64");
+        let table = module
+            .tables
+            .add_local(false, size, Some(size), RefType::FUNCREF);
+        let offset = ConstExpr::Value(walrus::ir::Value::I32(0));
+        let items = ElementItems::Functions(functions.iter().map(|(_, id)| *id).collect());
+        let element = module
+            .elements
+            .add(ElementKind::Active { table, offset }, items);
+        module.tables.get_mut(table).elem_segments.insert(element);
+        let _ = module.exports.add(table_name, ExportItem::Table(table));
+
+        let slots = functions.into_iter().map(|(name, _)| name).zip(0..);
+        Ok(slots.collect())
     }
 }
 
@@ -906,14 +1395,45 @@ impl CopyForMerger for ConstOp {
 /* [1]: This case is impossible since in an earlier pass clashing names had been covered. */
 
 trait MergedJoinable {
-    fn join(&self, module: &mut Module, mapping: &mut Mapping, rename_map: &mut MergeRenamer);
+    /// Add the resolved items to the merged `module`, `modules` lists the
+    /// considered modules in the order in which they are considered.
+    fn join(
+        &self,
+        module: &mut Module,
+        mapping: &mut Mapping,
+        rename_map: &mut MergeRenamer,
+        dedupe_imports: bool,
+        modules: &[IdentifierModule],
+    );
 }
 
 impl MergedJoinable for ReducedDependenciesFunction {
-    fn join(&self, module: &mut Module, mapping: &mut Mapping, rename_map: &mut MergeRenamer) {
+    fn join(
+        &self,
+        module: &mut Module,
+        mapping: &mut Mapping,
+        rename_map: &mut MergeRenamer,
+        dedupe_imports: bool,
+        _modules: &[IdentifierModule],
+    ) {
         // 1. Include all remaining imports:
+        let mut added_imports = HashMap::new();
         for old_import in &self.remaining_imports {
-            let new_import = Merger::add_new_import_function(module, old_import);
+            let name = rename_map.import_name(old_import);
+            let key = (
+                old_import.exporting_module().clone(),
+                name,
+                old_import.ty().clone(),
+            );
+            let new_import = match added_imports.get(&key) {
+                Some(new_import) if dedupe_imports => *new_import,
+                _ => {
+                    let new_import =
+                        Merger::add_new_import_function(module, mapping, old_import, name);
+                    added_imports.insert(key, new_import);
+                    new_import
+                }
+            };
             mapping
                 .funcs
                 .insert(old_import.to_mapping_ref(), new_import);
@@ -946,7 +1466,7 @@ impl MergedJoinable for ReducedDependenciesFunction {
             let reduced = mapping.funcs.get(&old_export.to_mapping_ref());
 
             let mut old_export = old_export.clone();
-            rename_map.compute_export_name(&mut old_export, RenameStrategy::functions);
+            rename_map.compute_export_name(&mut old_export);
 
             // TODO: I did this multiple times, unwrapping should be turned into an error throwing?
             // The reduced should be present in the new mapping
@@ -965,58 +1485,305 @@ impl MergedJoinable for ReducedDependenciesFunction {
 }
 
 impl MergedJoinable for ReducedDependenciesGlobal {
-    fn join(&self, module: &mut Module, mapping: &mut Mapping, rename_map: &mut MergeRenamer) {
+    fn join(
+        &self,
+        module: &mut Module,
+        mapping: &mut Mapping,
+        _rename_map: &mut MergeRenamer,
+        dedupe_imports: bool,
+        _modules: &[IdentifierModule],
+    ) {
         // 1. Include all remaining imports:
+        let mut added_imports = HashMap::new();
         for old_import in &self.remaining_imports {
-            let new_import = Merger::add_new_import_global(module, old_import);
+            let key = (
+                old_import.exporting_module().clone(),
+                old_import.exporting_identifier().clone(),
+                *old_import.ty(),
+                old_import.mutable(),
+                old_import.shared(),
+            );
+            let new_import = match added_imports.get(&key) {
+                Some(new_import) if dedupe_imports => *new_import,
+                _ => {
+                    let new_import = Merger::add_new_import_global(module, old_import);
+                    added_imports.insert(key, new_import);
+                    new_import
+                }
+            };
             mapping
                 .globals
                 .insert(old_import.to_mapping_ref(), new_import);
-            let _ = rename_map; // TODO: ... put to use
-        }
-
-        // // 2. Include all locals:
-        // self.reduction_map
-        //     .keys()
-        //     .filter_map(|node| node.as_local())
-        //     .for_each(|old_local| {
-        //         let new_local = Merger::add_new_local_global(module, mapping, old_local);
-        //         mapping
-        //             .globals
-        //             .insert(old_local.to_mapping_ref(), new_local);
-        //     });
-
-        // for (node, reduced) in &self.reduction_map {
-        //     // Find location of reduced node:
-        //     let reduced = mapping.globals.get(&reduced.to_mapping_ref()).copied();
-
-        //     // The reduced should be present in the new mapping
-        //     #[cfg(debug_assertions)]
-        //     debug_assert!(reduced.is_some());
-
-        //     // Inject pointer from old to new
-        //     if let Some(reduced) = reduced {
-        //         mapping.globals.insert(node.to_mapping_ref(), reduced);
-        //     }
-        // }
-
-        // for old_export in &self.remaining_exports {
-        //     let reduced = mapping.globals.get(&old_export.to_mapping_ref());
-
-        //     let mut old_export = old_export.clone();
-        //     rename_map.rename_if_required(&mut old_export, RenameStrategy::globals);
-
-        //     // TODO: I did this multiple times, unwrapping should be turned into an error throwing?
-        //     // The reduced should be present in the new mapping
-        //     #[cfg(debug_assertions)]
-        //     debug_assert!(reduced.is_some());
-
-        //     // Inject pointer from old to new
-        //     if let Some(reduced) = reduced {
-        //         Merger::add_new_export(module, old_export.identifier(), *reduced);
-        //     }
-        // }
+        }
+
+        // 2. Include all locals:
+        self.reduction_map
+            .keys()
+            .filter_map(|node| node.as_local())
+            .for_each(|old_local| {
+                let new_local = Merger::add_new_local_global(module, old_local);
+                mapping
+                    .globals
+                    .insert(old_local.to_mapping_ref(), new_local);
+            });
+
+        for (node, reduced) in &self.reduction_map {
+            // Find location of reduced node:
+            let reduced = mapping.globals.get(&reduced.to_mapping_ref()).copied();
+
+            // The reduced should be present in the new mapping
+            #[cfg(debug_assertions)]
+            debug_assert!(reduced.is_some());
+
+            // Inject pointer from old to new
+            if let Some(reduced) = reduced {
+                mapping.globals.insert(node.to_mapping_ref(), reduced);
+            }
+        }
+
+        // Exports are included along with their module, see `Merger::include`
+    }
+}
+
+impl MergedJoinable for ReducedDependenciesTable {
+    fn join(
+        &self,
+        module: &mut Module,
+        mapping: &mut Mapping,
+        _rename_map: &mut MergeRenamer,
+        dedupe_imports: bool,
+        _modules: &[IdentifierModule],
+    ) {
+        // 1. Include all remaining imports:
+        let mut added_imports = HashMap::new();
+        for old_import in &self.remaining_imports {
+            let key = (
+                old_import.exporting_module().clone(),
+                old_import.exporting_identifier().clone(),
+                *old_import.ty(),
+                old_import.limits().clone(),
+            );
+            let new_import = match added_imports.get(&key) {
+                Some(new_import) if dedupe_imports => *new_import,
+                _ => {
+                    let new_import = Merger::add_new_import_table(module, old_import);
+                    added_imports.insert(key, new_import);
+                    new_import
+                }
+            };
+            mapping
+                .tables
+                .insert(old_import.to_mapping_ref(), new_import);
+        }
+
+        // 2. Include all locals:
+        self.reduction_map
+            .keys()
+            .filter_map(|node| node.as_local())
+            .for_each(|old_local| {
+                let new_local = Merger::add_new_local_table(module, old_local);
+                mapping.tables.insert(old_local.to_mapping_ref(), new_local);
+            });
+
+        for (node, reduced) in &self.reduction_map {
+            // Find location of reduced node:
+            let reduced = mapping.tables.get(&reduced.to_mapping_ref()).copied();
+
+            // The reduced should be present in the new mapping
+            #[cfg(debug_assertions)]
+            debug_assert!(reduced.is_some());
+
+            // Inject pointer from old to new
+            if let Some(reduced) = reduced {
+                mapping.tables.insert(node.to_mapping_ref(), reduced);
+            }
+        }
+
+        // Exports are included along with their module, see `Merger::include`
     }
 }
 
-// TODO: implement this for Tables, Memories & Globals
+impl MergedJoinable for ReducedDependenciesMemory {
+    fn join(
+        &self,
+        module: &mut Module,
+        mapping: &mut Mapping,
+        _rename_map: &mut MergeRenamer,
+        dedupe_imports: bool,
+        modules: &[IdentifierModule],
+    ) {
+        // Memories are numbered in the order of their modules, eg. such that
+        // the memory of the module considered first remains the first memory
+        let position = |module| modules.iter().position(|m| m == module);
+
+        // 1. Include all remaining imports:
+        let mut remaining_imports: Vec<_> = self.remaining_imports.iter().collect();
+        remaining_imports.sort_by_key(|import| {
+            let index = import.imported_index().index();
+            (position(import.importing_module()), index)
+        });
+        let mut added_imports = HashMap::new();
+        for old_import in remaining_imports {
+            let key = (
+                old_import.exporting_module().clone(),
+                old_import.exporting_identifier().clone(),
+                old_import.limits().clone(),
+            );
+            let new_import = match added_imports.get(&key) {
+                Some(new_import) if dedupe_imports => *new_import,
+                _ => {
+                    let new_import = Merger::add_new_import_memory(module, old_import);
+                    added_imports.insert(key, new_import);
+                    new_import
+                }
+            };
+            mapping
+                .memories
+                .insert(old_import.to_mapping_ref(), new_import);
+        }
+
+        // 2. Include all locals:
+        let mut locals: Vec<_> = self
+            .reduction_map
+            .keys()
+            .filter_map(|node| node.as_local())
+            .collect();
+        locals.sort_by_key(|local| (position(local.module()), local.index().index()));
+        for old_local in locals {
+            let new_local = Merger::add_new_local_memory(module, old_local);
+            mapping
+                .memories
+                .insert(old_local.to_mapping_ref(), new_local);
+        }
+
+        for (node, reduced) in &self.reduction_map {
+            // Find location of reduced node:
+            let reduced = mapping.memories.get(&reduced.to_mapping_ref()).copied();
+
+            // The reduced should be present in the new mapping
+            #[cfg(debug_assertions)]
+            debug_assert!(reduced.is_some());
+
+            // Inject pointer from old to new
+            if let Some(reduced) = reduced {
+                mapping.memories.insert(node.to_mapping_ref(), reduced);
+            }
+        }
+
+        // Exports are included along with their module, see `Merger::include`
+    }
+}
+
+/// Whether a segment of `length` bytes at a constant `offset` fits in the
+/// addressable range of `memory`, bounded by its maximum when present.
+/// Offsets that are not constant (eg. `global.get`) are only known upon
+/// instantiation and are assumed to fit.
+fn fits_in_memory(memory: &walrus::Memory, offset: &ConstExpr, length: usize) -> bool {
+    let offset = match offset {
+        ConstExpr::Value(walrus::ir::Value::I32(offset)) => u64::from(offset.cast_unsigned()),
+        ConstExpr::Value(walrus::ir::Value::I64(offset)) => offset.cast_unsigned(),
+        _ => return true,
+    };
+    let page_size = 1_u128 << memory.page_size_log2.unwrap_or(16);
+    let addressable = match memory.maximum {
+        Some(maximum) => u128::from(maximum) * page_size,
+        None if memory.memory64 => 1 << 64,
+        None => 1 << 32,
+    };
+    u128::from(offset) + length as u128 <= addressable
+}
+
+#[cfg(test)]
+mod ref_null_tests {
+    use walrus::ir::{Instr, RefNull};
+    use walrus::{AbstractHeapType, HeapType};
+
+    use super::*;
+    use crate::merge_builder::Resolver;
+
+    /// A module exporting a function that tests a `ref.null` of `heap_type`,
+    /// with an element segment of the same type holding such a `ref.null`.
+    fn module_with_ref_nulls(heap_type: HeapType) -> Module {
+        let mut module = Module::default();
+        let _ = module.types.add(&[ValType::I32, ValType::I32], &[]);
+        let ref_type = RefType {
+            nullable: true,
+            heap_type,
+        };
+        let mut builder = FunctionBuilder::new(&mut module.types, &[], &[ValType::I32]);
+        builder.func_body().ref_null(ref_type).ref_is_null();
+        let null = builder.finish(vec![], &mut module.funcs);
+        let _ = module.exports.add("null", ExportItem::Function(null));
+        let items = ElementItems::Expressions(ref_type, vec![ConstExpr::RefNull(ref_type)]);
+        let _ = module.elements.add(ElementKind::Passive, items);
+        module
+    }
+
+    /// Merge the `modules` in order and emit the merged module.
+    fn merge(modules: &[NamedParsedModule<'_>]) -> Result<Vec<u8>, Error> {
+        let options = MergeOptions::default();
+        let mut resolver = Resolver::for_modules(modules);
+        for module in modules {
+            resolver.consider(module)?;
+        }
+        let mut merger = Merger::new(resolver.resolve(&options)?);
+        for module in modules {
+            merger.include_global_initialisers(module);
+        }
+        for module in modules {
+            merger.include(module)?;
+        }
+        let (wasm, _report) = merger.build(&options)?.emit_wasm();
+        Ok(wasm)
+    }
+
+    /// A `ref.null` of an abstract heap type is carried over, both in function
+    /// bodies & in constant expressions, into a merged module that validates.
+    #[test]
+    fn abstract_ref_null_round_trip() -> Result<(), Error> {
+        let func = HeapType::Abstract(AbstractHeapType::Func);
+        let module_a = module_with_ref_nulls(func);
+
+        // Another module adds its types first, shifting the type indices
+        let mut module_b = Module::default();
+        let builder = FunctionBuilder::new(&mut module_b.types, &[ValType::F64], &[]);
+        let other = builder.finish(vec![], &mut module_b.funcs);
+        let _ = module_b.exports.add("other", ExportItem::Function(other));
+
+        let wasm = merge(&[
+            NamedParsedModule::new("B", module_b),
+            NamedParsedModule::new("A", module_a),
+        ])?;
+        wasmparser::Validator::new()
+            .validate_all(&wasm)
+            .map_err(|error| Error::Parse(error.into()))?;
+
+        let merged = Module::from_buffer(&wasm).map_err(Error::Parse)?;
+        let null = merged.exports.get_func("null").map_err(Error::Parse)?;
+        let body = merged.funcs.get(null).kind.unwrap_local();
+        let ref_nulls: Vec<HeapType> = body
+            .block(body.entry_block())
+            .instrs
+            .iter()
+            .filter_map(|(instr, _)| match instr {
+                Instr::RefNull(RefNull { ty }) => Some(ty.heap_type),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ref_nulls, vec![func]);
+
+        Ok(())
+    }
+
+    /// A `ref.null` of a concrete heap type is rejected before anything is
+    /// merged, as walrus cannot emit the type index it refers to.
+    #[test]
+    fn concrete_ref_null_rejected() {
+        let module_a = module_with_ref_nulls(HeapType::Concrete(0));
+        let result = merge(&[NamedParsedModule::new("A", module_a)]);
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedValType { module, .. }) if module == "A"
+        ));
+    }
+}
@@ -1,8 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 
-use walrus::{DataId, ElementId, FunctionId, GlobalId, LocalId, MemoryId, TableId, TagId};
+use walrus::Module;
+use walrus::{DataId, ElementId, FunctionId, GlobalId, LocalId, MemoryId, TableId, TagId, TypeId};
 
-use crate::kinds::IdentifierModule;
+use crate::kinds::{FuncType, IdentifierModule};
 use crate::merger::provenance_identifier::{Identifier, New, Old};
 
 pub(crate) type OldIdTable = Identifier<Old, TableId>;
@@ -29,6 +31,9 @@ pub(crate) type NewIdLocal = Identifier<New, LocalId>;
 pub(crate) type OldIdTag = Identifier<Old, TagId>;
 pub(crate) type NewIdTag = Identifier<New, TagId>;
 
+pub(crate) type OldIdType = Identifier<Old, TypeId>;
+pub(crate) type NewIdType = Identifier<New, TypeId>;
+
 #[derive(Default, Debug, Clone)]
 pub struct Mapping {
     pub tables: HashMap<(IdentifierModule, OldIdTable), NewIdTable>,
@@ -39,4 +44,105 @@ pub struct Mapping {
     pub funcs: HashMap<(IdentifierModule, OldIdFunction), NewIdFunction>,
     pub locals: HashMap<(IdentifierModule, OldIdLocal), NewIdLocal>,
     pub tags: HashMap<(IdentifierModule, OldIdTag), NewIdTag>,
+    pub types: HashMap<(IdentifierModule, OldIdType), NewIdType>,
+    pub interned_types: TypeInterner,
+}
+
+/// The single entry point for adding function types to the merged module.
+///
+/// Both joining the resolved functions and copying function bodies add types,
+/// interning them ensures each signature is only added once.
+#[derive(Default, Debug, Clone)]
+pub struct TypeInterner {
+    interned: HashMap<FuncType, TypeId>,
+}
+
+impl TypeInterner {
+    pub(crate) fn intern(&mut self, new_module: &mut Module, ty: &FuncType) -> NewIdType {
+        let new_type = *self
+            .interned
+            .entry(ty.clone())
+            .or_insert_with(|| ty.add_to_module(new_module));
+        new_type.into()
+    }
+}
+
+impl Mapping {
+    /// Map a type onto the merged module, adding it on its first use only.
+    ///
+    /// Type uses (eg. `call_indirect`) are frequent, caching them avoids
+    /// rebuilding & hashing the same signature over and over again.
+    pub(crate) fn type_id(
+        &mut self,
+        old_module_name: &IdentifierModule,
+        old_module: &Module,
+        new_module: &mut Module,
+        old_id: OldIdType,
+    ) -> NewIdType {
+        let key = (old_module_name.clone(), old_id);
+        if let Some(new_id) = self.types.get(&key) {
+            return *new_id;
+        }
+        let ty = FuncType::from_types(*old_id, &old_module.types);
+        let new_id = self.interned_types.intern(new_module, &ty);
+        self.types.insert(key, new_id);
+        new_id
+    }
+}
+
+/// Where each item of the input modules ended up in the merged module.
+///
+/// Items are keyed by their input module and their index in that module,
+/// as they are numbered by walrus (ie. imports first). The ids refer to the
+/// merged `walrus::Module` they are returned with. Items removed from the
+/// merged module, eg. by tree shaking, are left out.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct IdMapping {
+    pub functions: HashMap<(String, usize), FunctionId>,
+    pub tables: HashMap<(String, usize), TableId>,
+    pub memories: HashMap<(String, usize), MemoryId>,
+    pub globals: HashMap<(String, usize), GlobalId>,
+    pub data: HashMap<(String, usize), DataId>,
+    pub elements: HashMap<(String, usize), ElementId>,
+    pub tags: HashMap<(String, usize), TagId>,
+}
+
+type OldToNew<Id> = HashMap<(IdentifierModule, Identifier<Old, Id>), Identifier<New, Id>>;
+
+impl Mapping {
+    /// The public form of this mapping, for the merged module `merged`.
+    pub(crate) fn to_id_mapping(&self, merged: &Module) -> IdMapping {
+        let functions = merged.funcs.iter().map(walrus::Function::id);
+        let tables = merged.tables.iter().map(walrus::Table::id);
+        let memories = merged.memories.iter().map(walrus::Memory::id);
+        let globals = merged.globals.iter().map(walrus::Global::id);
+        let data = merged.data.iter().map(walrus::Data::id);
+        let elements = merged.elements.iter().map(walrus::Element::id);
+        let tags = merged.tags.iter().map(walrus::Tag::id);
+        IdMapping {
+            functions: Self::present(&self.funcs, functions, FunctionId::index),
+            tables: Self::present(&self.tables, tables, TableId::index),
+            memories: Self::present(&self.memories, memories, MemoryId::index),
+            globals: Self::present(&self.globals, globals, GlobalId::index),
+            data: Self::present(&self.datas, data, DataId::index),
+            elements: Self::present(&self.elements, elements, ElementId::index),
+            tags: Self::present(&self.tags, tags, TagId::index),
+        }
+    }
+
+    fn present<Id: Copy + Eq + Hash>(
+        mapping: &OldToNew<Id>,
+        present: impl Iterator<Item = Id>,
+        index: fn(&Id) -> usize,
+    ) -> HashMap<(String, usize), Id> {
+        let present: HashSet<Id> = present.collect();
+        mapping
+            .iter()
+            .filter(|(_, new_id)| present.contains(&**new_id))
+            .map(|((module, old_id), new_id)| {
+                let key = (module.identifier().to_string(), index(old_id));
+                (key, **new_id)
+            })
+            .collect()
+    }
 }
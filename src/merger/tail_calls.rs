@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use walrus::ir::{Instr, ReturnCall, ReturnCallIndirect, ReturnCallRef};
+use walrus::{FunctionId, Module, TypeId};
+
+/// Rewrites each call in tail position to its `return_call` counterpart, so
+/// that (mutually) recursive functions run in constant stack space.
+///
+/// A call is in tail position when it is the last instruction of the function
+/// body, or of a `block`, `loop` or `if` that is itself in tail position, and
+/// its results are those of the calling function. Calls within a `try` are
+/// never rewritten, leaving the body would escape the handlers.
+pub(crate) fn optimize(module: &mut Module) {
+    let types: HashMap<FunctionId, TypeId> = module
+        .funcs
+        .iter()
+        .map(|function| (function.id(), function.ty()))
+        .collect();
+    let types_section = &module.types;
+    let results = |ty: TypeId| types_section.results(ty);
+
+    for (_, function) in module.funcs.iter_local_mut() {
+        let own_results = results(function.ty());
+        let mut tails = vec![function.entry_block()];
+        while let Some(seq) = tails.pop() {
+            let Some((last, _)) = function.block_mut(seq).instrs.last_mut() else {
+                continue;
+            };
+            match last {
+                Instr::Call(call) if results(types[&call.func]) == own_results => {
+                    let func = call.func;
+                    *last = Instr::ReturnCall(ReturnCall { func });
+                }
+                Instr::CallIndirect(call) if results(call.ty) == own_results => {
+                    let (ty, table) = (call.ty, call.table);
+                    *last = Instr::ReturnCallIndirect(ReturnCallIndirect { ty, table });
+                }
+                Instr::CallRef(call) if results(call.ty) == own_results => {
+                    let ty = call.ty;
+                    *last = Instr::ReturnCallRef(ReturnCallRef { ty });
+                }
+                Instr::Block(block) => tails.push(block.seq),
+                Instr::Loop(r#loop) => tails.push(r#loop.seq),
+                Instr::IfElse(if_else) => {
+                    tails.push(if_else.consequent);
+                    tails.push(if_else.alternative);
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -19,6 +19,7 @@ use walrus::ir::{
     TableGet, TableGrow, TableInit, TableSet, TableSize, TernOp, Throw, ThrowRef, Try, TryTable,
     Unop, Unreachable, V128Bitselect, Visitor,
 };
+use walrus::{HeapType, RefType, ValType};
 
 use crate::kinds::IdentifierModule;
 use crate::merger::old_to_new_mapping::Mapping;
@@ -85,6 +86,8 @@ pub(super) struct WasmFunctionCopy<'old_module, 'new_module> {
     new_function_index: NewIdFunction,
 
     sequence_stack: SequenceStack,
+
+    inline_const_globals: bool,
 }
 
 /*
@@ -145,9 +148,17 @@ impl<'old_module, 'new_module> WasmFunctionCopy<'old_module, 'new_module> {
             new_function_index,
 
             sequence_stack: SequenceStack::new(old_body_id, new_body_id),
+
+            inline_const_globals: false,
         }
     }
 
+    /// Replace reads of constant globals by their constant.
+    pub(super) fn inlining_const_globals(mut self, inline_const_globals: bool) -> Self {
+        self.inline_const_globals = inline_const_globals;
+        self
+    }
+
     fn map_id<OldId, NewId>(
         &self,
         old_id: OldId,
@@ -182,6 +193,7 @@ impl<'old_module, 'new_module> WasmFunctionCopy<'old_module, 'new_module> {
             //        report this to user of tool...
             // Could not find local, include in new module & add to set
             let old_local: Identifier<Old, _> = self.old_module.locals.get(*old_id).into();
+            debug_assert!(is_supported_val_type(old_local.ty()));
             let new_local: Identifier<New, _> = self.new_module.locals.add(old_local.ty()).into();
 
             self.mapping
@@ -192,10 +204,12 @@ impl<'old_module, 'new_module> WasmFunctionCopy<'old_module, 'new_module> {
     }
 
     fn old_to_new_type_id(&mut self, old_id: TypeId) -> TypeId {
-        let old_type = self.old_module.types.get(old_id);
-        self.new_module
-            .types
-            .add(old_type.params(), old_type.results())
+        *self.mapping.type_id(
+            &self.old_module_name,
+            self.old_module,
+            self.new_module,
+            old_id.into(),
+        )
     }
 
     fn current_sequence(&mut self) -> InstrSeqBuilder<'_> {
@@ -395,7 +409,14 @@ impl CopyOver for &GlobalGet {
         let old_global_id: Identifier<Old, _> = self.global.into();
         let new_global_id: Identifier<New, _> =
             target.map_id(old_global_id, &target.mapping.globals);
-        target.current_sequence().global_get(*new_global_id);
+        let constant = target
+            .inline_const_globals
+            .then(|| constant_of(&target.new_module.globals, *new_global_id))
+            .flatten();
+        match constant {
+            Some(value) => target.current_sequence().const_(value),
+            None => target.current_sequence().global_get(*new_global_id),
+        };
     }
 }
 
@@ -683,6 +704,8 @@ impl CopyOver for &TableFill {
 
 impl CopyOver for &RefNull {
     fn copy_over(&self, target: &mut WasmFunctionCopy<'_, '_>) {
+        // A concrete heap type would index the type section of the old module
+        debug_assert!(is_supported_val_type(ValType::Ref(self.ty)));
         target.current_sequence().ref_null(self.ty);
     }
 }
@@ -1017,3 +1040,73 @@ impl CopyOver for &ExternConvertAny {
         target.current_sequence().extern_convert_any();
     }
 }
+
+/// Whether values of type `ty` are copied over faithfully. Concrete heap
+/// types refer to the type section of their module and are not supported.
+/// The match is exhaustive on purpose, value types introduced by later walrus
+/// versions should be considered here.
+pub(crate) fn is_supported_val_type(ty: ValType) -> bool {
+    match ty {
+        ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64 | ValType::V128 => true,
+        ValType::Ref(RefType { heap_type, .. }) => matches!(heap_type, HeapType::Abstract(_)),
+    }
+}
+
+/// The value of the immutable, locally defined global `id`, if its
+/// initialiser evaluates to a constant.
+fn constant_of(globals: &walrus::ModuleGlobals, id: walrus::GlobalId) -> Option<walrus::ir::Value> {
+    use walrus::ir::Value;
+    use walrus::{ConstExpr, ConstOp, GlobalKind};
+
+    let global = globals.get(id);
+    let GlobalKind::Local(const_expr) = &global.kind else {
+        return None;
+    };
+    if global.mutable {
+        return None;
+    }
+    let const_ops = match const_expr {
+        ConstExpr::Value(value) => return Some(*value),
+        ConstExpr::Global(id) => return constant_of(globals, *id),
+        ConstExpr::RefNull(_) | ConstExpr::RefFunc(_) => return None,
+        ConstExpr::Extended(const_ops) => const_ops,
+    };
+
+    let mut stack = vec![];
+    for const_op in const_ops {
+        let value = match const_op {
+            ConstOp::I32Const(v) => Value::I32(*v),
+            ConstOp::I64Const(v) => Value::I64(*v),
+            ConstOp::F32Const(v) => Value::F32(*v),
+            ConstOp::F64Const(v) => Value::F64(*v),
+            ConstOp::V128Const(v) => Value::V128(*v),
+            ConstOp::GlobalGet(id) => constant_of(globals, *id)?,
+            ConstOp::I32Add | ConstOp::I32Sub | ConstOp::I32Mul => {
+                let (Value::I32(rhs), Value::I32(lhs)) = (stack.pop()?, stack.pop()?) else {
+                    return None;
+                };
+                Value::I32(match const_op {
+                    ConstOp::I32Add => lhs.wrapping_add(rhs),
+                    ConstOp::I32Sub => lhs.wrapping_sub(rhs),
+                    _ => lhs.wrapping_mul(rhs),
+                })
+            }
+            ConstOp::I64Add | ConstOp::I64Sub | ConstOp::I64Mul => {
+                let (Value::I64(rhs), Value::I64(lhs)) = (stack.pop()?, stack.pop()?) else {
+                    return None;
+                };
+                Value::I64(match const_op {
+                    ConstOp::I64Add => lhs.wrapping_add(rhs),
+                    ConstOp::I64Sub => lhs.wrapping_sub(rhs),
+                    _ => lhs.wrapping_mul(rhs),
+                })
+            }
+            ConstOp::RefNull(_) | ConstOp::RefFunc(_) | ConstOp::RefI31 => return None,
+        };
+        stack.push(value);
+    }
+    match stack[..] {
+        [value] => Some(value),
+        _ => None,
+    }
+}
@@ -1,15 +1,26 @@
+use std::borrow::Cow;
+
 use walrus::Module;
 
 /// A named WebAssembly module.
 /// The name will be used to resolve function name lookup.
 #[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
 pub struct NamedModule<'a, M> {
-    pub name: &'a str,
+    pub name: Cow<'a, str>,
     pub module: M,
 }
 
 impl<'a, T> NamedModule<'a, T> {
     pub fn new(name: &'a str, module: T) -> Self {
+        let name = Cow::Borrowed(name);
+        Self { name, module }
+    }
+}
+
+impl OwnedNamedModule {
+    #[must_use]
+    pub fn owned(name: String, module: Vec<u8>) -> Self {
+        let name = Cow::Owned(name);
         Self { name, module }
     }
 }
@@ -17,16 +28,19 @@ impl<'a, T> NamedModule<'a, T> {
 /// A named module that points to a byte-buffer
 pub type NamedBufferModule<'a> = NamedModule<'a, &'a [u8]>;
 
+/// A named module that owns both its name and its byte-buffer
+pub type OwnedNamedModule = NamedModule<'static, Vec<u8>>;
+
 /// A named module that points to the internal parsed module representation
 pub(crate) type NamedParsedModule<'a> = NamedModule<'a, Module>;
 
 /// Attempt to convert from buffer to internal parsed module representation
-impl<'a> TryFrom<&NamedBufferModule<'a>> for NamedParsedModule<'a> {
+impl<'a, M: AsRef<[u8]>> TryFrom<&'a NamedModule<'_, M>> for NamedParsedModule<'a> {
     type Error = anyhow::Error;
 
-    fn try_from(module: &NamedBufferModule<'a>) -> Result<Self, Self::Error> {
+    fn try_from(module: &'a NamedModule<'_, M>) -> Result<Self, Self::Error> {
         let NamedModule { name, module } = module;
-        let module = Module::from_buffer(module)?;
-        Result::Ok(NamedModule { name, module })
+        let module = Module::from_buffer(module.as_ref())?;
+        Result::Ok(NamedModule::new(name.as_ref(), module))
     }
 }
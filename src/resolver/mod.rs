@@ -4,15 +4,17 @@ use std::hash::Hash;
 use std::marker::PhantomData;
 
 use petgraph::acyclic::{Acyclic, AcyclicEdgeError};
-use petgraph::data::Build;
+use petgraph::data::{Build, Create};
 use petgraph::graph::{Graph, NodeIndex};
 use petgraph::visit::{EdgeRef, IntoNodeReferences};
 use walrus::{RefType, ValType};
 
 use crate::kinds::{CrossModuleMismatch, FuncType, IdentifierItem, IdentifierModule, Locals};
 use crate::kinds::{Function, Global, Memory, Table, Tag};
+use crate::resolver::subtyping::StructuralMatch;
 
 pub(crate) mod dependency_reduction;
+pub(crate) mod subtyping;
 
 // TODO: include provenance? Consider moving a Module::Import to this import?
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
@@ -125,10 +127,30 @@ pub(crate) mod instantiated {
     pub(crate) struct ImportDataFunction;
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-    pub(crate) struct ImportDataTable;
+    pub(crate) struct ImportDataTable {
+        pub(crate) limits: TableLimits,
+    }
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-    pub(crate) struct ImportDataMemory;
+    pub(crate) struct TableLimits {
+        pub(crate) table64: bool,
+        pub(crate) initial: u64,
+        pub(crate) maximum: Option<u64>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub(crate) struct ImportDataMemory {
+        pub(crate) limits: MemoryLimits,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub(crate) struct MemoryLimits {
+        pub(crate) shared: bool,
+        pub(crate) memory64: bool,
+        pub(crate) initial: u64,
+        pub(crate) maximum: Option<u64>,
+        pub(crate) page_size_log2: Option<u32>,
+    }
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub(crate) struct ImportDataGlobal {
@@ -141,31 +163,112 @@ pub(crate) mod instantiated {
 
     /* -- Locals -- */
     pub(crate) type LocalDataFunction = Locals;
-    pub(crate) type LocalDataTable    = ();
-    pub(crate) type LocalDataMemory   = ();
-    pub(crate) type LocalDataGlobal   = ();
+    pub(crate) type LocalDataTable    = TableLimits;
+    pub(crate) type LocalDataMemory   = MemoryLimits;
     pub(crate) type LocalDataTag      = ();
 
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub(crate) struct LocalDataGlobal {
+        pub(crate) mutable: bool,
+        pub(crate) shared: bool,
+    }
+
     /* Instantiated Imports, Locals & Exports */
 
     /* -- Imports -- */
     pub(crate) type ImportFunction<Id> = Import<KindFunction, TypeFunction, Id, ImportDataFunction>;
-    // pub(crate) type ImportTable<Id>    = Import<KindTable,    TypeTable,    Id, ImportDataTable   >;
-    // pub(crate) type ImportMemory<Id>   = Import<KindMemory,   TypeMemory,   Id, ImportDataMemory  >;
+    pub(crate) type ImportTable<Id>    = Import<KindTable,    TypeTable,    Id, ImportDataTable   >;
+    pub(crate) type ImportMemory<Id>   = Import<KindMemory,   TypeMemory,   Id, ImportDataMemory  >;
     pub(crate) type ImportGlobal<Id>   = Import<KindGlobal,   TypeGlobal,   Id, ImportDataGlobal  >;
     // pub(crate) type ImportTag<Id>      = Import<KindTag,      TypeTag,      Id, ImportDataTag     >;
 
     /* -- Locals -- */
     pub(crate) type LocalFunction<Id> = Local<KindFunction, TypeFunction, Id, LocalDataFunction>;
-    // pub(crate) type LocalTable<Id>    = Local<KindTable   , TypeTable   , Id, LocalDataTable   >;
-    // pub(crate) type LocalMemory<Id>   = Local<KindMemory  , TypeMemory  , Id, LocalDataMemory  >;
-    // pub(crate) type LocalGlobal<Id>   = Local<KindGlobal  , TypeGlobal  , Id, LocalDataGlobal  >;
+    pub(crate) type LocalTable<Id>    = Local<KindTable   , TypeTable   , Id, LocalDataTable   >;
+    pub(crate) type LocalMemory<Id>   = Local<KindMemory  , TypeMemory  , Id, LocalDataMemory  >;
+    pub(crate) type LocalGlobal<Id>   = Local<KindGlobal  , TypeGlobal  , Id, LocalDataGlobal  >;
 
     /* -- Exports -- */
     pub(crate) type ExportFunction<Id> = Export<KindFunction, TypeFunction, Id>;
     pub(crate) type ExportTable<Id>    = Export<KindTable   , TypeTable   , Id>;
     pub(crate) type ExportMemory<Id>   = Export<KindMemory  , TypeMemory  , Id>;
     pub(crate) type ExportGlobal<Id>   = Export<KindGlobal  , TypeGlobal  , Id>;
+    pub(crate) type ExportTag<Id>      = Export<KindTag     , TypeTag     , Id>;
+}
+
+impl instantiated::TableLimits {
+    pub(crate) fn of(table: &walrus::Table) -> Self {
+        Self {
+            table64: table.table64,
+            initial: table.initial,
+            maximum: table.maximum,
+        }
+    }
+}
+
+impl instantiated::TableLimits {
+    /// Whether a table with these limits can be provided to an import
+    /// requiring the `required` limits. Index types must agree.
+    /// ref: <https://webassembly.github.io/spec/core/valid/types.html#limits>
+    pub(crate) fn matches(&self, required: &Self) -> bool {
+        let initial_suffices = self.initial >= required.initial;
+        let maximum_suffices = match (self.maximum, required.maximum) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(maximum), Some(required)) => maximum <= required,
+        };
+        self.table64 == required.table64 && initial_suffices && maximum_suffices
+    }
+}
+
+impl instantiated::MemoryLimits {
+    pub(crate) fn of(memory: &walrus::Memory) -> Self {
+        Self {
+            shared: memory.shared,
+            memory64: memory.memory64,
+            initial: memory.initial,
+            maximum: memory.maximum,
+            page_size_log2: memory.page_size_log2,
+        }
+    }
+
+    /// The page size in bytes, as a power of two.
+    /// Absent a custom page size, pages count 64KiB.
+    fn page_size_log2(&self) -> u32 {
+        self.page_size_log2.unwrap_or(16)
+    }
+
+    /// Whether a memory with these limits can be provided to an import
+    /// requiring the `required` limits. Index types, sharedness & page sizes
+    /// must agree.
+    /// ref: <https://webassembly.github.io/spec/core/valid/types.html#limits>
+    pub(crate) fn matches(&self, required: &Self) -> bool {
+        if self.shared != required.shared
+            || self.memory64 != required.memory64
+            || self.page_size_log2() != required.page_size_log2()
+        {
+            return false;
+        }
+        let initial_suffices = self.initial >= required.initial;
+        let maximum_suffices = match (self.maximum, required.maximum) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(maximum), Some(required)) => maximum <= required,
+        };
+        initial_suffices && maximum_suffices
+    }
+}
+
+impl<Id> instantiated::ImportTable<Id> {
+    pub(crate) fn limits(&self) -> &instantiated::TableLimits {
+        &self.data.limits
+    }
+}
+
+impl<Id> instantiated::ImportMemory<Id> {
+    pub(crate) fn limits(&self) -> &instantiated::MemoryLimits {
+        &self.data.limits
+    }
 }
 
 impl<Id> instantiated::ImportGlobal<Id> {
@@ -192,6 +295,13 @@ impl<Kind, Type, Index, ImportData, LocalData> Node<Kind, Type, Index, ImportDat
             Node::Import(_) | Node::Export(_) => None,
         }
     }
+
+    pub fn as_export(&self) -> Option<&Export<Kind, Type, Index>> {
+        match self {
+            Node::Export(export) => Some(export),
+            Node::Import(_) | Node::Local(_) => None,
+        }
+    }
 }
 
 impl<Kind, Type, Index, ImportData, LocalData> Node<Kind, Type, Index, ImportData, LocalData> {
@@ -202,6 +312,14 @@ impl<Kind, Type, Index, ImportData, LocalData> Node<Kind, Type, Index, ImportDat
             Node::Export(export) => &export.ty,
         }
     }
+
+    fn module_(&self) -> &IdentifierModule {
+        match self {
+            Node::Import(import) => &import.importing_module,
+            Node::Local(local) => &local.module,
+            Node::Export(export) => &export.module,
+        }
+    }
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
@@ -239,6 +357,12 @@ where
         Self { exports, indices }
     }
 
+    fn with_capacity(indices: usize, exports: usize) -> Self {
+        let exports = Map::with_capacity(exports);
+        let indices = Map::with_capacity(indices);
+        Self { exports, indices }
+    }
+
     fn add_export(&mut self, node_index: NodeIndex, export_identifier: IdentifierItem<Kind>) {
         let unique_export = self
             .exports
@@ -268,10 +392,20 @@ pub(crate) struct Resolver<Kind, Type, Index, ImportData, LocalData> {
 }
 
 pub(crate) mod error {
+    use crate::error::Error;
+    use crate::kinds::{ExportKind, IdentifierModule};
     use crate::resolver::CrossModuleMismatch;
 
+    /// The modules whose imports & exports of one kind form a cycle.
     #[derive(Debug, Clone, Hash, PartialEq, Eq)]
-    pub(crate) struct Cycles;
+    pub(crate) struct Cycles(pub(crate) Vec<IdentifierModule>);
+
+    impl Cycles {
+        pub(crate) fn into_error(self, kind: ExportKind) -> Error {
+            let modules = self.0.iter().map(ToString::to_string).collect();
+            Error::ImportCycle { kind, modules }
+        }
+    }
 
     #[derive(Debug, Clone, Hash, PartialEq, Eq)]
     pub(crate) struct TypeMismatch(pub(crate) Vec<CrossModuleMismatch>);
@@ -288,12 +422,40 @@ where
     Index: Clone + Eq + Hash,
     Kind: Clone + Eq + Hash,
 {
+    #[cfg(test)]
     pub(crate) fn new() -> Self {
         let graph = Acyclic::new();
         let ref_map = Map::default();
         Self { graph, ref_map }
     }
 
+    /// A resolver with room for `modules` modules, defining or importing
+    /// `indices` items & exporting `exports` items in total. Each import &
+    /// export links to at most one node, bounding the number of edges.
+    pub(crate) fn with_capacity(modules: usize, indices: usize, exports: usize) -> Self {
+        let nodes = indices + exports;
+        let graph = Acyclic::with_capacity(nodes, nodes);
+        let ref_map = Map::with_capacity(modules);
+        Self { graph, ref_map }
+    }
+
+    /// Reserve room for `module`, defining or importing `indices` items &
+    /// exporting `exports` items, before these are added.
+    pub(crate) fn reserve_module(
+        &mut self,
+        module: &IdentifierModule,
+        indices: usize,
+        exports: usize,
+    ) {
+        self.ref_map
+            .entry(module.clone())
+            .and_modify(|references| {
+                references.indices.reserve(indices);
+                references.exports.reserve(exports);
+            })
+            .or_insert_with(|| ModuleReferences::with_capacity(indices, exports));
+    }
+
     fn get_module_ref_mut(
         &mut self,
         module: &IdentifierModule,
@@ -334,7 +496,11 @@ where
         // loop over all exports, link each to its import / local
         for (node_index, node) in self.graph.node_references() {
             match node {
-                // An import link is made to wherever the corresponding export is
+                // An import link is made to wherever the corresponding export is.
+                // The export is looked up in the module named by the import's
+                // namespace only, same-named exports elsewhere never bind.
+                // When a module imports from itself, the export is a distinct
+                // node, hence no self-loop is introduced in the acyclic graph.
                 Node::Import(import) => {
                     let import_node_index = node_index;
                     if let Some(module) = self.ref_map.get(&import.exporting_module)
@@ -376,6 +542,12 @@ where
         links
     }
 
+    /// Link each import to the export it names and each export to the item
+    /// it exports. Cycles between modules are fine, as long as each item
+    /// eventually resolves to a local or an unresolved import: since every
+    /// import & export links to at most one node, a cycle in the graph never
+    /// reaches such a definition. Hence only cycles without any definition,
+    /// that can never be resolved, are rejected.
     pub fn link_nodes(
         mut self,
     ) -> Result<Linked<Kind, Type, Index, ImportData, LocalData>, error::Cycles> {
@@ -387,12 +559,30 @@ where
                 .try_add_edge(from, to, edge.clone())
                 .map_err(|cycle_err| {
                     debug_assert!(matches!(cycle_err, AcyclicEdgeError::Cycle(_)));
-                    error::Cycles
+                    error::Cycles(self.cycle_modules(from, to))
                 })?;
         }
 
         Ok(Linked { graph: self.graph })
     }
+
+    /// The modules of the nodes on the cycle that linking `from` to `to`
+    /// would close: as each node links to at most one other node, following
+    /// the links from `to` leads back to `from`.
+    fn cycle_modules(&self, from: NodeIndex, to: NodeIndex) -> Vec<IdentifierModule> {
+        let mut modules = vec![];
+        let mut current = Some(to);
+        while let Some(node) = current {
+            modules.push(self.graph.node_weight(node).unwrap().module_().clone());
+            if node == from {
+                break;
+            }
+            current = self.graph.neighbors(node).next();
+        }
+        modules.sort();
+        modules.dedup();
+        modules
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -405,10 +595,12 @@ struct Mismatch {
     to: NodeIndex,
 }
 
-impl<Kind, Type: Eq, Index, ImportData, LocalData>
+impl<Kind: StructuralMatch<Type>, Type: Eq, Index, ImportData, LocalData>
     Linked<Kind, Type, Index, ImportData, LocalData>
 {
-    fn type_mismatches(&self) -> Vec<Mismatch> {
+    /// The import edges whose types do not match, with `structural` the
+    /// exported type may be a subtype of the imported type.
+    fn type_mismatches(&self, structural: bool) -> Vec<Mismatch> {
         let mut mismatches = vec![];
         for edge_ref in self.graph.edge_references() {
             let index_from = edge_ref.source();
@@ -420,13 +612,14 @@ impl<Kind, Type: Eq, Index, ImportData, LocalData>
             let edge = edge_ref.weight();
 
             let equal_type = from.ty_() == to.ty_();
+            let matching_type = equal_type || (structural && Kind::matches(to.ty_(), from.ty_()));
 
             match edge {
                 Edge::Imports => {
                     let index_import = index_from;
                     let index_export = index_to;
 
-                    if !equal_type {
+                    if !matching_type {
                         mismatches.push(Mismatch {
                             from: index_import,
                             to: index_export,
@@ -450,7 +643,7 @@ impl<Kind, Type: Eq, Index, ImportData, LocalData>
     }
 
     pub(crate) fn type_check_mismatch_break(&mut self) {
-        for Mismatch { from, to } in self.type_mismatches() {
+        for Mismatch { from, to } in self.type_mismatches(false) {
             let edge = self.graph.find_edge(from, to);
             #[cfg(debug_assertions)]
             debug_assert!(edge.is_some());
@@ -461,7 +654,19 @@ impl<Kind, Type: Eq, Index, ImportData, LocalData>
     }
 
     pub(crate) fn type_check_mismatch_signal(&self) -> Result<(), error::TypeMismatch> {
-        let type_mismatches = self.type_mismatches();
+        self.type_check_mismatch_signal_with(false)
+    }
+
+    /// Like [`Self::type_check_mismatch_signal`], allowing for subtypes.
+    pub(crate) fn type_check_mismatch_signal_structural(&self) -> Result<(), error::TypeMismatch> {
+        self.type_check_mismatch_signal_with(true)
+    }
+
+    pub(crate) fn type_check_mismatch_signal_with(
+        &self,
+        structural: bool,
+    ) -> Result<(), error::TypeMismatch> {
+        let type_mismatches = self.type_mismatches(structural);
 
         if type_mismatches.is_empty() {
             return Ok(());
@@ -0,0 +1,117 @@
+use walrus::{AbstractHeapType, HeapType, RefType, ValType};
+
+use crate::kinds::{FuncType, Function, Global, Memory, Table, Tag};
+
+/// How an import of a kind matches the export it links to, when allowing
+/// for subtypes. Functions are the only kind where the types may differ:
+/// tables & mutable globals can be written to, tags must be equivalent and
+/// memories are not typed.
+pub(crate) trait StructuralMatch<Type> {
+    fn matches(provided: &Type, required: &Type) -> bool;
+}
+
+impl StructuralMatch<FuncType> for Function {
+    fn matches(provided: &FuncType, required: &FuncType) -> bool {
+        provided.is_subtype_of(required)
+    }
+}
+
+impl<Type: Eq> StructuralMatch<Type> for Table {
+    fn matches(provided: &Type, required: &Type) -> bool {
+        provided == required
+    }
+}
+
+impl<Type: Eq> StructuralMatch<Type> for Memory {
+    fn matches(provided: &Type, required: &Type) -> bool {
+        provided == required
+    }
+}
+
+impl<Type: Eq> StructuralMatch<Type> for Global {
+    fn matches(provided: &Type, required: &Type) -> bool {
+        provided == required
+    }
+}
+
+/// Kind-less resolution, as used when testing the resolver in isolation.
+#[cfg(test)]
+impl<Type: Eq> StructuralMatch<Type> for () {
+    fn matches(provided: &Type, required: &Type) -> bool {
+        provided == required
+    }
+}
+
+impl<Type: Eq> StructuralMatch<Type> for Tag {
+    fn matches(provided: &Type, required: &Type) -> bool {
+        provided == required
+    }
+}
+
+/// Import matching, where the type of the export may be a subtype of the
+/// type that is required by the import.
+///
+/// ref: <https://webassembly.github.io/spec/core/valid/matching.html>
+pub(crate) trait Subtype {
+    fn is_subtype_of(&self, required: &Self) -> bool;
+}
+
+impl Subtype for FuncType {
+    /// Parameters are contravariant, results are covariant.
+    fn is_subtype_of(&self, required: &Self) -> bool {
+        let params = self.params().len() == required.params().len()
+            && (required.params().iter())
+                .zip(self.params())
+                .all(|(required, provided)| required.is_subtype_of(provided));
+        let results = self.results().len() == required.results().len()
+            && (self.results().iter())
+                .zip(required.results())
+                .all(|(provided, required)| provided.is_subtype_of(required));
+        params && results
+    }
+}
+
+impl Subtype for ValType {
+    fn is_subtype_of(&self, required: &Self) -> bool {
+        match (self, required) {
+            (ValType::Ref(provided), ValType::Ref(required)) => {
+                provided.heap_type_is_subtype_of(required)
+            }
+            (provided, required) => provided == required,
+        }
+    }
+}
+
+trait RefSubtype {
+    fn heap_type_is_subtype_of(&self, required: &Self) -> bool;
+}
+
+impl RefSubtype for RefType {
+    fn heap_type_is_subtype_of(&self, required: &Self) -> bool {
+        let nullable = !self.nullable || required.nullable;
+        let heap_type = match (self.heap_type, required.heap_type) {
+            (HeapType::Abstract(provided), HeapType::Abstract(required)) => {
+                abstract_is_subtype_of(provided, required)
+            }
+            // Concrete types are compared by their index only
+            (provided, required) => provided == required,
+        };
+        nullable && heap_type
+    }
+}
+
+/// ref: <https://webassembly.github.io/gc/core/valid/matching.html#heap-types>
+fn abstract_is_subtype_of(provided: AbstractHeapType, required: AbstractHeapType) -> bool {
+    use AbstractHeapType::{Any, Array, Eq, Exn, Extern, Func, I31, NoExn, NoExtern, NoFunc};
+    use AbstractHeapType::{None, Struct};
+    provided == required
+        || matches!(
+            (provided, required),
+            (Eq | I31 | Struct | Array | None, Any)
+                | (I31 | Struct | Array | None, Eq)
+                | (None, I31 | Struct | Array)
+                | (NoFunc, Func)
+                | (NoExtern, Extern)
+                | (NoExn, Exn)
+        )
+}
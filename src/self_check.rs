@@ -0,0 +1,150 @@
+use std::collections::HashSet as Set;
+
+use walrus::{ExportItem, ImportKind, Module};
+
+use crate::IdMapping;
+use crate::error::Error;
+use crate::kinds::{ExportKind, export_kind};
+use crate::merge_options::MergeOptions;
+use crate::named_module::NamedModule;
+
+/// Check the `merged` module against its `inputs`, following items through
+/// `mapping` rather than by name: every kept export must still be exported
+/// and every remaining import must be an import of an input module.
+pub(crate) fn check(
+    inputs: &[NamedModule<'_, walrus::Module>],
+    merged: &Module,
+    mapping: &IdMapping,
+    options: &MergeOptions,
+) -> Result<(), Error> {
+    let failed = |detail: String| Err(Error::SelfCheckFailed(detail));
+
+    let exports: Set<Item> = merged
+        .exports
+        .iter()
+        .map(|export| item(export.item))
+        .collect();
+    for (module, name, export) in kept_exports(inputs, options) {
+        match mapped(mapping, module, export) {
+            Some(merged) if exports.contains(&item(merged)) => {}
+            _ => return failed(format!("kept export {module}::{name} is missing")),
+        }
+    }
+
+    let input_imports: Set<(&str, Item)> = inputs
+        .iter()
+        .flat_map(|input| {
+            input.module.imports.iter().filter_map(|import| {
+                let merged = mapped(mapping, &input.name, imported_item(&import.kind))?;
+                Some((import.module.as_str(), item(merged)))
+            })
+        })
+        .collect();
+    for import in merged.imports.iter() {
+        let merged = item(imported_item(&import.kind));
+        if !input_imports.contains(&(import.module.as_str(), merged)) {
+            let (module, name) = (&import.module, &import.name);
+            return failed(format!(
+                "import {module}::{name} is not imported by any input"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the emitted `merged` module parses & validates.
+pub(crate) fn check_emitted(merged: &[u8]) -> Result<(), Error> {
+    let failed = |detail: String| Err(Error::SelfCheckFailed(detail));
+
+    if let Err(error) = wasmparser::validate(merged) {
+        return failed(format!("merged module is invalid: {error}"));
+    }
+    if let Err(error) = walrus::Module::from_buffer(merged) {
+        return failed(format!("merged module does not parse: {error}"));
+    }
+    Ok(())
+}
+
+/// The kept exports the input modules define, as `(module, name, item)`.
+fn kept_exports<'a>(
+    inputs: &'a [NamedModule<'_, walrus::Module>],
+    options: &MergeOptions,
+) -> Vec<(&'a str, &'a str, ExportItem)> {
+    let Some(keep_exports) = &options.keep_exports else {
+        return vec![];
+    };
+    let kept = keep_exports.names();
+
+    let mut kept_exports = vec![];
+    for input in inputs {
+        for export in input.module.exports.iter() {
+            let export_id = (input.name.to_string(), export.name.clone());
+            if !kept.contains(&export_id)
+                || options.internal_exports.contains(&export_id)
+                || (export_kind(export.item) == ExportKind::Memory
+                    && options.generate_memory_accessors)
+            {
+                continue;
+            }
+            kept_exports.push((&*input.name, export.name.as_str(), export.item));
+        }
+    }
+    kept_exports
+}
+
+/// The item of `module` in the merged module, if it is retained.
+fn mapped(mapping: &IdMapping, module: &str, item: ExportItem) -> Option<ExportItem> {
+    let key = |index| (module.to_string(), index);
+    match item {
+        ExportItem::Function(id) => mapping
+            .functions
+            .get(&key(id.index()))
+            .copied()
+            .map(ExportItem::Function),
+        ExportItem::Table(id) => mapping
+            .tables
+            .get(&key(id.index()))
+            .copied()
+            .map(ExportItem::Table),
+        ExportItem::Memory(id) => mapping
+            .memories
+            .get(&key(id.index()))
+            .copied()
+            .map(ExportItem::Memory),
+        ExportItem::Global(id) => mapping
+            .globals
+            .get(&key(id.index()))
+            .copied()
+            .map(ExportItem::Global),
+        ExportItem::Tag(id) => mapping
+            .tags
+            .get(&key(id.index()))
+            .copied()
+            .map(ExportItem::Tag),
+    }
+}
+
+/// An item of the merged module, identified by its kind & index.
+type Item = (ExportKind, usize);
+
+fn item(item: ExportItem) -> Item {
+    let index = match item {
+        ExportItem::Function(id) => id.index(),
+        ExportItem::Table(id) => id.index(),
+        ExportItem::Memory(id) => id.index(),
+        ExportItem::Global(id) => id.index(),
+        ExportItem::Tag(id) => id.index(),
+    };
+    (export_kind(item), index)
+}
+
+fn imported_item(kind: &ImportKind) -> ExportItem {
+    match kind {
+        ImportKind::Function(id) => ExportItem::Function(*id),
+        ImportKind::Table(id) => ExportItem::Table(*id),
+        ImportKind::Memory(id) => ExportItem::Memory(*id),
+        ImportKind::Global(id) => ExportItem::Global(*id),
+        ImportKind::Tag(id) => ExportItem::Tag(*id),
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::HashSet as Set;
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use crate::features::FeatureProfile;
+use crate::merge_options::RenameStrategy;
+use crate::merge_options::{
+    ClashingExports, KeepExports, LinkTypeMismatch, MergeOptions, NamePolicy,
+};
+use crate::merge_options::{DEFAULT_RENAMER, NAMESPACE_DOT_RENAMER, NUMERIC_SUFFIX_RENAMER};
+use crate::merge_options::{ExportIdentifier, ModuleOrder, ResolvedExports, StartTrapPolicy};
+
+/// The [`MergeOptions`] that can be read from (or written to) a configuration
+/// file. Rename strategies are referred to by name, the callbacks cannot be
+/// configured. Absent fields take their default value.
+///
+/// Eg. in JSON:
+/// ```json
+/// {
+///   "name_policy": { "clashes": { "rename": "dot" } },
+///   "keep_exports": { "functions": [["A", "f"]] },
+///   "tree_shake": true
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SerializableMergeOptions {
+    pub name_policy: SerializableNamePolicy,
+    pub link_type_mismatch: SerializableLinkTypeMismatch,
+    pub resolved_exports: SerializableResolvedExports,
+    pub keep_exports: Option<SerializableKeepExports>,
+    /// As `(module, name)`.
+    pub internal_exports: Vec<(String, String)>,
+    pub collect_all_errors: bool,
+    pub strip_custom_sections: bool,
+    pub emit_producers: bool,
+    pub start_trap_policy: SerializableStartTrapPolicy,
+    pub require_self_contained: bool,
+    pub inline_const_globals: bool,
+    pub max_output_bytes: Option<usize>,
+    pub tree_shake: bool,
+    /// As `(module, name, new name)`.
+    pub export_renames: Vec<(String, String, String)>,
+    pub keep_unused_imports: bool,
+    pub export_function_table: Option<String>,
+    pub embed_input_hashes: bool,
+    pub generate_memory_accessors: bool,
+    pub preserve_function_names: bool,
+    pub order: SerializableModuleOrder,
+    pub feature_profile: SerializableFeatureProfile,
+    pub tailcall_optimize: bool,
+    /// As `(module, name)`.
+    pub main_memory: Option<(String, String)>,
+    pub component_core: bool,
+}
+
+/// A built-in rename strategy, by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenameStrategyName {
+    /// See [`DEFAULT_RENAMER`].
+    Default,
+    /// See [`NAMESPACE_DOT_RENAMER`].
+    Dot,
+    /// See [`NUMERIC_SUFFIX_RENAMER`].
+    NumericSuffix,
+}
+
+/// See [`ClashingExports`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializableClashingExports {
+    Rename(RenameStrategyName),
+    AlwaysPrefix(RenameStrategyName),
+    #[default]
+    Signal,
+}
+
+/// See [`LinkTypeMismatch`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializableLinkTypeMismatch {
+    Ignore,
+    #[default]
+    Signal,
+    Structural,
+}
+
+/// See [`ResolvedExports`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializableResolvedExports {
+    #[default]
+    Remove,
+    Keep,
+}
+
+/// See [`StartTrapPolicy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializableStartTrapPolicy {
+    #[default]
+    Abort,
+    Continue,
+}
+
+/// See [`ModuleOrder`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializableModuleOrder {
+    #[default]
+    Inclusion,
+    Topological,
+}
+
+/// See [`FeatureProfile`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializableFeatureProfile {
+    Mvp,
+    Wasm2,
+    #[default]
+    All,
+}
+
+/// See [`NamePolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SerializableNamePolicy {
+    pub clashes: SerializableClashingExports,
+    pub settle_imports: bool,
+    pub dedupe_imports: bool,
+}
+
+impl Default for SerializableNamePolicy {
+    fn default() -> Self {
+        let defaults = NamePolicy::default();
+        Self {
+            clashes: SerializableClashingExports::default(),
+            settle_imports: defaults.settle_imports,
+            dedupe_imports: defaults.dedupe_imports,
+        }
+    }
+}
+
+/// See [`KeepExports`], each export as `(module, name)`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SerializableKeepExports {
+    pub functions: Vec<(String, String)>,
+    pub tables: Vec<(String, String)>,
+    pub memories: Vec<(String, String)>,
+    pub globals: Vec<(String, String)>,
+    pub tags: Vec<(String, String)>,
+}
+
+impl Default for SerializableMergeOptions {
+    fn default() -> Self {
+        let defaults = MergeOptions::default();
+        Self {
+            name_policy: SerializableNamePolicy::default(),
+            link_type_mismatch: SerializableLinkTypeMismatch::default(),
+            resolved_exports: SerializableResolvedExports::default(),
+            keep_exports: None,
+            internal_exports: vec![],
+            collect_all_errors: defaults.collect_all_errors,
+            strip_custom_sections: defaults.strip_custom_sections,
+            emit_producers: defaults.emit_producers,
+            start_trap_policy: SerializableStartTrapPolicy::default(),
+            require_self_contained: defaults.require_self_contained,
+            inline_const_globals: defaults.inline_const_globals,
+            max_output_bytes: defaults.max_output_bytes,
+            tree_shake: defaults.tree_shake,
+            export_renames: vec![],
+            keep_unused_imports: defaults.keep_unused_imports,
+            export_function_table: defaults.export_function_table,
+            embed_input_hashes: defaults.embed_input_hashes,
+            generate_memory_accessors: defaults.generate_memory_accessors,
+            preserve_function_names: defaults.preserve_function_names,
+            order: SerializableModuleOrder::default(),
+            feature_profile: SerializableFeatureProfile::default(),
+            tailcall_optimize: defaults.tailcall_optimize,
+            main_memory: defaults.main_memory,
+            component_core: defaults.component_core,
+        }
+    }
+}
+
+impl From<RenameStrategyName> for RenameStrategy {
+    fn from(name: RenameStrategyName) -> Self {
+        match name {
+            RenameStrategyName::Default => DEFAULT_RENAMER,
+            RenameStrategyName::Dot => NAMESPACE_DOT_RENAMER,
+            RenameStrategyName::NumericSuffix => NUMERIC_SUFFIX_RENAMER,
+        }
+    }
+}
+
+impl From<SerializableClashingExports> for ClashingExports {
+    fn from(clashing_exports: SerializableClashingExports) -> Self {
+        match clashing_exports {
+            SerializableClashingExports::Rename(name) => Self::Rename(name.into()),
+            SerializableClashingExports::AlwaysPrefix(name) => Self::AlwaysPrefix(name.into()),
+            SerializableClashingExports::Signal => Self::Signal,
+        }
+    }
+}
+
+impl From<SerializableLinkTypeMismatch> for LinkTypeMismatch {
+    fn from(link_type_mismatch: SerializableLinkTypeMismatch) -> Self {
+        match link_type_mismatch {
+            SerializableLinkTypeMismatch::Ignore => Self::Ignore,
+            SerializableLinkTypeMismatch::Signal => Self::Signal,
+            SerializableLinkTypeMismatch::Structural => Self::Structural,
+        }
+    }
+}
+
+impl From<SerializableResolvedExports> for ResolvedExports {
+    fn from(resolved_exports: SerializableResolvedExports) -> Self {
+        match resolved_exports {
+            SerializableResolvedExports::Remove => Self::Remove,
+            SerializableResolvedExports::Keep => Self::Keep,
+        }
+    }
+}
+
+impl From<SerializableStartTrapPolicy> for StartTrapPolicy {
+    fn from(start_trap_policy: SerializableStartTrapPolicy) -> Self {
+        match start_trap_policy {
+            SerializableStartTrapPolicy::Abort => Self::Abort,
+            SerializableStartTrapPolicy::Continue => Self::Continue,
+        }
+    }
+}
+
+impl From<SerializableModuleOrder> for ModuleOrder {
+    fn from(order: SerializableModuleOrder) -> Self {
+        match order {
+            SerializableModuleOrder::Inclusion => Self::Inclusion,
+            SerializableModuleOrder::Topological => Self::Topological,
+        }
+    }
+}
+
+impl From<SerializableFeatureProfile> for FeatureProfile {
+    fn from(feature_profile: SerializableFeatureProfile) -> Self {
+        match feature_profile {
+            SerializableFeatureProfile::Mvp => Self::Mvp,
+            SerializableFeatureProfile::Wasm2 => Self::Wasm2,
+            SerializableFeatureProfile::All => Self::All,
+        }
+    }
+}
+
+impl From<SerializableNamePolicy> for NamePolicy {
+    fn from(name_policy: SerializableNamePolicy) -> Self {
+        Self {
+            clashes: name_policy.clashes.into(),
+            settle_imports: name_policy.settle_imports,
+            dedupe_imports: name_policy.dedupe_imports,
+        }
+    }
+}
+
+impl From<SerializableKeepExports> for KeepExports {
+    fn from(keep_exports: SerializableKeepExports) -> Self {
+        fn identifiers<Name: From<String> + Eq + Hash>(
+            exports: Vec<(String, String)>,
+        ) -> Set<ExportIdentifier<Name>> {
+            exports
+                .into_iter()
+                .map(|(module, name)| ExportIdentifier {
+                    module: module.into(),
+                    name: name.into(),
+                })
+                .collect()
+        }
+
+        Self {
+            functions: identifiers(keep_exports.functions),
+            tables: identifiers(keep_exports.tables),
+            memories: identifiers(keep_exports.memories),
+            globals: identifiers(keep_exports.globals),
+            tags: identifiers(keep_exports.tags),
+        }
+    }
+}
+
+impl From<SerializableMergeOptions> for MergeOptions {
+    fn from(options: SerializableMergeOptions) -> Self {
+        Self {
+            name_policy: options.name_policy.into(),
+            link_type_mismatch: options.link_type_mismatch.into(),
+            resolved_exports: options.resolved_exports.into(),
+            keep_exports: options.keep_exports.map(Into::into),
+            on_parse_error: None,
+            internal_exports: options.internal_exports.into_iter().collect(),
+            collect_all_errors: options.collect_all_errors,
+            strip_custom_sections: options.strip_custom_sections,
+            emit_producers: options.emit_producers,
+            start_trap_policy: options.start_trap_policy.into(),
+            require_self_contained: options.require_self_contained,
+            inline_const_globals: options.inline_const_globals,
+            max_output_bytes: options.max_output_bytes,
+            tree_shake: options.tree_shake,
+            export_renames: options
+                .export_renames
+                .into_iter()
+                .map(|(module, name, new_name)| ((module, name), new_name))
+                .collect(),
+            keep_unused_imports: options.keep_unused_imports,
+            export_function_table: options.export_function_table,
+            embed_input_hashes: options.embed_input_hashes,
+            generate_memory_accessors: options.generate_memory_accessors,
+            preserve_function_names: options.preserve_function_names,
+            order: options.order.into(),
+            post_process: None,
+            feature_profile: options.feature_profile.into(),
+            tailcall_optimize: options.tailcall_optimize,
+            main_memory: options.main_memory,
+            component_core: options.component_core,
+        }
+    }
+}
@@ -5,7 +5,7 @@ use wasmtime::*;
 use wat::parse_str;
 
 use wasm_mergers::merge_options::DEFAULT_RENAMER;
-use wasm_mergers::merge_options::{ClashingExports, KeepExports, MergeOptions};
+use wasm_mergers::merge_options::{ClashingExports, KeepExports, MergeOptions, NamePolicy};
 use wasm_mergers::{MergeConfiguration, NamedModule};
 
 mod smithed_tests;
@@ -360,6 +360,8 @@ fn merge_cycle_chain() -> Result<(), Error> {
 /// where `func_a` and `func_a'` are defined as a lookup of each other.
 #[test]
 fn illegal_loop() -> Result<(), Error> {
+    use wasm_mergers::kinds::ExportKind;
+
     const WAT_MOD_B: &str = r#"
       (module
         (import "WAT_MOD_A" "func_a" (func $func_a (param i32) (result i32)))
@@ -384,7 +386,11 @@ fn illegal_loop() -> Result<(), Error> {
         .merge()
         .expect_err("Expect infinite cycle loop");
 
-    assert!(matches!(error, wasm_mergers::error::Error::ImportCycle));
+    assert!(matches!(
+        error,
+        wasm_mergers::error::Error::ImportCycle { kind: ExportKind::Function, modules }
+            if modules == ["WAT_MOD_A", "WAT_MOD_B"]
+    ));
 
     Ok(())
 }
@@ -627,7 +633,9 @@ fn composition_of_cross_deps() -> Result<(), Error> {
         &NamedModule::new("e", &wat_e),
     ];
 
-    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+    let (merged, report) =
+        MergeConfiguration::new(modules, MergeOptions::default()).merge_with_report()?;
+    assert!(report.is_self_contained);
 
     // Instantiate merged module (should be self-contained)
     let mut store = Store::<()>::default();
@@ -645,6 +653,15 @@ fn composition_of_cross_deps() -> Result<(), Error> {
 
     assert_eq!(wasm_call!(store, e), rs_e());
 
+    // Without the `ab` provider, the merged module still has imports
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("cd", &wat_cd),
+        &NamedModule::new("e", &wat_e),
+    ];
+    let (_, report) =
+        MergeConfiguration::new(modules, MergeOptions::default()).merge_with_report()?;
+    assert!(!report.is_self_contained);
+
     Ok(())
 }
 
@@ -694,7 +711,10 @@ fn test_multi_memory() -> Result<(), Error> {
     ];
 
     let merge_options = MergeOptions {
-        clashing_exports: ClashingExports::Rename(DEFAULT_RENAMER),
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+            ..Default::default()
+        },
         ..Default::default()
     };
 
@@ -787,15 +807,4914 @@ fn kind_mismatch_expect() -> Result<(), Error> {
         resolved_exports: ResolvedExports::Keep,
         ..Default::default()
     };
-    let outcome = MergeConfiguration::new(modules_abc, options).merge()?;
-    let parsed = Module::from_buffer(&outcome)?;
-    let exports = parsed.exports.iter().collect::<Vec<_>>();
-
-    // FIXME: Add support for the 'ResolvedExports::Keep'.
-    assert_eq!(exports.len(), 1); // TODO: should be 2 / merging should fail!
+    assert!(matches!(
+        MergeConfiguration::new(modules_abc, options).merge(),
+        Err(Error::ExportNameClash(_))
+    ));
 
     Ok(())
 }
 
 // TODO: if two modules import from the same location, are they the same node
 //       in the graph? If not ... this should be explored!
+
+/// The participating module names are reported in input order,
+/// and invalid names (duplicate or empty) are reported as an error.
+#[test]
+fn module_names_in_input_order() -> Result<(), Error> {
+    use wasm_mergers::error::Error;
+
+    let empty = parse_str("(module)")?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("c", &empty),
+        &NamedModule::new("a", &empty),
+        &NamedModule::new("b", &empty),
+    ];
+    let configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    assert_eq!(configuration.module_names()?, vec!["c", "a", "b"]);
+
+    let duplicates: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("a", &empty),
+        &NamedModule::new("b", &empty),
+        &NamedModule::new("a", &empty),
+    ];
+    let mut configuration = MergeConfiguration::new(duplicates, MergeOptions::default());
+    assert!(matches!(
+        configuration.module_names(),
+        Err(Error::DuplicateModuleName(name)) if name == "a"
+    ));
+    assert!(matches!(
+        configuration.merge(),
+        Err(Error::DuplicateModuleName(name)) if name == "a"
+    ));
+
+    let unnamed: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("", &empty)];
+    let configuration = MergeConfiguration::new(unnamed, MergeOptions::default());
+    assert!(matches!(
+        configuration.module_names(),
+        Err(Error::EmptyModuleName)
+    ));
+
+    Ok(())
+}
+
+/// The `dylink.0` sections of Emscripten side modules are combined into
+/// a single leading section, or dropped when the merge is self-contained.
+#[test]
+fn merge_dylink_sections() -> Result<(), Error> {
+    const DYLINK_A: &str = r#"(@custom "dylink.0" (before first)
+        "\01\04\10\02\01\00"            ;; mem info: 16 bytes, 2^2 aligned, 1 table slot
+        "\02\09\01\07libc.so"           ;; needed: libc.so
+    )"#;
+    const DYLINK_B: &str = r#"(@custom "dylink.0" (before first)
+        "\01\04\0a\03\02\00"            ;; mem info: 10 bytes, 2^3 aligned, 2 table slots
+        "\02\11\02\07libc.so\07libm.so" ;; needed: libc.so, libm.so
+    )"#;
+
+    let side_a = parse_str(format!(
+        r#"(module {DYLINK_A}
+            (import "env" "memory" (memory 1))
+            (func (export "a")))"#
+    ))?;
+    let side_b = parse_str(format!(
+        r#"(module {DYLINK_B}
+            (import "env" "memory" (memory 1))
+            (func (export "b")))"#
+    ))?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("a", &side_a),
+        &NamedModule::new("b", &side_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+    Module::new(&Engine::default(), &merged)?;
+
+    let name = b"dylink.0";
+    let occurrences = merged.windows(name.len()).filter(|w| w == name).count();
+    assert_eq!(occurrences, 1);
+
+    #[rustfmt::skip]
+    let expected_section: Vec<u8> = [
+        &[0, 34, 8][..], name,
+        &[1, 4, 32, 3, 3, 0],
+        &[2, 17, 2, 7], b"libc.so", &[7], b"libm.so",
+    ].concat();
+    assert_eq!(&merged[8..8 + expected_section.len()], expected_section);
+
+    // Without any remaining imports, the merged module is no side module
+    let contained_a = parse_str(format!(r#"(module {DYLINK_A} (func (export "a")))"#))?;
+    let contained_b = parse_str(format!(r#"(module {DYLINK_B} (func (export "b")))"#))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("a", &contained_a),
+        &NamedModule::new("b", &contained_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+    assert!(!merged.windows(name.len()).any(|w| w == name));
+
+    // Requirements that cannot be laid out in 32 bits are rejected
+    const DYLINK_HUGE: &str = r#"(@custom "dylink.0" (before first)
+        "\01\04\00\20\00\00"            ;; mem info: 0 bytes, 2^32 aligned
+    )"#;
+    let side_huge = parse_str(format!(
+        r#"(module {DYLINK_HUGE}
+            (import "env" "memory" (memory 1))
+            (func (export "huge")))"#
+    ))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("a", &side_a),
+        &NamedModule::new("huge", &side_huge),
+    ];
+    let outcome = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(outcome, Err(wasm_mergers::error::Error::Parse(_))));
+
+    // Unknown subsections are skipped, their length tells where they end
+    const DYLINK_UNKNOWN: &str = r#"(@custom "dylink.0" (before first)
+        "\7f\02\ab\cd"                  ;; unknown subsection of 2 bytes
+        "\01\04\0a\03\02\00"            ;; mem info: 10 bytes, 2^3 aligned, 2 table slots
+    )"#;
+    let side_unknown = parse_str(format!(
+        r#"(module {DYLINK_UNKNOWN}
+            (import "env" "memory" (memory 1))
+            (func (export "b")))"#
+    ))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("a", &side_a),
+        &NamedModule::new("b", &side_unknown),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+    #[rustfmt::skip]
+    let expected_section: Vec<u8> = [
+        &[0, 26, 8][..], name,
+        &[1, 4, 32, 3, 3, 0],
+        &[2, 9, 1, 7], b"libc.so",
+    ].concat();
+    assert_eq!(&merged[8..8 + expected_section.len()], expected_section);
+
+    Ok(())
+}
+
+/// A module that fails to parse can be substituted by a stub module the hook
+/// captures, or left out of the merge entirely.
+#[test]
+fn recover_from_parse_error() -> Result<(), Error> {
+    use std::sync::Arc;
+    use wasm_mergers::merge_options::ParseRecovery;
+
+    let valid = parse_str(r#"(module (func (export "f") (result i32) i32.const 42))"#)?;
+    let corrupt: &[u8] = b"\0asm corrupt";
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("valid", &valid),
+        &NamedModule::new("corrupt", corrupt),
+    ];
+
+    // By default, the merge is aborted
+    let options = MergeOptions::default();
+    let outcome = MergeConfiguration::new(modules, options).merge();
+    assert!(matches!(outcome, Err(wasm_mergers::error::Error::Parse(_))));
+
+    // Substitute the corrupt module by an empty module
+    let stub = parse_str("(module)")?;
+    let options = MergeOptions {
+        on_parse_error: Some(Arc::new(move |name, _| {
+            assert_eq!(name, "corrupt");
+            ParseRecovery::Replace(stub.clone())
+        })),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let f = instance.get_typed_func::<(), i32>(&mut store, "f")?;
+    assert_eq!(f.call(&mut store, ())?, 42);
+
+    // Or skip it altogether
+    let options = MergeOptions {
+        on_parse_error: Some(Arc::new(|_, _| ParseRecovery::Skip)),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+    Module::new(&engine, &merged)?;
+
+    Ok(())
+}
+
+/// A module importing one of its own exports, backed by a local definition,
+/// is resolved to that definition and is not considered an import cycle.
+#[test]
+fn self_import_resolves_to_local() -> Result<(), Error> {
+    let self_importing = parse_str(
+        r#"
+        (module
+          (import "self" "f" (func $imported_f (result i32)))
+          (func $f (export "f") (result i32)
+            i32.const 42)
+          (func (export "g") (result i32)
+            call $imported_f))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("self", &self_importing)];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &merged)?;
+    assert_eq!(module.imports().len(), 0);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let g = instance.get_typed_func::<(), i32>(&mut store, "g")?;
+    assert_eq!(g.call(&mut store, ())?, 42);
+
+    Ok(())
+}
+
+/// Modules performing `return_call_indirect` through their own table keep
+/// reaching their own functions, and keep tail-call semantics once merged.
+///
+/// Tables are not coalesced, each module's table is retained as-is.
+#[test]
+fn merge_return_call_indirect() -> Result<(), Error> {
+    let gen_wat = |result: i32| {
+        format!(
+            r#"
+            (module
+              (type $countdown (func (param i32) (result i32)))
+              (table 2 funcref)
+              (elem (i32.const 0) $done $loop)
+              (func $done (type $countdown)
+                i32.const {result})
+              (func $loop (type $countdown)
+                (i32.sub (local.get 0) (i32.const 1))
+                ;; index 1 (loop) while n != 0, index 0 (done) otherwise
+                (i32.ne (local.get 0) (i32.const 0))
+                return_call_indirect (type $countdown))
+              (func (export "countdown") (param i32) (result i32)
+                (return_call_indirect (type $countdown)
+                  (local.get 0) (i32.const 1))))"#
+        )
+    };
+
+    let wasm_a = parse_str(gen_wat(1))?;
+    let wasm_b = parse_str(gen_wat(2))?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merge_options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, merge_options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    // A depth that exhausts the stack unless calls are in tail position
+    const DEPTH: i32 = 1_000_000;
+    let countdown_a = instance.get_typed_func::<i32, i32>(&mut store, "A:countdown")?;
+    let countdown_b = instance.get_typed_func::<i32, i32>(&mut store, "B:countdown")?;
+    assert_eq!(countdown_a.call(&mut store, DEPTH)?, 1);
+    assert_eq!(countdown_b.call(&mut store, DEPTH)?, 2);
+
+    Ok(())
+}
+
+/// Repeated identical signatures, eg. in `call_indirect`, map onto one type.
+#[test]
+fn identical_signatures_share_a_type() -> Result<(), Error> {
+    const CALLS: usize = 1_000;
+
+    let gen_wat = |name: &str| {
+        let calls = "(call_indirect (type $t) (local.get 0) (i32.const 0)) drop\n".repeat(CALLS);
+        format!(
+            r#"
+            (module
+              (type $t (func (param i32) (result i32)))
+              (table 1 funcref)
+              (elem (i32.const 0) $id)
+              (func $id (type $t) local.get 0)
+              (func (export "{name}") (param i32)
+                {calls}))"#
+        )
+    };
+
+    let wasm_a = parse_str(gen_wat("a"))?;
+    let wasm_b = parse_str(gen_wat("b"))?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    // No more types than any single input, despite thousands of type uses
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let input = walrus::Module::from_buffer(&wasm_a)?;
+    assert!(parsed.types.iter().count() <= input.types.iter().count());
+
+    Ok(())
+}
+
+/// An export marked internal enables linking, yet is absent after merging,
+/// even when it is requested to be kept.
+#[test]
+fn internal_exports_are_stripped() -> Result<(), Error> {
+    let provider = parse_str(r#"(module (func (export "bridge") (result i32) i32.const 42))"#)?;
+    let consumer = parse_str(
+        r#"
+        (module
+          (import "provider" "bridge" (func $bridge (result i32)))
+          (func (export "main") (result i32) call $bridge))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("provider", &provider),
+        &NamedModule::new("consumer", &consumer),
+    ];
+
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("provider".into(), "bridge".to_string());
+    let options = MergeOptions {
+        keep_exports: Some(keep_exports),
+        internal_exports: once(("provider".to_string(), "bridge".to_string())).collect(),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    assert_eq!(module.imports().len(), 0);
+    let export_names: Vec<_> = module.exports().map(|export| export.name()).collect();
+    assert_eq!(export_names, vec!["main"]);
+
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let main = instance.get_typed_func::<(), i32>(&mut store, "main")?;
+    assert_eq!(main.call(&mut store, ())?, 42);
+
+    Ok(())
+}
+
+/// A module's `start` may be an imported function, both when the import is
+/// resolved by another module and when it remains an import after merging.
+#[test]
+fn start_is_imported_function() -> Result<(), Error> {
+    let provider = parse_str(
+        r#"
+        (module
+          (global $initialized (export "initialized") (mut i32) (i32.const 0))
+          (func (export "init")
+            (global.set $initialized (i32.const 1))))"#,
+    )?;
+    let app = parse_str(
+        r#"
+        (module
+          (import "provider" "init" (func $init))
+          (start $init))"#,
+    )?;
+
+    // Resolved: the start function is the provider's `init`
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("provider", &provider),
+        &NamedModule::new("app", &app),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let initialized = instance.get_global(&mut store, "initialized").unwrap();
+    assert_eq!(initialized.get(&mut store).i32(), Some(1));
+
+    // Unresolved: the start function remains an import
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("app", &app)];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let mut store = Store::new(&Engine::default(), false);
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let init = Func::wrap(&mut store, |mut caller: Caller<'_, bool>| {
+        *caller.data_mut() = true;
+    });
+    Instance::new(&mut store, &module, &[init.into()])?;
+    assert!(*store.data());
+
+    Ok(())
+}
+
+/// Under `StartTrapPolicy::Continue` the starts are exported rather than
+/// combined, a trapping start no longer keeps the other modules from starting.
+#[test]
+fn start_trap_policy_continue() -> Result<(), Error> {
+    use wasm_mergers::merge_options::StartTrapPolicy;
+
+    let wasm_a = parse_str(r#"(module (func $start unreachable) (start $start))"#)?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (global $runs (mut i32) (i32.const 0))
+          (func $start
+            (global.set $runs (i32.add (global.get $runs) (i32.const 1))))
+          (func (export "runs") (result i32) (global.get $runs))
+          (start $start))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    // Combined, the trapping start of A aborts the instantiation
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    assert!(Instance::new(&mut store, &module, &[]).is_err());
+
+    let options = MergeOptions {
+        start_trap_policy: StartTrapPolicy::Continue,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let start_a = instance.get_typed_func::<(), ()>(&mut store, "start_A")?;
+    let start_b = instance.get_typed_func::<(), ()>(&mut store, "start_B")?;
+    let runs = instance.get_typed_func::<(), i32>(&mut store, "runs")?;
+    // The starts are left to the host, none ran upon instantiation
+    assert_eq!(runs.call(&mut store, ())?, 0);
+    assert!(start_a.call(&mut store, ()).is_err());
+    // The start placed after the trapping one still runs, exactly once
+    start_b.call(&mut store, ())?;
+    assert_eq!(runs.call(&mut store, ())?, 1);
+
+    Ok(())
+}
+
+/// Exports can be grouped per module, following the `module.export` convention.
+#[test]
+fn namespace_dot_exports() -> Result<(), Error> {
+    use wasm_mergers::merge_options::NAMESPACE_DOT_RENAMER;
+
+    let wasm_a = parse_str(r#"(module (func (export "f") (result i32) i32.const 1))"#)?;
+    let wasm_b = parse_str(r#"(module (func (export "g.h") (result i32) i32.const 2))"#)?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::AlwaysPrefix(NAMESPACE_DOT_RENAMER),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let export_names = module.exports().map(|export| export.name()).sorted();
+    assert_eq!(export_names.collect::<Vec<_>>(), vec!["A.f", "B.g.h"]);
+
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let f = instance.get_typed_func::<(), i32>(&mut store, "A.f")?;
+    let g = instance.get_typed_func::<(), i32>(&mut store, "B.g.h")?;
+    assert_eq!(f.call(&mut store, ())?, 1);
+    assert_eq!(g.call(&mut store, ())?, 2);
+
+    Ok(())
+}
+
+/// Clashing exports can be renamed by numbering the subsequent occurrences.
+#[test]
+fn numeric_suffix_exports() -> Result<(), Error> {
+    use wasm_mergers::merge_options::NUMERIC_SUFFIX_RENAMER;
+
+    let gen_wat =
+        |result: i32| format!(r#"(module (func (export "f") (result i32) i32.const {result}))"#);
+    let wasm_a = parse_str(gen_wat(1))?;
+    let wasm_b = parse_str(gen_wat(2))?;
+    let wasm_c = parse_str(gen_wat(3))?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(NUMERIC_SUFFIX_RENAMER),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let export_names = module.exports().map(|export| export.name()).sorted();
+    assert_eq!(export_names.collect::<Vec<_>>(), vec!["f", "f_1", "f_2"]);
+
+    // The first occurrence, in input order, keeps its name
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let mut results = vec![];
+    for name in ["f", "f_1", "f_2"] {
+        let f = instance.get_typed_func::<(), i32>(&mut store, name)?;
+        results.push(f.call(&mut store, ())?);
+    }
+    assert_eq!(results, vec![1, 2, 3]);
+
+    Ok(())
+}
+
+/// A custom strategy is created by its constructor and adjusted by field, eg.
+/// to number the renames of clashing exports by a scheme of its own.
+#[test]
+fn custom_numbered_rename() -> Result<(), Error> {
+    use wasm_mergers::merge_options::{RenameStrategy, default_rename};
+
+    let gen_wat =
+        |result: i32| format!(r#"(module (func (export "f") (result i32) i32.const {result}))"#);
+    let wasm_a = parse_str(gen_wat(1))?;
+    let wasm_b = parse_str(gen_wat(2))?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let mut strategy = RenameStrategy::new(
+        default_rename,
+        default_rename,
+        default_rename,
+        default_rename,
+        default_rename,
+    );
+    strategy.first_occurrence = false;
+    strategy.numbered = Some(|name, n| format!("{name}#{n}"));
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(strategy),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let export_names = parsed.exports.iter().map(|export| &export.name).sorted();
+    assert_eq!(export_names.collect::<Vec<_>>(), ["f", "f#1"]);
+
+    Ok(())
+}
+
+/// A numbered rename skips the names that other exports already take.
+#[test]
+fn numeric_suffix_skips_taken_names() -> Result<(), Error> {
+    use wasm_mergers::merge_options::NUMERIC_SUFFIX_RENAMER;
+
+    let gen_wat = |name: &str, result: i32| {
+        format!(r#"(module (func (export "{name}") (result i32) i32.const {result}))"#)
+    };
+    let wasm_a = parse_str(gen_wat("f", 1))?;
+    let wasm_b = parse_str(gen_wat("f", 2))?;
+    let wasm_c = parse_str(gen_wat("f_1", 3))?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(NUMERIC_SUFFIX_RENAMER),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+    wasmparser::validate(&merged)?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let mut results = vec![];
+    for name in ["f", "f_1", "f_2"] {
+        let f = instance.get_typed_func::<(), i32>(&mut store, name)?;
+        results.push(f.call(&mut store, ())?);
+    }
+    assert_eq!(results, vec![1, 3, 2]);
+
+    Ok(())
+}
+
+/// Merging a subset of the configured modules leaves imports from the
+/// excluded modules external.
+#[test]
+fn merge_subset_of_modules() -> Result<(), Error> {
+    let wasm_a = parse_str(r#"(module (func (export "a") (result i32) i32.const 1))"#)?;
+    let wasm_b = parse_str(r#"(module (func (export "b") (result i32) i32.const 2))"#)?;
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (import "a" "a" (func $a (result i32)))
+          (import "b" "b" (func $b (result i32)))
+          (func (export "c") (result i32)
+            (i32.add (call $a) (call $b))))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("a", &wasm_a),
+        &NamedModule::new("b", &wasm_b),
+        &NamedModule::new("c", &wasm_c),
+    ];
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    let merged = configuration.merge_subset(&["a", "c"])?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let imports: Vec<_> = module
+        .imports()
+        .map(|import| (import.module(), import.name()))
+        .collect();
+    assert_eq!(imports, vec![("b", "b")]);
+
+    let b = Func::wrap(&mut store, || 40);
+    let instance = Instance::new(&mut store, &module, &[b.into()])?;
+    let c = instance.get_typed_func::<(), i32>(&mut store, "c")?;
+    assert_eq!(c.call(&mut store, ())?, 41);
+
+    // A name that is not configured is reported rather than left out
+    let outcome = configuration.merge_subset(&["a", "typo"]);
+    assert!(matches!(
+        outcome,
+        Err(wasm_mergers::error::Error::UnknownModule(name)) if name == "typo"
+    ));
+
+    Ok(())
+}
+
+/// Linking a memory import is only allowed when the linked memory's limits
+/// satisfy the limits the import requires.
+#[test]
+fn memory_limits_mismatch() -> Result<(), Error> {
+    use wasm_mergers::error::Error;
+
+    let importer = parse_str(r#"(module (import "A" "m" (memory 1 1)))"#)?;
+
+    let exporter = parse_str(r#"(module (memory (export "m") 2))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &exporter),
+        &NamedModule::new("B", &importer),
+    ];
+    let outcome = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(
+        outcome,
+        Err(Error::MemoryLimitsMismatch(mismatches))
+            if mismatches.len() == 1
+                && mismatches[0].importing == "B".into()
+                && mismatches[0].exporting == "A".into()
+    ));
+
+    // A satisfied import is linked to the memory it resolves to
+    let importer = parse_str(
+        r#"(module
+            (import "A" "m" (memory 1 1))
+            (func (export "load") (result i32)
+                (i32.load8_u (i32.const 0))))"#,
+    )?;
+    let exporter = parse_str(
+        r#"(module
+            (memory (export "m") 1 1)
+            (data (i32.const 0) "\2a"))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &exporter),
+        &NamedModule::new("B", &importer),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &merged)?;
+    assert_eq!(module.imports().len(), 0);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let load = instance.get_typed_func::<(), i32>(&mut store, "load")?;
+    assert_eq!(load.call(&mut store, ())?, 42);
+
+    Ok(())
+}
+
+/// Importers requiring more initial pages than the shared memory declares
+/// grow it to the largest requirement, within its maximum.
+#[test]
+fn memory_initial_reconciled() -> Result<(), Error> {
+    use wasm_mergers::error::Error;
+
+    let exporter = parse_str(r#"(module (memory (export "m") 1))"#)?;
+    let importer_b = parse_str(r#"(module (import "A" "m" (memory 1)))"#)?;
+    let importer_c = parse_str(r#"(module (import "A" "m" (memory 3)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &exporter),
+        &NamedModule::new("B", &importer_b),
+        &NamedModule::new("C", &importer_c),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+    let merged = walrus::Module::from_buffer(&merged)?;
+    // Both imports are linked to the one memory of A, grown to fit both
+    let mut memories = merged.memories.iter();
+    let memory = memories.next().unwrap();
+    assert!(memories.next().is_none());
+    assert!(memory.import.is_none());
+    assert_eq!(memory.initial, 3);
+    assert_eq!(memory.maximum, None);
+
+    // Growing beyond the maximum of the shared memory remains a mismatch.
+    let exporter = parse_str(r#"(module (memory (export "m") 1 2))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &exporter),
+        &NamedModule::new("C", &importer_c),
+    ];
+    let outcome = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(
+        outcome,
+        Err(Error::MemoryLimitsMismatch(mismatches))
+            if mismatches.len() == 1 && mismatches[0].importing == "C".into()
+    ));
+
+    Ok(())
+}
+
+/// All resolution errors can be reported at once.
+#[test]
+fn collect_all_errors() -> Result<(), Error> {
+    use wasm_mergers::error::Error;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) i32.const 0)
+          (global (export "g") i32 (i32.const 0)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func (result i64)))
+          (global (export "g") i32 (i32.const 1)))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    // By default, the first error is reported
+    let outcome = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(outcome, Err(Error::TypeMismatch(_))));
+
+    let options = MergeOptions {
+        collect_all_errors: true,
+        ..Default::default()
+    };
+    let outcome = MergeConfiguration::new(modules, options).merge();
+    let Err(Error::Aggregate(errors)) = outcome else {
+        panic!("Expected an aggregate error, got {outcome:?}");
+    };
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], Error::TypeMismatch(_)));
+    assert!(matches!(&errors[1], Error::ExportNameClash(clashes) if clashes.contains_key("g")));
+
+    Ok(())
+}
+
+/// `memory.grow` and `memory.size` keep targeting a module's own memory,
+/// even when its memory index is shifted in the merged module.
+#[test]
+fn memory_grow_after_reindexing() -> Result<(), Error> {
+    let gen_wat = |name: &str| {
+        format!(
+            r#"
+            (module
+              (memory 1)
+              (func (export "grow_{name}") (result i32)
+                (drop (memory.grow (i32.const 2)))
+                ;; Write & read in the grown region
+                (i32.store (i32.const 131072) (i32.const 42))
+                (i32.load (i32.const 131072)))
+              (func (export "size_{name}") (result i32)
+                memory.size))"#
+        )
+    };
+
+    let wasm_a = parse_str(gen_wat("a"))?;
+    let wasm_b = parse_str(gen_wat("b"))?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    declare_fns_from_wasm! { instance, store,
+        grow_b [] [i32],
+        size_a [] [i32],
+        size_b [] [i32]
+    };
+
+    // Module B's memory is shifted to index 1, growing it leaves A's untouched
+    assert_eq!(wasm_call!(store, grow_b), 42);
+    assert_eq!(wasm_call!(store, size_a), 1);
+    assert_eq!(wasm_call!(store, size_b), 3);
+
+    Ok(())
+}
+
+/// A module using SIMD is incompatible with an MVP target.
+#[test]
+fn check_target_features() -> Result<(), Error> {
+    use wasm_mergers::error::Error as MergeError;
+    use wasm_mergers::features::{Feature, FeatureSet};
+
+    let wasm_simd = parse_str(
+        r#"
+        (module
+          (func (export "splat") (result i32)
+            (i32x4.extract_lane 0
+              (i32x4.add (v128.const i32x4 1 2 3 4) (i32x4.splat (i32.const 1))))))"#,
+    )?;
+    let wasm_mvp = parse_str(
+        r#"
+        (module
+          (func (export "answer") (result i32)
+            (i32.const 42)))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("mvp", &wasm_mvp),
+        &NamedModule::new("simd", &wasm_simd),
+    ];
+    let configuration = MergeConfiguration::new(modules, MergeOptions::default());
+
+    let error = configuration.check_target(&FeatureSet::mvp()).unwrap_err();
+    assert!(matches!(error, MergeError::FeatureNotInTarget(feature) if feature == "simd"));
+
+    configuration.check_target(&FeatureSet::mvp().with(Feature::Simd))?;
+    configuration.check_target(&FeatureSet::all())?;
+
+    Ok(())
+}
+
+/// Merging two single-memory modules yields a multi-memory module, which an
+/// MVP target does not support even though each input on its own does.
+#[test]
+fn check_target_scans_merged_module() -> Result<(), Error> {
+    use wasm_mergers::error::Error as MergeError;
+    use wasm_mergers::features::{Feature, FeatureSet};
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory 1)
+          (func (export "size_a") (result i32)
+            (memory.size)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (memory 1)
+          (func (export "size_b") (result i32)
+            (memory.size)))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("a", &wasm_a),
+        &NamedModule::new("b", &wasm_b),
+    ];
+    for modules in iter_permutations(modules) {
+        let configuration = MergeConfiguration::new(&modules, MergeOptions::default());
+        let error = configuration.check_target(&FeatureSet::mvp()).unwrap_err();
+        assert!(
+            matches!(error, MergeError::FeatureNotInTarget(feature) if feature == "multi-memory")
+        );
+
+        configuration.check_target(&FeatureSet::mvp().with(Feature::MultiMemory))?;
+    }
+
+    Ok(())
+}
+
+/// A module using bulk memory is rejected under the MVP feature profile.
+#[test]
+fn feature_profile() -> Result<(), Error> {
+    use wasm_mergers::error::Error as MergeError;
+    use wasm_mergers::features::FeatureProfile;
+
+    let wasm_bulk = parse_str(
+        r#"
+        (module
+          (memory 1)
+          (func (export "clear") (memory.fill (i32.const 0) (i32.const 0) (i32.const 8))))"#,
+    )?;
+    let wasm_mvp = parse_str(r#"(module (func (export "answer") (result i32) (i32.const 42)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("mvp", &wasm_mvp),
+        &NamedModule::new("bulk", &wasm_bulk),
+    ];
+
+    let options = MergeOptions {
+        feature_profile: FeatureProfile::Mvp,
+        ..Default::default()
+    };
+    let outcome = MergeConfiguration::new(modules, options).merge();
+    assert!(matches!(
+        outcome,
+        Err(MergeError::FeatureNotAllowed { feature, module })
+            if feature == "bulk-memory" && module == "bulk"
+    ));
+
+    for feature_profile in [FeatureProfile::Wasm2, FeatureProfile::All] {
+        let options = MergeOptions {
+            feature_profile,
+            ..Default::default()
+        };
+        MergeConfiguration::new(modules, options).merge()?;
+    }
+
+    Ok(())
+}
+
+/// Features the merge itself introduces are checked against the feature
+/// profile as well, eg. multiple memories or tail calls.
+#[test]
+fn feature_profile_of_merged_module() -> Result<(), Error> {
+    use wasm_mergers::error::Error as MergeError;
+    use wasm_mergers::features::FeatureProfile;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory 1)
+          (func (export "size_a") (result i32)
+            (memory.size)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (memory 1)
+          (func (export "size_b") (result i32)
+            (memory.size)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("a", &wasm_a),
+        &NamedModule::new("b", &wasm_b),
+    ];
+
+    let options = MergeOptions {
+        feature_profile: FeatureProfile::Mvp,
+        ..Default::default()
+    };
+    let outcome = MergeConfiguration::new(modules, options).merge();
+    assert!(matches!(
+        outcome,
+        Err(MergeError::MergedFeatureNotAllowed(feature)) if feature == "multi-memory"
+    ));
+
+    let wasm_caller = parse_str(
+        r#"
+        (module
+          (import "callee" "answer" (func $answer (result i32)))
+          (func (export "call") (result i32)
+            (call $answer)))"#,
+    )?;
+    let wasm_callee =
+        parse_str(r#"(module (func (export "answer") (result i32) (i32.const 42)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("caller", &wasm_caller),
+        &NamedModule::new("callee", &wasm_callee),
+    ];
+
+    let options = MergeOptions {
+        feature_profile: FeatureProfile::Wasm2,
+        tailcall_optimize: true,
+        ..Default::default()
+    };
+    let outcome = MergeConfiguration::new(modules, options).merge();
+    assert!(matches!(
+        outcome,
+        Err(MergeError::MergedFeatureNotAllowed(feature)) if feature == "tail-call"
+    ));
+
+    let options = MergeOptions {
+        feature_profile: FeatureProfile::Wasm2,
+        ..Default::default()
+    };
+    MergeConfiguration::new(modules, options).merge()?;
+
+    Ok(())
+}
+
+/// An active data segment past its memory's maximum is signalled.
+#[test]
+fn data_offset_overflow() -> Result<(), Error> {
+    use wasm_mergers::error::Error as MergeError;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "ok"))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (memory 1 1)
+          (data (i32.const 65530) "0123456789"))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let outcome = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(outcome, Err(MergeError::DataOffsetOverflow(module)) if module == "B"));
+
+    // Without the second module, the segment fits
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+    MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    Ok(())
+}
+
+/// Two exports of one function remain exports of a single merged function.
+#[test]
+fn aliased_exports() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (global $count (mut i32) (i32.const 0))
+          (func $f (result i32)
+            (global.set $count (i32.add (global.get $count) (i32.const 1)))
+            (global.get $count))
+          (export "a" (func $f))
+          (export "b" (func $f)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "a" (func $a (result i32)))
+          (import "A" "b" (func $b (result i32)))
+          (func (export "both") (result i32)
+            (i32.add (call $a) (call $b))))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("A".to_string().into(), "a".into());
+    keep_exports.keep_function("A".to_string().into(), "b".into());
+    let options = MergeOptions {
+        keep_exports: Some(keep_exports),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    assert_eq!(parsed.funcs.iter().count(), 2);
+    assert_eq!(parsed.exports.get_func("a")?, parsed.exports.get_func("b")?);
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    declare_fns_from_wasm! { instance, store,
+        a [] [i32],
+        b [] [i32],
+        both [] [i32]
+    };
+
+    // Both names share the counter of the one function
+    assert_eq!(wasm_call!(store, a), 1);
+    assert_eq!(wasm_call!(store, b), 2);
+    assert_eq!(wasm_call!(store, both), 3 + 4);
+
+    Ok(())
+}
+
+/// Modules can be configured from `(name, bytes)` pairs directly.
+#[test]
+fn merge_from_named_bytes() -> Result<(), Error> {
+    let wasm_even = parse_str(
+        r#"
+        (module
+          (import "odd" "odd" (func $odd (param i32) (result i32)))
+          (func (export "even") (param i32) (result i32)
+            (if (result i32) (i32.eqz (local.get 0))
+              (then (i32.const 1))
+              (else (call $odd (i32.sub (local.get 0) (i32.const 1)))))))"#,
+    )?;
+    let wasm_odd = parse_str(
+        r#"
+        (module
+          (import "even" "even" (func $even (param i32) (result i32)))
+          (func (export "odd") (param i32) (result i32)
+            (if (result i32) (i32.eqz (local.get 0))
+              (then (i32.const 0))
+              (else (call $even (i32.sub (local.get 0) (i32.const 1)))))))"#,
+    )?;
+
+    let keep_options = || {
+        let mut keep_exports = KeepExports::default();
+        keep_exports.keep_function("even".to_string().into(), "even".into());
+        keep_exports.keep_function("odd".to_string().into(), "odd".into());
+        MergeOptions {
+            keep_exports: Some(keep_exports),
+            ..Default::default()
+        }
+    };
+
+    let named_bytes: &[(&str, &[u8])] = &[("even", &wasm_even), ("odd", &wasm_odd)];
+    let merged_pairs = MergeConfiguration::from_named_bytes(named_bytes, keep_options()).merge()?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("even", &wasm_even),
+        &NamedModule::new("odd", &wasm_odd),
+    ];
+    let merged_slice = MergeConfiguration::new(modules, keep_options()).merge()?;
+
+    for merged in [merged_pairs, merged_slice] {
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+
+        declare_fns_from_wasm! { instance, store,
+            even [i32] [i32],
+            odd [i32] [i32]
+        };
+
+        for n in 0..100 {
+            assert_eq!(wasm_call!(store, even, n), i32::from(n % 2 == 0));
+            assert_eq!(wasm_call!(store, odd, n), i32::from(n % 2 == 1));
+        }
+    }
+
+    Ok(())
+}
+
+/// The signatures of remaining imports & exports are reported.
+#[test]
+fn report_remaining_signatures() -> Result<(), Error> {
+    use walrus::ValType;
+
+    let wasm_consumer = parse_str(
+        r#"
+        (module
+          (import "host" "log" (func $log (param i32 f64) (result i64)))
+          (import "host" "base" (global $base i32))
+          (func (export "run") (param f64) (result i64)
+            (call $log (global.get $base) (local.get 0))))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("consumer", &wasm_consumer)];
+    let (_, report) =
+        MergeConfiguration::new(modules, MergeOptions::default()).merge_with_report()?;
+
+    assert!(!report.is_self_contained);
+
+    let log = &report.function_imports[&("host".to_string(), "log".to_string())];
+    assert_eq!(log.params(), &[ValType::I32, ValType::F64]);
+    assert_eq!(log.results(), &[ValType::I64]);
+
+    let base = report.global_imports[&("host".to_string(), "base".to_string())];
+    assert_eq!(base, ValType::I32);
+
+    let run = &report.function_exports["run"];
+    assert_eq!(run.params(), &[ValType::F64]);
+    assert_eq!(run.results(), &[ValType::I64]);
+    assert!(report.global_exports.is_empty());
+
+    Ok(())
+}
+
+/// The number of entries in the type section of a binary module.
+fn type_section_len(wasm: &[u8]) -> u64 {
+    fn read_leb(bytes: &[u8], position: &mut usize) -> u64 {
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let byte = bytes[*position];
+            *position += 1;
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return result;
+            }
+            shift += 7;
+        }
+    }
+
+    let mut position = 8; // Skip magic & version
+    while position < wasm.len() {
+        let id = wasm[position];
+        position += 1;
+        let size = read_leb(wasm, &mut position);
+        if id == 1 {
+            return read_leb(wasm, &mut position);
+        }
+        position += usize::try_from(size).unwrap();
+    }
+    0
+}
+
+/// Signatures shared by imports, locals and indirect calls are added once.
+#[test]
+fn distinct_signatures_only() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (type $unary (func (param i32) (result i32)))
+          (import "host" "double" (func $double (type $unary)))
+          (table 1 funcref)
+          (elem (i32.const 0) $double)
+          (func (export "apply") (param i32) (result i32)
+            (call_indirect (type $unary) (local.get 0) (i32.const 0)))
+          (func (export "pair") (result i32 i32)
+            (i32.const 1) (i32.const 2)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "apply" (func $apply (param i32) (result i32)))
+          (import "host" "triple" (func $triple (param i32) (result i32)))
+          (func (export "both") (param i32) (result i32)
+            (call $triple (call $apply (local.get 0))))
+          (func (export "swap") (param i32 i32) (result i32 i32)
+            (local.get 1) (local.get 0)))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    // (i32) -> i32, () -> (i32 i32) and (i32 i32) -> (i32 i32)
+    assert_eq!(type_section_len(&merged), 3);
+
+    Ok(())
+}
+
+/// Merging empty modules without custom sections yields a bare module.
+#[test]
+fn minimal_empty_merge() -> Result<(), Error> {
+    let wasm_a = parse_str("(module)")?;
+    let wasm_b = parse_str(r#"(module $b (@custom "meta" "content"))"#)?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        strip_custom_sections: true,
+        emit_producers: false,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    // Only the magic number & version remain
+    assert_eq!(merged, b"\0asm\x01\0\0\0");
+
+    let store = Store::<()>::default();
+    Module::from_binary(store.engine(), &merged)?;
+
+    Ok(())
+}
+
+/// A reference-typed `select` keeps its type across the merge.
+#[test]
+fn typed_select_validates() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "pick") (param externref externref i32) (result externref)
+            (select (result externref) (local.get 0) (local.get 1) (local.get 2))))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "pick" (func $pick (param externref externref i32) (result externref)))
+          (func (export "is_first_null") (param externref i32) (result i32)
+            (ref.is_null (call $pick (ref.null extern) (local.get 0) (local.get 1)))))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let mut store = Store::<()>::default();
+    Module::validate(store.engine(), &merged)?;
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    let is_first_null = instance
+        .get_typed_func::<(Option<Rooted<ExternRef>>, i32), i32>(&mut store, "is_first_null")?;
+    let value = ExternRef::new(&mut store, 42)?;
+    assert_eq!(is_first_null.call(&mut store, (Some(value), 1))?, 1);
+    assert_eq!(is_first_null.call(&mut store, (Some(value), 0))?, 0);
+
+    Ok(())
+}
+
+/// Requiring a self-contained merge lists the imports left without provider.
+#[test]
+fn require_self_contained() -> Result<(), Error> {
+    use wasm_mergers::error::Error as MergeError;
+    use wasm_mergers::kinds::ExportKind;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) (i32.const 1)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (result i32)))
+          (import "C" "g" (func $g (result i32)))
+          (func (export "h") (result i32)
+            (i32.add (call $f) (call $g))))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        require_self_contained: true,
+        ..Default::default()
+    };
+
+    let outcome = MergeConfiguration::new(modules, options.clone()).merge();
+    let Err(MergeError::UnresolvedImports(unresolved)) = outcome else {
+        panic!("expected unresolved imports, got {outcome:?}");
+    };
+    let expected = ("C".to_string(), "g".to_string(), ExportKind::Function);
+    assert_eq!(unresolved, vec![expected]);
+
+    // Once a provider is present, the merge succeeds
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (func (export "g") (result i32) (i32.const 2)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+    MergeConfiguration::new(modules, options).merge()?;
+
+    Ok(())
+}
+
+/// Identifiers are ordered by their names.
+#[test]
+fn identifiers_order_lexicographically() {
+    use wasm_mergers::kinds::{Function, IdentifierItem, IdentifierModule};
+
+    let mut modules: Vec<IdentifierModule> = ["b", "a", "B", "ab"].map(Into::into).to_vec();
+    modules.sort();
+    let expected: Vec<IdentifierModule> = ["B", "a", "ab", "b"].map(Into::into).to_vec();
+    assert_eq!(modules, expected);
+
+    let mut items: Vec<IdentifierItem<Function>> =
+        ["g", "f"].map(|name| name.to_string().into()).to_vec();
+    items.sort();
+    let names: Vec<String> = items.into_iter().map(Into::into).collect();
+    assert_eq!(names, ["f", "g"]);
+}
+
+/// Identical imports collapse into one, unless deduplication is disabled.
+#[test]
+fn dedupe_identical_imports() -> Result<(), Error> {
+    let gen_wat = |name: &str| {
+        format!(
+            r#"
+            (module
+              (import "env" "abort" (func $abort (param i32)))
+              (func (export "fail_{name}") (param i32)
+                (call $abort (local.get 0))))"#
+        )
+    };
+    let wasm_a = parse_str(gen_wat("a"))?;
+    let wasm_b = parse_str(gen_wat("b"))?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let count_imports = |options: MergeOptions| -> Result<usize, Error> {
+        let merged = MergeConfiguration::new(modules, options).merge()?;
+        let merged = walrus::Module::from_buffer(&merged)?;
+        Ok(merged.imports.iter().count())
+    };
+
+    assert_eq!(count_imports(MergeOptions::default())?, 1);
+
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            dedupe_imports: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(count_imports(options)?, 2);
+
+    Ok(())
+}
+
+/// Imports can be queried for the source they reduced to.
+#[test]
+fn query_import_resolution() -> Result<(), Error> {
+    use wasm_mergers::ResolutionTarget;
+
+    let wasm_fib = parse_str(
+        r#"
+        (module
+          (import "indirect_fib" "indirect_fib" (func $indirect_fib (param i32) (result i32)))
+          (import "host" "trace" (func $trace (param i32)))
+          (func $fib (param $n i32) (result i32)
+            (call $trace (local.get $n))
+            (if (result i32) (i32.lt_s (local.get $n) (i32.const 2))
+              (then (local.get $n))
+              (else
+                (i32.add
+                  (call $indirect_fib (i32.sub (local.get $n) (i32.const 1)))
+                  (call $indirect_fib (i32.sub (local.get $n) (i32.const 2)))))))
+          (export "fib" (func $fib)))"#,
+    )?;
+    let wasm_indirect_fib = parse_str(
+        r#"
+        (module
+          (import "fib" "fib" (func $fib (param i32) (result i32)))
+          (export "indirect_fib" (func $fib)))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("fib", &wasm_fib),
+        &NamedModule::new("indirect_fib", &wasm_indirect_fib),
+    ];
+    let (_, report) =
+        MergeConfiguration::new(modules, MergeOptions::default()).merge_with_report()?;
+
+    // `$fib` follows the two imports in the function index space of `fib`
+    let fib_local = ResolutionTarget::Local {
+        module: "fib".to_string(),
+        index: 2,
+    };
+    assert_eq!(report.resolves_to("indirect_fib", "fib"), Some(&fib_local));
+    assert_eq!(report.resolves_to("fib", "indirect_fib"), Some(&fib_local));
+
+    let trace = ResolutionTarget::RemainingImport {
+        module: "host".to_string(),
+        name: "trace".to_string(),
+    };
+    assert_eq!(report.resolves_to("fib", "trace"), Some(&trace));
+    assert_eq!(report.resolves_to("fib", "absent"), None);
+
+    Ok(())
+}
+
+/// Reads of constant globals can be inlined, leaving the global unused.
+#[test]
+fn inline_const_globals() -> Result<(), Error> {
+    use walrus::ir::{Instr, Value};
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (global $answer i32 (i32.const 42))
+          (global $counter (mut i32) (i32.const 0))
+          (func (export "answer") (result i32)
+            (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+            (global.get $answer)))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+    let options = MergeOptions {
+        inline_const_globals: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut parsed = walrus::Module::from_buffer(&merged)?;
+    let answer = parsed.exports.get_func("answer")?;
+    let body = parsed.funcs.get(answer).kind.unwrap_local();
+    let instructions: Vec<_> = body
+        .block(body.entry_block())
+        .instrs
+        .iter()
+        .map(|(instr, _)| instr.clone())
+        .collect();
+
+    // Only the mutable counter is still read
+    let global_reads = instructions
+        .iter()
+        .filter(|instr| matches!(instr, Instr::GlobalGet(_)))
+        .count();
+    assert_eq!(global_reads, 1);
+    assert!(matches!(
+        instructions.last(),
+        Some(Instr::Const(constant)) if matches!(constant.value, Value::I32(42))
+    ));
+
+    walrus::passes::gc::run(&mut parsed);
+    assert_eq!(parsed.globals.iter().count(), 1);
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, answer [] [i32] };
+    assert_eq!(wasm_call!(store, answer), 42);
+
+    Ok(())
+}
+
+/// Reads of a global initialised by an extended constant expression are
+/// inlined with the value of that expression.
+#[test]
+fn inline_const_globals_extended() -> Result<(), Error> {
+    use walrus::ir::{Instr, Value};
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (global $answer i32 (i32.add (i32.const 40) (i32.const 2)))
+          (func (export "answer") (result i32)
+            (global.get $answer)))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+    let options = MergeOptions {
+        inline_const_globals: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let answer = parsed.exports.get_func("answer")?;
+    let body = parsed.funcs.get(answer).kind.unwrap_local();
+    let instructions: Vec<_> = body
+        .block(body.entry_block())
+        .instrs
+        .iter()
+        .map(|(instr, _)| instr.clone())
+        .collect();
+    assert!(matches!(
+        &instructions[..],
+        [Instr::Const(constant)] if matches!(constant.value, Value::I32(42))
+    ));
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, answer [] [i32] };
+    assert_eq!(wasm_call!(store, answer), 42);
+
+    Ok(())
+}
+
+/// Reads of a constant global imported from another module are inlined,
+/// whether the providing module comes before or after the consumer.
+#[test]
+fn inline_const_globals_across_modules() -> Result<(), Error> {
+    use walrus::ir::{Instr, Value};
+
+    let wasm_provider = parse_str(
+        r#"
+        (module
+          (global (export "answer") i32 (i32.add (i32.const 40) (i32.const 2))))"#,
+    )?;
+    let wasm_consumer = parse_str(
+        r#"
+        (module
+          (import "provider" "answer" (global $answer i32))
+          (func (export "answer") (result i32)
+            (global.get $answer)))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("provider", &wasm_provider),
+        &NamedModule::new("consumer", &wasm_consumer),
+    ];
+    let options = MergeOptions {
+        inline_const_globals: true,
+        ..Default::default()
+    };
+    for modules in iter_permutations(modules) {
+        let merged = MergeConfiguration::new(&modules, options.clone()).merge()?;
+
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        let answer = parsed.exports.get_func("answer")?;
+        let body = parsed.funcs.get(answer).kind.unwrap_local();
+        let instructions: Vec<_> = body
+            .block(body.entry_block())
+            .instrs
+            .iter()
+            .map(|(instr, _)| instr.clone())
+            .collect();
+        assert!(matches!(
+            &instructions[..],
+            [Instr::Const(constant)] if matches!(constant.value, Value::I32(42))
+        ));
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, answer [] [i32] };
+        assert_eq!(wasm_call!(store, answer), 42);
+    }
+
+    Ok(())
+}
+
+/// Merges exceeding the output budget are signalled.
+#[test]
+fn output_size_budget() -> Result<(), Error> {
+    use wasm_mergers::error::Error as MergeError;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) (i32.const 42)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+
+    let options = MergeOptions {
+        max_output_bytes: Some(16),
+        ..Default::default()
+    };
+    let outcome = MergeConfiguration::new(modules, options).merge();
+    assert!(matches!(
+        outcome,
+        Err(MergeError::OutputTooLarge { actual, limit: 16 }) if actual > 16
+    ));
+
+    let options = MergeOptions {
+        max_output_bytes: Some(1024),
+        ..Default::default()
+    };
+    MergeConfiguration::new(modules, options).merge()?;
+
+    Ok(())
+}
+
+/// Element segments of expressions keep their null and function slots.
+#[test]
+fn element_expressions_with_nulls() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "one") (result i32) (i32.const 1)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "one" (func $one (result i32)))
+          (func $two (result i32) (i32.const 2))
+          (table $slots 4 funcref)
+          (elem (table $slots) (i32.const 0) funcref
+            (ref.null func) (ref.func $one) (ref.null func) (ref.func $two))
+          (func (export "is_null") (param i32) (result i32)
+            (ref.is_null (table.get $slots (local.get 0))))
+          (func (export "call_slot") (param i32) (result i32)
+            (call_indirect $slots (result i32) (local.get 0))))"#,
+    )?;
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    declare_fns_from_wasm! { instance, store,
+        is_null [i32] [i32],
+        call_slot [i32] [i32]
+    };
+
+    assert_eq!(wasm_call!(store, is_null, 0), 1);
+    assert_eq!(wasm_call!(store, is_null, 1), 0);
+    assert_eq!(wasm_call!(store, is_null, 2), 1);
+    assert_eq!(wasm_call!(store, is_null, 3), 0);
+    assert_eq!(wasm_call!(store, call_slot, 1), 1);
+    assert_eq!(wasm_call!(store, call_slot, 3), 2);
+
+    Ok(())
+}
+
+/// Modules with generated, owned names can be merged.
+#[test]
+fn merge_owned_modules() -> Result<(), Error> {
+    use wasm_mergers::OwnedNamedModule;
+
+    // Each module `m{i}` adds one to the result of its predecessor
+    let mut modules: Vec<OwnedNamedModule> = vec![];
+    for i in 0..4 {
+        let wat = if i == 0 {
+            r#"(module (func (export "count") (result i32) (i32.const 1)))"#.to_string()
+        } else {
+            let previous = i - 1;
+            format!(
+                r#"
+                (module
+                  (import "m{previous}" "count" (func $previous (result i32)))
+                  (func (export "count") (result i32)
+                    (i32.add (call $previous) (i32.const 1))))"#
+            )
+        };
+        modules.push(OwnedNamedModule::owned(format!("m{i}"), parse_str(wat)?));
+    }
+
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("m3".to_string().into(), "count".into());
+    let options = MergeOptions {
+        keep_exports: Some(keep_exports),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::from_owned(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, count [] [i32] };
+    assert_eq!(wasm_call!(store, count), 4);
+
+    Ok(())
+}
+
+/// Structural linking allows subtypes for functions, tables stay invariant.
+#[test]
+fn structural_link_subtypes() -> Result<(), Error> {
+    use wasm_mergers::error::Error as MergeError;
+    use wasm_mergers::merge_options::LinkTypeMismatch;
+
+    // The export results in `nullfuncref`, a subtype of `funcref`
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "make") (result nullfuncref) (ref.null nofunc)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "make" (func $make (result funcref)))
+          (func (export "made_null") (result i32)
+            (ref.is_null (call $make))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let outcome = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(outcome, Err(MergeError::TypeMismatch(_))));
+
+    let structural = MergeOptions {
+        link_type_mismatch: LinkTypeMismatch::Structural,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, structural.clone()).merge()?;
+
+    // The call now yields the more specific type, the merged module validates
+    let merged = walrus::Module::from_buffer(&merged)?;
+    assert_eq!(merged.imports.iter().count(), 0);
+
+    // Tables are read & written, their element types must be equivalent
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (table (export "t") 1 nullfuncref))"#,
+    )?;
+    let wasm_d = parse_str(
+        r#"
+        (module
+          (import "C" "t" (table 1 funcref)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("C", &wasm_c),
+        &NamedModule::new("D", &wasm_d),
+    ];
+    let outcome = MergeConfiguration::new(modules, structural).merge();
+    assert!(matches!(outcome, Err(MergeError::TypeMismatch(_))));
+
+    Ok(())
+}
+
+/// Tree shaking removes unreachable functions, but never kept exports.
+#[test]
+fn tree_shake_retains_kept_exports() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func $unused (result i32) (i32.const 0))
+          (func (export "kept") (result i32) (i32.const 42))
+          (func (export "linked") (result i32) (i32.const 1)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "linked" (func $linked (result i32)))
+          (func (export "uses_linked") (result i32) (call $linked)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("A".to_string().into(), "kept".into());
+    let options = MergeOptions {
+        keep_exports: Some(keep_exports),
+        tree_shake: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    // `$unused` is gone, `kept` survives despite no module calling it
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    assert_eq!(parsed.funcs.iter().count(), 3);
+    let exports = parsed.exports.iter().map(|export| &export.name).sorted();
+    assert_eq!(exports.collect::<Vec<_>>(), ["kept", "uses_linked"]);
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store,
+        kept [] [i32],
+        uses_linked [] [i32]
+    };
+    assert_eq!(wasm_call!(store, kept), 42);
+    assert_eq!(wasm_call!(store, uses_linked), 1);
+
+    Ok(())
+}
+
+/// Atomic waits & notifies target the right shared memory after reindexing.
+#[test]
+fn atomic_wait_notify_shared_memory() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory 1 1 shared)
+          (func (export "store_a") (param i32 i32)
+            (i32.atomic.store (local.get 0) (local.get 1))))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (memory 1 1 shared)
+          (func (export "store_b") (param i32 i64)
+            (i64.atomic.store (local.get 0) (local.get 1)))
+          (func (export "notify") (param i32) (result i32)
+            (memory.atomic.notify (local.get 0) (i32.const 1)))
+          (func (export "wait32") (param i32 i32) (result i32)
+            (memory.atomic.wait32 (local.get 0) (local.get 1) (i64.const 0)))
+          (func (export "wait64") (param i32 i64) (result i32)
+            (memory.atomic.wait64 (local.get 0) (local.get 1) (i64.const 0))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let mut config = Config::new();
+    config.wasm_threads(true).shared_memory(true);
+    let engine = Engine::new(&config)?;
+    let mut store = Store::new(&engine, ());
+    let module = Module::from_binary(&engine, &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    declare_fns_from_wasm! { instance, store,
+        store_a [i32, i32] [],
+        store_b [i32, i64] [],
+        notify [i32] [i32],
+        wait32 [i32, i32] [i32],
+        wait64 [i32, i64] [i32]
+    };
+
+    const NOT_EQUAL: i32 = 1;
+    const TIMED_OUT: i32 = 2;
+
+    // Writes to A's memory are not observed by the waits on B's memory
+    wasm_call!(store, store_a, 0, 7);
+    assert_eq!(wasm_call!(store, wait32, 0, 7), NOT_EQUAL);
+    assert_eq!(wasm_call!(store, wait32, 0, 0), TIMED_OUT);
+
+    // The 64-bit wait compares the full 64-bit value
+    wasm_call!(store, store_b, 8, 1 << 32);
+    assert_eq!(wasm_call!(store, wait64, 8, 0), NOT_EQUAL);
+    assert_eq!(wasm_call!(store, wait64, 8, 1 << 32), TIMED_OUT);
+
+    // Without waiters, none are woken
+    assert_eq!(wasm_call!(store, notify, 0), 0);
+
+    Ok(())
+}
+
+/// Exports can be given an explicit final name.
+#[test]
+fn explicit_export_renames() -> Result<(), Error> {
+    let gen_wat = |value: i32| {
+        format!(
+            r#"
+            (module
+              (func (export "get") (result i32) (i32.const {value}))
+              (func (export "other") (result i32) (i32.const 0)))"#
+        )
+    };
+    let wasm_a = parse_str(gen_wat(1))?;
+    let wasm_b = parse_str(gen_wat(2))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let mut options = MergeOptions::default();
+    let renames = [
+        ("A", "get", "get_one"),
+        ("B", "get", "get_two"),
+        ("B", "other", "zero"),
+    ];
+    for (module, name, new_name) in renames {
+        let key = (module.to_string(), name.to_string());
+        options.export_renames.insert(key, new_name.to_string());
+    }
+
+    // Module A's `other` keeps its name, the renames resolve all clashes
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let exports = parsed.exports.iter().map(|export| &export.name).sorted();
+    assert_eq!(
+        exports.collect::<Vec<_>>(),
+        ["get_one", "get_two", "other", "zero"]
+    );
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store,
+        get_one [] [i32],
+        get_two [] [i32]
+    };
+    assert_eq!(wasm_call!(store, get_one), 1);
+    assert_eq!(wasm_call!(store, get_two), 2);
+
+    Ok(())
+}
+
+/// Renaming a kept export is reported, as hosts may bind to its original name.
+#[test]
+fn kept_export_renamed_warning() -> Result<(), Error> {
+    use wasm_mergers::MergeWarning;
+
+    let gen_wat =
+        |value: i32| format!(r#"(module (func (export "get") (result i32) (i32.const {value})))"#);
+    let wasm_a = parse_str(gen_wat(1))?;
+    let wasm_b = parse_str(gen_wat(2))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("A".to_string().into(), "get".into());
+    keep_exports.keep_function("B".to_string().into(), "get".into());
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+            ..Default::default()
+        },
+        keep_exports: Some(keep_exports),
+        ..Default::default()
+    };
+
+    let (_, report) = MergeConfiguration::new(modules, options).merge_with_report()?;
+    assert_eq!(report.warnings.len(), 2);
+    for module in ["A", "B"] {
+        let renamed = MergeWarning::KeptExportRenamed {
+            module: module.to_string(),
+            original: "get".to_string(),
+            new: format!("{module}:get"),
+        };
+        assert!(report.warnings.contains(&renamed));
+    }
+
+    // Without a clash, kept exports keep their name
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("A".to_string().into(), "get".into());
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+            ..Default::default()
+        },
+        keep_exports: Some(keep_exports),
+        ..Default::default()
+    };
+    let (_, report) = MergeConfiguration::new(modules, options).merge_with_report()?;
+    assert!(report.warnings.is_empty());
+
+    Ok(())
+}
+
+/// A patch describes only what merging plugins adds over the base.
+#[test]
+fn merge_patch_over_base() -> Result<(), Error> {
+    use wasm_mergers::MergePatch;
+    use wasm_mergers::kinds::ExportKind;
+
+    let base = parse_str(
+        r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "run") (result i32) (i32.const 0)))"#,
+    )?;
+    let plugin = parse_str(
+        r#"
+        (module
+          (import "base" "run" (func $run (result i32)))
+          (memory 1)
+          (data (i32.const 0) "plugin")
+          (func (export "plugin_run") (result i32) (call $run)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("base", &base),
+        &NamedModule::new("plugin", &plugin),
+    ];
+
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    let patch = configuration.merge_patch("base")?;
+    let expected = MergePatch {
+        // The plugin's import of `run` is resolved, not added
+        added_functions: vec![("plugin".to_string(), 1)],
+        added_globals: vec![],
+        added_data_segments: vec![("plugin".to_string(), 0)],
+        resolved_imports: vec![],
+        added_exports: vec![("plugin_run".to_string(), ExportKind::Function)],
+        // Only the plugin uses `run`, which is hence no longer exported
+        removed_exports: vec![("run".to_string(), ExportKind::Function)],
+    };
+    assert_eq!(patch, expected);
+
+    // Base imports that a plugin provides are resolved rather than added
+    let importing_base = parse_str(
+        r#"
+        (module
+          (import "plugin" "plugin_run" (func $plugin_run (result i32)))
+          (func (export "run") (result i32) (call $plugin_run)))"#,
+    )?;
+    let plugin = parse_str(
+        r#"
+        (module
+          (func (export "plugin_run") (result i32) (i32.const 1)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("base", &importing_base),
+        &NamedModule::new("plugin", &plugin),
+    ];
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    let patch = configuration.merge_patch("base")?;
+    let expected = MergePatch {
+        added_functions: vec![("plugin".to_string(), 0)],
+        added_globals: vec![],
+        added_data_segments: vec![],
+        resolved_imports: vec![(
+            "plugin".to_string(),
+            "plugin_run".to_string(),
+            ExportKind::Function,
+        )],
+        // The plugin's exports are only used by the base
+        added_exports: vec![],
+        removed_exports: vec![],
+    };
+    assert_eq!(patch, expected);
+
+    // Merging the base alone adds nothing
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("base", &base)];
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    assert!(configuration.merge_patch("base")?.is_empty());
+    assert!(matches!(
+        configuration.merge_patch("absent"),
+        Err(wasm_mergers::error::Error::UnknownModule(_))
+    ));
+
+    Ok(())
+}
+
+/// A global that the base imports from a plugin is resolved to the plugin's
+/// definition, which the patch adds.
+#[test]
+fn merge_patch_resolves_globals() -> Result<(), Error> {
+    use wasm_mergers::MergePatch;
+    use wasm_mergers::kinds::ExportKind;
+
+    let base = parse_str(
+        r#"
+        (module
+          (global (import "plugin" "offset") i32)
+          (func (export "run") (result i32) (global.get 0)))"#,
+    )?;
+    let plugin = parse_str(
+        r#"
+        (module
+          (global (export "offset") i32 (i32.const 8)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("base", &base),
+        &NamedModule::new("plugin", &plugin),
+    ];
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    let patch = configuration.merge_patch("base")?;
+    let expected = MergePatch {
+        added_functions: vec![],
+        added_globals: vec![("plugin".to_string(), 0)],
+        added_data_segments: vec![],
+        resolved_imports: vec![(
+            "plugin".to_string(),
+            "offset".to_string(),
+            ExportKind::Global,
+        )],
+        // The plugin's export is only used by the base
+        added_exports: vec![],
+        removed_exports: vec![],
+    };
+    assert_eq!(patch, expected);
+
+    Ok(())
+}
+
+/// Memories keep their page size. Custom page sizes are not (yet) supported
+/// by the parser and are reported as such, rather than being lost.
+#[test]
+fn custom_page_sizes() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory (export "memory_a") 2)
+          (func (export "size_a") (result i32) (memory.size))
+          (func (export "store_a") (param i32) (i32.store8 (local.get 0) (i32.const 1))))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (memory (export "memory_b") 1)
+          (func (export "size_b") (result i32) (memory.size))
+          (func (export "store_b") (param i32) (i32.store8 (local.get 0) (i32.const 1))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    assert!(
+        parsed
+            .memories
+            .iter()
+            .all(|memory| memory.page_size_log2.is_none())
+    );
+
+    let mut config = Config::new();
+    config.wasm_multi_memory(true);
+    let engine = Engine::new(&config)?;
+    let mut store = Store::new(&engine, ());
+    let module = Module::from_binary(&engine, &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store,
+        size_a [] [i32],
+        size_b [] [i32],
+        store_a [i32] [],
+        store_b [i32] []
+    };
+    assert_eq!(wasm_call!(store, size_a), 2);
+    assert_eq!(wasm_call!(store, size_b), 1);
+    wasm_call!(store, store_a, 2 * 65536 - 1);
+    assert!(store_a.call(&mut store, 2 * 65536).is_err());
+    wasm_call!(store, store_b, 65535);
+    assert!(store_b.call(&mut store, 65536).is_err());
+
+    // A custom page size is rejected when parsing
+    let wasm_c = parse_str(r#"(module (memory (export "memory_c") 2 (pagesize 1)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+    let result = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(result, Err(wasm_mergers::error::Error::Parse(_))));
+
+    Ok(())
+}
+
+/// Element segments are linked to their table, regardless of the textual
+/// order in which they are declared.
+#[test]
+fn element_segments_before_tables() -> Result<(), Error> {
+    let gen_wat = |value: i32| {
+        format!(
+            r#"
+            (module
+              (elem (table $second) (i32.const 0) func $second)
+              (elem (table $first) (i32.const 1) func $first)
+              (type $get (func (result i32)))
+              (func $first (result i32) (i32.const {value}))
+              (func $second (result i32) (i32.add (i32.const {value}) (i32.const 10)))
+              (table $first 2 funcref)
+              (table $second 1 funcref)
+              (func (export "call_first") (result i32)
+                (call_indirect $first (type $get) (i32.const 1)))
+              (func (export "call_second") (result i32)
+                (call_indirect $second (type $get) (i32.const 0))))"#
+        )
+    };
+    let wasm_a = parse_str(gen_wat(1))?;
+    let wasm_b = parse_str(gen_wat(2))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    // Each table holds exactly the segment targeting it
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    assert_eq!(parsed.tables.iter().count(), 4);
+    for table in parsed.tables.iter() {
+        assert_eq!(table.elem_segments.len(), 1);
+        for element_id in &table.elem_segments {
+            let element = parsed.elements.get(*element_id);
+            assert!(matches!(
+                element.kind,
+                walrus::ElementKind::Active { table: target, .. } if target == table.id()
+            ));
+        }
+    }
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    for (name, expected) in [
+        ("A:call_first", 1),
+        ("A:call_second", 11),
+        ("B:call_first", 2),
+        ("B:call_second", 12),
+    ] {
+        let function = instance.get_typed_func::<(), i32>(&mut store, name)?;
+        assert_eq!(function.call(&mut store, ())?, expected);
+    }
+
+    Ok(())
+}
+
+/// The merged module is checked to instantiate with the given host imports.
+#[cfg(feature = "instantiate-check")]
+#[test]
+fn merge_and_check_host_imports() -> Result<(), Error> {
+    use wasm_mergers::kinds::ExportKind;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (import "host" "log" (func $log (param i32) (result i32)))
+          (func (export "f") (result i32) (call $log (i32.const 1))))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (result i32)))
+          (func (export "g") (result i32) (call $f)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let host_imports = [("host", "log", ExportKind::Function)];
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    configuration.merge_and_check(&host_imports)?;
+
+    // Omitting the host import fails the check
+    let result = configuration.merge_and_check(&[]);
+    let Err(wasm_mergers::error::Error::UnresolvedImports(missing)) = result else {
+        panic!("Expected unresolved imports, got {result:?}");
+    };
+    assert_eq!(
+        missing,
+        [("host".to_string(), "log".to_string(), ExportKind::Function)]
+    );
+
+    Ok(())
+}
+
+/// Unused imports are kept when tree shaking if requested.
+#[test]
+fn keep_unused_imports() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (import "host" "side_effect" (func))
+          (import "host" "used" (func $used (result i32)))
+          (func (export "f") (result i32) (call $used)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+
+    let imports_after = |keep_unused_imports| -> Result<Vec<String>, Error> {
+        let options = MergeOptions {
+            tree_shake: true,
+            keep_unused_imports,
+            ..Default::default()
+        };
+        let merged = MergeConfiguration::new(modules, options).merge()?;
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        assert_eq!(parsed.exports.iter().count(), 1);
+        let imports = parsed.imports.iter().map(|import| import.name.clone());
+        Ok(imports.sorted().collect())
+    };
+
+    assert_eq!(imports_after(true)?, ["side_effect", "used"]);
+    assert_eq!(imports_after(false)?, ["used"]);
+
+    Ok(())
+}
+
+/// Invalid input modules are reported by name.
+#[test]
+fn validate_inputs() -> Result<(), Error> {
+    let valid = parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+    // Structurally fine but ill-typed: the function returns nothing
+    let invalid = parse_str(r#"(module (func (export "g") (result i32)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("valid", &valid),
+        &NamedModule::new("invalid", &invalid),
+    ];
+
+    let configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    let result = configuration.validate_inputs();
+    let Err(wasm_mergers::error::Error::InvalidInput { module, .. }) = result else {
+        panic!("Expected an invalid input, got {result:?}");
+    };
+    assert_eq!(module, "invalid");
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("valid", &valid)];
+    let configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    configuration.validate_inputs()?;
+
+    Ok(())
+}
+
+/// Exported functions are collected in an exported function table.
+#[test]
+fn export_function_table() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "one") (result i32) (i32.const 1))
+          (func (export "two") (result i32) (i32.const 2)))"#,
+    )?;
+    let wasm_b = parse_str(r#"(module (func (export "three") (result i32) (i32.const 3)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        export_function_table: Some("functions".to_string()),
+        ..Default::default()
+    };
+    let (merged, report) = MergeConfiguration::new(modules, options).merge_with_report()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let table = instance
+        .get_table(&mut store, "functions")
+        .expect("function table is exported");
+    assert_eq!(table.size(&store), 3);
+    for (name, expected) in [("one", 1), ("two", 2), ("three", 3)] {
+        let slot = report.function_table[name];
+        let function = table
+            .get(&mut store, slot.into())
+            .and_then(|slot| slot.as_func().flatten().copied())
+            .expect("slot holds a function");
+        let function = function.typed::<(), i32>(&store)?;
+        assert_eq!(function.call(&mut store, ())?, expected);
+    }
+
+    // The table name cannot be taken by an existing export
+    let options = MergeOptions {
+        export_function_table: Some("one".to_string()),
+        ..Default::default()
+    };
+    let result = MergeConfiguration::new(modules, options).merge();
+    assert!(matches!(
+        result,
+        Err(wasm_mergers::error::Error::ReservedExportName(_))
+    ));
+
+    Ok(())
+}
+
+/// Blocks, loops and ifs with multiple results keep their type.
+#[test]
+fn multi_value_blocks() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "pair") (param i32) (result i32 i64)
+            (block (result i32 i64)
+              (local.get 0)
+              (i64.const 2)))
+          (func (export "looped") (result i32 i64)
+            (loop (result i32 i64)
+              (i32.const 3)
+              (i64.const 4)))
+          (func (export "chosen") (param i32) (result i32 i64)
+            (if (result i32 i64) (local.get 0)
+              (then (i32.const 5) (i64.const 6))
+              (else (i32.const 7) (i64.const 8)))))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "pair" (func $pair (param i32) (result i32 i64)))
+          (func (export "swapped") (param i32) (result i64 i32)
+            (local $first i32)
+            (local $second i64)
+            (local.get 0)
+            (block (param i32) (result i32 i64)
+              (call $pair))
+            (local.set $second)
+            (local.set $first)
+            (local.get $second)
+            (local.get $first)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("A".to_string().into(), "pair".into());
+    let options = MergeOptions {
+        keep_exports: Some(keep_exports),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let pair = instance.get_typed_func::<i32, (i32, i64)>(&mut store, "pair")?;
+    assert_eq!(pair.call(&mut store, 1)?, (1, 2));
+    let looped = instance.get_typed_func::<(), (i32, i64)>(&mut store, "looped")?;
+    assert_eq!(looped.call(&mut store, ())?, (3, 4));
+    let chosen = instance.get_typed_func::<i32, (i32, i64)>(&mut store, "chosen")?;
+    assert_eq!(chosen.call(&mut store, 1)?, (5, 6));
+    assert_eq!(chosen.call(&mut store, 0)?, (7, 8));
+    let swapped = instance.get_typed_func::<i32, (i64, i32)>(&mut store, "swapped")?;
+    assert_eq!(swapped.call(&mut store, 9)?, (2, 9));
+
+    Ok(())
+}
+
+/// The merged module lists the hash of each merged input module, by the
+/// name it is merged under.
+#[test]
+fn embed_input_hashes() -> Result<(), Error> {
+    use sha2::{Digest, Sha256};
+    use std::sync::Arc;
+    use wasm_mergers::merge_options::ParseRecovery;
+
+    let wasm_a = parse_str(r#"(module (func (export "a")))"#)?;
+    let wasm_b = parse_str(r#"(module (func (export "b")))"#)?;
+    let corrupt: &[u8] = b"\0asm corrupt";
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("corrupt", corrupt),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        embed_input_hashes: true,
+        on_parse_error: Some(Arc::new(|_, _| ParseRecovery::Skip)),
+        ..Default::default()
+    };
+    let mut configuration = MergeConfiguration::new(modules, options);
+    configuration
+        .rename_modules
+        .insert("B".to_string(), "Beta".to_string());
+    let merged = configuration.merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let (_, section) = parsed
+        .customs
+        .iter()
+        .find(|(_, section)| section.name() == "merge.inputs")
+        .expect("input hashes are embedded");
+    let data = section.data(&walrus::IdsToIndices::default());
+
+    // Names and counts are short, hence single byte LEB128 lengths
+    let mut expected = vec![2];
+    for (name, wasm) in [("A", &wasm_a), ("Beta", &wasm_b)] {
+        expected.push(u8::try_from(name.len()).unwrap());
+        expected.extend_from_slice(name.as_bytes());
+        expected.extend_from_slice(&Sha256::digest(wasm));
+    }
+    assert_eq!(&*data, &expected[..]);
+
+    Ok(())
+}
+
+/// Imports resolve on the original export names, before clashing exports
+/// are renamed.
+#[test]
+fn resolve_through_renamed_exports() -> Result<(), Error> {
+    let gen_wat =
+        |value: i32| format!(r#"(module (func (export "f") (result i32) (i32.const {value})))"#);
+    let wasm_a = parse_str(gen_wat(1))?;
+    let wasm_c = parse_str(gen_wat(3))?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (result i32)))
+          (func (export "g") (result i32) (call $f)))"#,
+    )?;
+
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("A".to_string().into(), "f".into());
+    keep_exports.keep_function("C".to_string().into(), "f".into());
+    keep_exports.keep_function("B".to_string().into(), "g".into());
+
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+    for modules in iter_permutations(modules) {
+        let options = MergeOptions {
+            name_policy: NamePolicy {
+                clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+                ..Default::default()
+            },
+            keep_exports: Some(keep_exports.clone()),
+            ..Default::default()
+        };
+        let merged = MergeConfiguration::new(&modules, options).merge()?;
+
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        let exports = parsed.exports.iter().map(|export| &export.name).sorted();
+        assert_eq!(exports.collect::<Vec<_>>(), ["A:f", "C:f", "g"]);
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        for (name, expected) in [("g", 1), ("A:f", 1), ("C:f", 3)] {
+            let function = instance.get_typed_func::<(), i32>(&mut store, name)?;
+            assert_eq!(function.call(&mut store, ())?, expected);
+        }
+    }
+
+    Ok(())
+}
+
+/// Type mismatches of all kinds are reported together.
+#[test]
+fn collect_mismatches_of_all_kinds() -> Result<(), Error> {
+    use wasm_mergers::kinds::ExportKind;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) (i32.const 1))
+          (global (export "g") i32 (i32.const 2)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func (result i64)))
+          (import "A" "g" (global f64)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let configuration = MergeConfiguration::new(modules, MergeOptions::default());
+
+    let mismatches = configuration.collect_mismatches()?;
+    let kinds = mismatches.iter().map(|(kind, _)| *kind).collect::<Vec<_>>();
+    assert_eq!(kinds, [ExportKind::Function, ExportKind::Global]);
+    for (_, mismatch) in &mismatches {
+        assert_eq!(mismatch.importing.to_string(), "B");
+        assert_eq!(mismatch.exporting.to_string(), "A");
+    }
+
+    Ok(())
+}
+
+/// Shared globals (shared-everything-threads) are not (yet) supported by the
+/// parser and are reported as such, rather than losing their sharedness.
+#[test]
+fn shared_globals_unsupported() -> Result<(), Error> {
+    let wasm_a = parse_str(r#"(module (global (export "g") (shared mut i32) (i32.const 0)))"#)?;
+    let wasm_b = parse_str(r#"(module (import "A" "g" (global (shared mut i32))))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let result = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(result, Err(wasm_mergers::error::Error::Parse(_))));
+
+    Ok(())
+}
+
+/// The mapping traces input functions to the merged module.
+#[test]
+fn merge_with_mapping() -> Result<(), Error> {
+    let wasm_a = parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (result i32)))
+          (func (export "g") (result i32) (call $f)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    let (merged, mapping) = configuration.merge_with_mapping()?;
+
+    let function_of = |module: &str, index: usize| mapping.functions[&(module.to_string(), index)];
+
+    // B's import of f is A's f
+    assert_eq!(function_of("A", 0), function_of("B", 0));
+    assert_ne!(function_of("A", 0), function_of("B", 1));
+    assert!(matches!(
+        merged.funcs.get(function_of("A", 0)).kind,
+        walrus::FunctionKind::Local(_)
+    ));
+    let g = merged.exports.get_func("g")?;
+    assert_eq!(g, function_of("B", 1));
+
+    Ok(())
+}
+
+/// Tree shaking keeps active data segments along with their memory,
+/// never leaving a segment orphaned.
+#[test]
+fn tree_shake_active_data() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory 1)
+          (data (i32.const 0) "unreferenced")
+          (memory $other 1)
+          (func (export "f") (result i32) (i32.const 1)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+    let options = MergeOptions {
+        tree_shake: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    assert_eq!(parsed.memories.iter().count(), 1);
+    let data = parsed.data.iter().collect::<Vec<_>>();
+    assert_eq!(data.len(), 1);
+    let walrus::DataKind::Active { memory, .. } = data[0].kind else {
+        panic!("Expected an active data segment");
+    };
+    assert_eq!(memory, parsed.memories.iter().next().unwrap().id());
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    Instance::new(&mut store, &module, &[])?;
+
+    Ok(())
+}
+
+/// Modules without imports & exports are reported, tree shaking removes
+/// their functions.
+#[test]
+fn isolated_module() -> Result<(), Error> {
+    use wasm_mergers::MergeWarning;
+
+    let isolated = parse_str(r#"(module (func (nop)) (func (nop)))"#)?;
+    let wasm_a = parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("isolated", &isolated),
+        &NamedModule::new("A", &wasm_a),
+    ];
+
+    let merge = |tree_shake| {
+        let options = MergeOptions {
+            tree_shake,
+            ..Default::default()
+        };
+        MergeConfiguration::new(modules, options).merge_with_report()
+    };
+
+    let (merged, report) = merge(false)?;
+    assert_eq!(
+        report.warnings,
+        [MergeWarning::IsolatedModule("isolated".to_string())]
+    );
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    assert_eq!(parsed.funcs.iter().count(), 3);
+
+    let (merged, _) = merge(true)?;
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    assert_eq!(parsed.funcs.iter().count(), 1);
+
+    Ok(())
+}
+
+/// Exported memories are replaced by accessors to each memory.
+#[test]
+fn generate_memory_accessors() -> Result<(), Error> {
+    let gen_wat = |name: &str| format!(r#"(module (memory (export "{name}") 1))"#);
+    let wasm_a = parse_str(gen_wat("memory_a"))?;
+    let wasm_b = parse_str(gen_wat("memory_b"))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        generate_memory_accessors: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let exports = parsed.exports.iter().map(|export| &export.name).sorted();
+    assert_eq!(
+        exports.collect::<Vec<_>>(),
+        [
+            "read_memory_a",
+            "read_memory_b",
+            "write_memory_a",
+            "write_memory_b"
+        ]
+    );
+
+    let mut config = Config::new();
+    config.wasm_multi_memory(true);
+    let engine = Engine::new(&config)?;
+    let mut store = Store::new(&engine, ());
+    let module = Module::from_binary(&engine, &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store,
+        read_memory_a [i32] [i32],
+        read_memory_b [i32] [i32],
+        write_memory_a [i32, i32] [],
+        write_memory_b [i32, i32] []
+    };
+    wasm_call!(store, write_memory_a, 8, 42);
+    wasm_call!(store, write_memory_b, 8, 7);
+    assert_eq!(wasm_call!(store, read_memory_a, 8), 42);
+    assert_eq!(wasm_call!(store, read_memory_b, 8), 7);
+    assert_eq!(wasm_call!(store, read_memory_a, 9), 0);
+
+    Ok(())
+}
+
+/// Kept exports that clash are signalled, or renamed when so configured.
+#[test]
+fn kept_exports_clash() -> Result<(), Error> {
+    let gen_wat =
+        |value: i32| format!(r#"(module (func (export "f") (result i32) (i32.const {value})))"#);
+    let wasm_a = parse_str(gen_wat(1))?;
+    let wasm_b = parse_str(gen_wat(2))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("A".to_string().into(), "f".into());
+    keep_exports.keep_function("B".to_string().into(), "f".into());
+
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Signal,
+            ..Default::default()
+        },
+        keep_exports: Some(keep_exports.clone()),
+        ..Default::default()
+    };
+    let result = MergeConfiguration::new(modules, options).merge();
+    let Err(wasm_mergers::error::Error::ExportNameClash(clashes)) = result else {
+        panic!("Expected an export name clash, got {result:?}");
+    };
+    assert_eq!(clashes.keys().collect::<Vec<_>>(), ["f"]);
+    let exporting = clashes["f"]
+        .iter()
+        .map(|clash| clash.exporting_module.as_str())
+        .sorted();
+    assert_eq!(exporting.collect::<Vec<_>>(), ["A", "B"]);
+
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+            ..Default::default()
+        },
+        keep_exports: Some(keep_exports),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let exports = parsed.exports.iter().map(|export| &export.name).sorted();
+    assert_eq!(exports.collect::<Vec<_>>(), ["A:f", "B:f"]);
+
+    Ok(())
+}
+
+/// An import binds to the module named by its namespace, even when the
+/// importing module exports a function of the same name.
+#[test]
+fn import_binds_to_its_namespace() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (import "B" "g" (func $imported (result i32)))
+          (func (export "g") (result i32) (i32.const 1))
+          (func (export "call_g") (result i32) (call $imported)))"#,
+    )?;
+    let wasm_b = parse_str(r#"(module (func (export "g") (result i32) (i32.const 2)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    for modules in iter_permutations(modules) {
+        let merged = MergeConfiguration::new(&modules, MergeOptions::default()).merge()?;
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store,
+            g [] [i32],
+            call_g [] [i32]
+        };
+        assert_eq!(wasm_call!(store, g), 1);
+        assert_eq!(wasm_call!(store, call_g), 2);
+    }
+
+    Ok(())
+}
+
+/// Merge options read from a configuration file.
+#[cfg(feature = "serde")]
+#[test]
+fn merge_options_from_configuration_file() -> Result<(), Error> {
+    use wasm_mergers::merge_options::SerializableMergeOptions;
+
+    let configuration = r#"
+        {
+          "name_policy": { "clashes": { "rename": "dot" } },
+          "keep_exports": { "functions": [["A", "f"]] },
+          "export_renames": [["B", "h", "answer"]],
+          "tree_shake": true
+        }"#;
+    let options: SerializableMergeOptions = serde_json::from_str(configuration)?;
+    let round_tripped = serde_json::from_str(&serde_json::to_string(&options)?)?;
+    assert_eq!(options, round_tripped);
+    assert!(options.emit_producers && options.name_policy.dedupe_imports);
+
+    let wasm_a = parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (result i32)))
+          (func (export "f") (result i32) (i32.const 2))
+          (func (export "h") (result i32) (i32.const 42)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let options: MergeOptions = options.into();
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let exports = module.exports().map(|export| export.name()).sorted();
+    assert_eq!(exports.collect::<Vec<_>>(), ["A.f", "B.f", "answer"]);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, answer [] [i32] };
+    assert_eq!(wasm_call!(store, answer), 42);
+
+    Ok(())
+}
+
+/// Internal exports are absent from the merged module, even when kept, while
+/// the other exports remain.
+#[test]
+fn internal_exports_override_keep() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) (i32.const 1))
+          (func (export "g") (result i32) (i32.const 2))
+          (func (export "h") (result i32) (i32.const 3)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "g" (func $g (result i32)))
+          (func (export "call_g") (result i32) (call $g)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("A".into(), "f".to_string());
+    let options = MergeOptions {
+        keep_exports: Some(keep_exports),
+        internal_exports: [("A", "f"), ("A", "g")]
+            .map(|(module, name)| (module.to_string(), name.to_string()))
+            .into(),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    assert_eq!(module.imports().len(), 0);
+    let exports = module.exports().map(|export| export.name()).sorted();
+    assert_eq!(exports.collect::<Vec<_>>(), ["call_g", "h"]);
+
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, call_g [] [i32] };
+    assert_eq!(wasm_call!(store, call_g), 2);
+
+    Ok(())
+}
+
+/// The report lists the modules in the order their starts are called, which
+/// is the order in which the modules are given.
+#[test]
+fn report_start_order() -> Result<(), Error> {
+    let module_with_start = |digit: i32| {
+        parse_str(format!(
+            r#"
+            (module
+              (import "log" "digits" (global $digits (mut i32)))
+              (func $start
+                (global.set $digits
+                  (i32.add (i32.mul (global.get $digits) (i32.const 10)) (i32.const {digit}))))
+              (start $start))"#
+        ))
+    };
+    let wasm_no_start = parse_str(r#"(module (func (export "f")))"#)?;
+    let wasm_a = module_with_start(1)?;
+    let wasm_b = module_with_start(2)?;
+    let wasm_c = module_with_start(3)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+
+    for modules in iter_permutations(modules) {
+        let (_, report) =
+            MergeConfiguration::new(&modules, MergeOptions::default()).merge_with_report()?;
+        let given_order: Vec<_> = modules
+            .iter()
+            .map(|module| module.name.to_string())
+            .collect();
+        assert_eq!(report.start_order, given_order);
+    }
+
+    // Modules without a start are left out
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("D", &wasm_no_start),
+        &NamedModule::new("C", &wasm_c),
+        &NamedModule::new("A", &wasm_a),
+    ];
+    let (merged, report) =
+        MergeConfiguration::new(modules, MergeOptions::default()).merge_with_report()?;
+    assert_eq!(report.start_order, ["C", "A"]);
+
+    // The starts run in the reported order
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let digits = Global::new(
+        &mut store,
+        GlobalType::new(ValType::I32, Mutability::Var),
+        Val::I32(0),
+    )?;
+    let imports: Vec<Extern> = module.imports().map(|_| Extern::Global(digits)).collect();
+    let _instance = Instance::new(&mut store, &module, &imports)?;
+    assert_eq!(digits.get(&mut store).unwrap_i32(), 31);
+
+    Ok(())
+}
+
+/// The start of a base module is combined with the start of a plugin, the
+/// base's start runs first when the base is given first.
+#[test]
+fn base_and_plugin_starts() -> Result<(), Error> {
+    let wasm_base = parse_str(
+        r#"
+        (module
+          (global $trace (mut i32) (i32.const 0))
+          (func $start (global.set $trace (i32.const 1)))
+          (func (export "get") (result i32) (global.get $trace))
+          (func (export "set") (param i32) (global.set $trace (local.get 0)))
+          (func (export "get_trace") (result i32) (global.get $trace))
+          (start $start))"#,
+    )?;
+    let wasm_plugin = parse_str(
+        r#"
+        (module
+          (import "base" "get" (func $get (result i32)))
+          (import "base" "set" (func $set (param i32)))
+          (func $start
+            (call $set
+              (i32.add (i32.mul (call $get) (i32.const 10)) (i32.const 2))))
+          (start $start))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("base", &wasm_base),
+        &NamedModule::new("plugin", &wasm_plugin),
+    ];
+    let (merged, report) =
+        MergeConfiguration::new(modules, MergeOptions::default()).merge_with_report()?;
+    assert_eq!(report.start_order, ["base", "plugin"]);
+    assert!(report.is_self_contained);
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, get_trace [] [i32] };
+    assert_eq!(wasm_call!(store, get_trace), 12);
+
+    Ok(())
+}
+
+/// Identical passive segments of different modules are kept apart, dropping
+/// the segment of one module leaves that of another intact.
+#[test]
+fn dropped_segments_are_not_shared() -> Result<(), Error> {
+    let module_with_segments = |prefix: &str| {
+        parse_str(format!(
+            r#"
+            (module
+              (memory 1)
+              (table 1 funcref)
+              (data $data "\2a")
+              (elem $elem func $answer)
+              (func $answer (result i32) (i32.const 42))
+              (func (export "{prefix}_drop")
+                (data.drop $data)
+                (elem.drop $elem))
+              (func (export "{prefix}_load") (result i32)
+                (memory.init $data (i32.const 0) (i32.const 0) (i32.const 1))
+                (table.init $elem (i32.const 0) (i32.const 0) (i32.const 1))
+                (i32.add
+                  (i32.load8_u (i32.const 0))
+                  (call_indirect (result i32) (i32.const 0)))))"#
+        ))
+    };
+    let wasm_a = module_with_segments("a")?;
+    let wasm_b = module_with_segments("b")?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    for modules in iter_permutations(modules) {
+        let merged = MergeConfiguration::new(&modules, MergeOptions::default()).merge()?;
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store,
+            a_drop [] [],
+            a_load [] [i32],
+            b_load [] [i32]
+        };
+        wasm_call!(store, a_drop);
+        assert!(a_load.call(&mut store, ()).is_err());
+        assert_eq!(wasm_call!(store, b_load), 84);
+    }
+
+    Ok(())
+}
+
+/// The merge of the cross dependent modules passes the self check, also when
+/// kept exports are renamed and imports remain.
+#[test]
+fn merge_verified_cross_deps() -> Result<(), Error> {
+    let wasm_ab = parse_str(
+        r#"
+        (module
+          (import "host" "two" (func $two (result i32)))
+          (func (export "a") (result i32) (call $two))
+          (func (export "b") (result i32) (i32.const 3)))"#,
+    )?;
+    let wasm_cd = parse_str(
+        r#"
+        (module
+          (import "ab" "a" (func $a (result i32)))
+          (import "ab" "b" (func $b (result i32)))
+          (func (export "c") (result i32) (i32.mul (call $a) (i32.const 5)))
+          (func (export "d") (result i32) (i32.mul (call $b) (i32.const 7))))"#,
+    )?;
+    let wasm_e = parse_str(
+        r#"
+        (module
+          (import "cd" "c" (func $c (result i32)))
+          (import "cd" "d" (func $d (result i32)))
+          (func (export "e") (result i32) (i32.add (call $c) (call $d))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("ab", &wasm_ab),
+        &NamedModule::new("cd", &wasm_cd),
+        &NamedModule::new("e", &wasm_e),
+    ];
+
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("ab".into(), "a".to_string());
+    keep_exports.keep_function("cd".into(), "d".to_string());
+    keep_exports.keep_function("cd".into(), "absent".to_string());
+    for clashes in [
+        ClashingExports::Signal,
+        ClashingExports::AlwaysPrefix(DEFAULT_RENAMER),
+    ] {
+        let options = MergeOptions {
+            name_policy: NamePolicy {
+                clashes,
+                ..Default::default()
+            },
+            keep_exports: Some(keep_exports.clone()),
+            ..Default::default()
+        };
+        MergeConfiguration::new(modules, options).merge_verified()?;
+    }
+
+    Ok(())
+}
+
+/// Functions keep their name when requested, prefixed by their module name,
+/// and names in the merged name section are unique.
+#[test]
+fn function_names_are_unique() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func $f (export "a") (result i32) (i32.const 1))
+          (func $B::f (export "b") (result i32) (i32.const 2)))"#,
+    )?;
+    let wasm_b = parse_str(r#"(module (func $f (export "c") (result i32) (i32.const 3)))"#)?;
+    let wasm_a_b = parse_str(r#"(module (func $f (export "d") (result i32) (i32.const 4)))"#)?;
+    // Both `A` & `A::B` have a function named `A::B::f` once prefixed
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("A::B", &wasm_a_b),
+    ];
+
+    let options = MergeOptions {
+        preserve_function_names: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+    let merged = walrus::Module::from_buffer(&merged)?;
+    let names: Vec<_> = merged
+        .funcs
+        .iter()
+        .filter_map(|function| function.name.as_deref())
+        .sorted()
+        .collect();
+    assert_eq!(names, ["A::B::f", "A::B::f#1", "A::f", "B::f"]);
+
+    // Stripping custom sections strips the function names
+    let options = MergeOptions {
+        preserve_function_names: true,
+        strip_custom_sections: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+    let merged = walrus::Module::from_buffer(&merged)?;
+    assert!(merged.funcs.iter().all(|function| function.name.is_none()));
+
+    Ok(())
+}
+
+/// The interface of a merge has the imports & exports of the full merge,
+/// with the same types, yet none of its behaviour.
+#[test]
+fn merge_interface() -> Result<(), Error> {
+    fn surface(wasm: &[u8]) -> Result<Vec<String>, Error> {
+        let module = walrus::Module::from_buffer(wasm)?;
+        let describe_function = |id: walrus::FunctionId| {
+            let ty = module.types.get(module.funcs.get(id).ty());
+            format!("{:?} -> {:?}", ty.params(), ty.results())
+        };
+        let describe_global = |id: walrus::GlobalId| {
+            let global = module.globals.get(id);
+            format!("{} mut={}", global.ty, global.mutable)
+        };
+        let describe_memory = |id: walrus::MemoryId| {
+            let memory = module.memories.get(id);
+            format!("memory {} {:?}", memory.initial, memory.maximum)
+        };
+        let describe_table = |id: walrus::TableId| {
+            let table = module.tables.get(id);
+            format!("{} {} {:?}", table.element_ty, table.initial, table.maximum)
+        };
+        let imports = module.imports.iter().map(|import| {
+            let ty = match import.kind {
+                walrus::ImportKind::Function(id) => describe_function(id),
+                walrus::ImportKind::Global(id) => describe_global(id),
+                walrus::ImportKind::Memory(id) => describe_memory(id),
+                walrus::ImportKind::Table(id) => describe_table(id),
+                walrus::ImportKind::Tag(_) => "tag".to_string(),
+            };
+            format!("import {} {}: {ty}", import.module, import.name)
+        });
+        let exports = module.exports.iter().map(|export| {
+            let ty = match export.item {
+                walrus::ExportItem::Function(id) => describe_function(id),
+                walrus::ExportItem::Global(id) => describe_global(id),
+                walrus::ExportItem::Memory(id) => describe_memory(id),
+                walrus::ExportItem::Table(id) => describe_table(id),
+                walrus::ExportItem::Tag(_) => "tag".to_string(),
+            };
+            format!("export {}: {ty}", export.name)
+        });
+        Ok(imports.chain(exports).sorted().collect())
+    }
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (import "host" "log" (func $log (param i32)))
+          (import "host" "base" (global $base i32))
+          (memory (export "memory") 1 2)
+          (data (i32.const 0) "\2a")
+          (table (export "table") 1 funcref)
+          (elem (i32.const 0) func $f)
+          (global (export "counter") (mut i64) (i64.const 7))
+          (func $f (export "f") (param i32) (result i32)
+            (call $log (local.get 0))
+            (i32.add (global.get $base) (i32.load8_u (i32.const 0))))
+          (func $init (call $log (i32.const 0)))
+          (start $init))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (param i32) (result i32)))
+          (import "host" "log" (func $log (param i32)))
+          (func (export "g") (result i32) (call $f (i32.const 1))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    let merged = configuration.merge()?;
+    let interface = configuration.merge_interface()?;
+    assert_eq!(surface(&interface)?, surface(&merged)?);
+    assert!(interface.len() < merged.len());
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &interface)?;
+    let log = Func::wrap(&mut store, |_: i32| -> () {
+        panic!("The interface calls no imports")
+    });
+    let base = Global::new(
+        &mut store,
+        GlobalType::new(ValType::I32, Mutability::Const),
+        Val::I32(0),
+    )?;
+    let imports: Vec<Extern> = module
+        .imports()
+        .map(|import| match import.name() {
+            "log" => Extern::Func(log),
+            _ => Extern::Global(base),
+        })
+        .collect();
+    let instance = Instance::new(&mut store, &module, &imports)?;
+    let g = instance.get_typed_func::<(), i32>(&mut store, "g")?;
+    assert!(g.call(&mut store, ()).is_err());
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.data(&store)[0], 0);
+
+    Ok(())
+}
+
+/// Element segments of a typed function reference are not (yet) supported by
+/// the parser and are reported as such.
+#[test]
+fn typed_funcref_elements_unsupported() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (type $answer (func (result i32)))
+          (table $table 1 (ref null $answer))
+          (func $f (type $answer) (i32.const 42))
+          (elem (table $table) (i32.const 0) (ref null $answer) (ref.func $f))
+          (func (export "call") (result i32) (call_indirect $table (type $answer) (i32.const 0))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+    let result = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(result, Err(wasm_mergers::error::Error::Parse(_))));
+
+    Ok(())
+}
+
+/// A `ref.null` of a typed function reference is not (yet) supported by the
+/// parser either, it is reported as a parse error rather than panicking.
+#[test]
+fn typed_ref_null_unsupported() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (type $answer (func (result i32)))
+          (func (export "null") (result i32)
+            (ref.is_null (ref.null $answer))))"#,
+    )?;
+    let wasm_b = parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("A", &wasm_a),
+    ];
+    let result = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(
+        result,
+        Err(wasm_mergers::error::Error::Parse(error)) if error.to_string().starts_with("A: ")
+    ));
+
+    Ok(())
+}
+
+/// A start taking parameters cannot be called from the merged start, such an
+/// (invalid) module is rejected when parsing rather than merged.
+#[test]
+fn start_with_params_rejected() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func $init (param i32))
+          (start $init))"#,
+    )?;
+    let wasm_b = parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("A", &wasm_a),
+    ];
+    let result = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(result, Err(wasm_mergers::error::Error::Parse(_))));
+
+    Ok(())
+}
+
+/// A (malformed) module exporting two items under the same name is reported
+/// by module & name.
+#[test]
+fn duplicate_export_in_module() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) (i32.const 1))
+          (global (export "f") i32 (i32.const 2)))"#,
+    )?;
+    let wasm_b = parse_str(r#"(module (func (export "f") (result i32) (i32.const 3)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("A", &wasm_a),
+    ];
+    let result = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    let Err(wasm_mergers::error::Error::DuplicateExportInModule { module, name }) = result else {
+        panic!("Expected a duplicate export, got {result:?}");
+    };
+    assert_eq!((module.as_str(), name.as_str()), ("A", "f"));
+
+    Ok(())
+}
+
+/// Under topological order modules are included before the modules that
+/// import from them, regardless of the order in which they are given.
+#[test]
+fn topological_module_order() -> Result<(), Error> {
+    use wasm_mergers::merge_options::ModuleOrder;
+
+    /// The exported memories as `(name, index)`, sorted by name.
+    fn exported_memories(wasm: &[u8]) -> Result<Vec<(String, u32)>, Error> {
+        let mut memories = vec![];
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            if let wasmparser::Payload::ExportSection(section) = payload? {
+                for export in section {
+                    let export = export?;
+                    if export.kind == wasmparser::ExternalKind::Memory {
+                        memories.push((export.name.to_string(), export.index));
+                    }
+                }
+            }
+        }
+        memories.sort();
+        Ok(memories)
+    }
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory (export "memory_a") 1)
+          (func $start)
+          (func (export "a") (result i32) (i32.const 1))
+          (start $start))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "a" (func $a (result i32)))
+          (memory (export "memory_b") 1)
+          (func $start)
+          (func (export "b") (result i32) (i32.add (call $a) (i32.const 1)))
+          (start $start))"#,
+    )?;
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (import "B" "b" (func $b (result i32)))
+          (import "A" "a" (func $a (result i32)))
+          (func $start)
+          (func (export "c") (result i32) (i32.add (call $a) (call $b)))
+          (start $start))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("C", &wasm_c),
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let options = MergeOptions {
+        order: ModuleOrder::Topological,
+        ..Default::default()
+    };
+    let mut outputs = vec![];
+    for modules in iter_permutations(modules) {
+        let (merged, report) =
+            MergeConfiguration::new(&modules, options.clone()).merge_with_report()?;
+        assert_eq!(report.start_order, ["A", "B", "C"]);
+        outputs.push(exported_memories(&merged)?);
+    }
+    // Memories are ordered by module, regardless of the order they are given
+    assert!(outputs.iter().all_equal());
+    assert_eq!(
+        outputs[0],
+        [("memory_a".to_string(), 0), ("memory_b".to_string(), 1)]
+    );
+
+    let (_, report) =
+        MergeConfiguration::new(modules, MergeOptions::default()).merge_with_report()?;
+    assert_eq!(report.start_order, ["C", "A", "B"]);
+
+    Ok(())
+}
+
+/// The signatures of the exports of the cross dependent modules, without
+/// merging them.
+#[test]
+fn export_signatures_cross_deps() -> Result<(), Error> {
+    use wasm_mergers::ExportType;
+    use wasm_mergers::kinds::ExportKind;
+
+    let wasm_ab = parse_str(
+        r#"
+        (module
+          (memory (export "memory") 1 2)
+          (global (export "counter") (mut i64) (i64.const 0))
+          (func (export "a") (result i32) (i32.const 2))
+          (func (export "b") (result i32) (i32.const 3)))"#,
+    )?;
+    let wasm_cd = parse_str(
+        r#"
+        (module
+          (import "ab" "a" (func $a (result i32)))
+          (import "ab" "b" (func $b (result i32)))
+          (table (export "table") 1 funcref)
+          (func (export "c") (param i32) (result i32) (i32.mul (call $a) (local.get 0)))
+          (func (export "d") (result i32) (i32.mul (call $b) (i32.const 7))))"#,
+    )?;
+    let wasm_e = parse_str(
+        r#"
+        (module
+          (import "cd" "c" (func $c (param i32) (result i32)))
+          (import "cd" "d" (func $d (result i32)))
+          (func (export "d") (result i64) (i64.const 0))
+          (func (export "e") (result i32) (i32.add (call $c (i32.const 5)) (call $d))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("ab", &wasm_ab),
+        &NamedModule::new("cd", &wasm_cd),
+        &NamedModule::new("e", &wasm_e),
+    ];
+
+    let configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    let signatures = configuration.export_signatures()?;
+    let signatures: Vec<_> = signatures
+        .into_iter()
+        .sorted_by(|a, b| a.name.cmp(&b.name))
+        .collect();
+
+    let names: Vec<_> = signatures.iter().map(|sig| sig.name.as_str()).collect();
+    // The export `d` of `cd` is resolved, hence does not clash with that of `e`
+    assert_eq!(names, ["counter", "d", "e", "memory", "table"]);
+    let kinds: Vec<_> = signatures.iter().map(|sig| sig.ty.kind()).collect();
+    assert_eq!(
+        kinds,
+        [
+            ExportKind::Global,
+            ExportKind::Function,
+            ExportKind::Function,
+            ExportKind::Memory,
+            ExportKind::Table
+        ]
+    );
+    let ExportType::Function(d) = &signatures[1].ty else {
+        panic!("Expected a function, got {:?}", signatures[1].ty);
+    };
+    assert_eq!(
+        (d.params(), d.results()),
+        (&[][..], &[walrus::ValType::I64][..])
+    );
+    assert_eq!(signatures[1].module, "e");
+    assert_eq!(
+        signatures[0].ty,
+        ExportType::Global {
+            ty: walrus::ValType::I64,
+            mutable: true
+        }
+    );
+    assert_eq!(
+        signatures[3].ty,
+        ExportType::Memory {
+            initial: 1,
+            maximum: Some(2)
+        }
+    );
+
+    // The signatures agree with the exports of the merge
+    let merged = MergeConfiguration::new(modules, configuration.options.clone()).merge()?;
+    let store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let exports = module.exports().map(|export| export.name()).sorted();
+    assert!(exports.eq(names));
+
+    Ok(())
+}
+
+/// Modules that import from each other in a cycle spanning four modules,
+/// grounded in a single local definition, resolve to that definition.
+///
+/// ```text
+/// A (local f, imports D.h) → D (re-exports C.h) → C (re-exports B.g) → B (re-exports A.f)
+/// ```
+#[test]
+fn grounded_cycle_across_modules() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (import "D" "h" (func $h (param i32) (result i32)))
+          (func $f (export "f") (param i32) (result i32)
+            (if (result i32) (i32.eqz (local.get 0))
+              (then (i32.const 0))
+              (else (i32.add (i32.const 1) (call $h (i32.sub (local.get 0) (i32.const 1)))))))
+          (func (export "run") (result i32) (call $f (i32.const 5))))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"(module (import "A" "f" (func $f (param i32) (result i32))) (export "g" (func $f)))"#,
+    )?;
+    let wasm_c = parse_str(
+        r#"(module (import "B" "g" (func $g (param i32) (result i32))) (export "h" (func $g)))"#,
+    )?;
+    let wasm_d = parse_str(
+        r#"(module (import "C" "h" (func $h (param i32) (result i32))) (export "h" (func $h)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+        &NamedModule::new("D", &wasm_d),
+    ];
+
+    for modules in iter_permutations(modules) {
+        let (merged, report) =
+            MergeConfiguration::new(&modules, MergeOptions::default()).merge_with_report()?;
+        assert!(report.is_self_contained);
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, run [] [i32] };
+        assert_eq!(wasm_call!(store, run), 5);
+    }
+
+    Ok(())
+}
+
+/// The post-processing hook may transform the merged module before emission,
+/// as configured by the state it captures.
+#[test]
+fn post_process() -> Result<(), Error> {
+    use std::sync::Arc;
+
+    let wasm_a = parse_str(r#"(module (func $f (result i32) (i32.const 42)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+    let export_name = "answer".to_string();
+    let options = MergeOptions {
+        post_process: Some(Arc::new(move |module| {
+            let (function, _) = module.funcs.iter_local().next().unwrap();
+            module.exports.add(&export_name, function);
+        })),
+        ..Default::default()
+    };
+    assert!(format!("{options:?}").contains("post_process: Some(..)"));
+    let (merged, report) = MergeConfiguration::new(modules, options).merge_with_report()?;
+    assert!(report.function_exports.contains_key("answer"));
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, answer [] [i32] };
+    assert_eq!(wasm_call!(store, answer), 42);
+
+    Ok(())
+}
+
+/// A mutable global that multiple modules import from the module defining it
+/// resolves to that single definition, such that the modules share it.
+#[test]
+fn shared_global_import() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (global $counter (export "counter") (mut i32) (i32.const 0)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "counter" (global $counter (mut i32)))
+          (func (export "increment")
+            (global.set $counter (i32.add (global.get $counter) (i32.const 1)))))"#,
+    )?;
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (import "A" "counter" (global $counter (mut i32)))
+          (func (export "read") (result i32) (global.get $counter)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+
+    for modules in iter_permutations(modules) {
+        let merged = MergeConfiguration::new(&modules, MergeOptions::default()).merge()?;
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        assert_eq!(parsed.imports.iter().count(), 0);
+        assert_eq!(parsed.globals.iter().count(), 1);
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, increment [] [], read [] [i32] };
+        assert_eq!(wasm_call!(store, read), 0);
+        wasm_call!(store, increment);
+        wasm_call!(store, increment);
+        assert_eq!(wasm_call!(store, read), 2);
+    }
+
+    Ok(())
+}
+
+/// A module with a start but no exports contributes its start, and all that
+/// its start reaches survives tree shaking.
+#[test]
+fn start_only_module() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (import "B" "set_flag" (func $set_flag (param i32)))
+          (func $patch (call $set_flag (i32.const 42)))
+          (func $start (call $patch))
+          (func $unused (call $set_flag (i32.const 0)))
+          (start $start))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (global $flag (mut i32) (i32.const 0))
+          (func (export "set_flag") (param i32) (global.set $flag (local.get 0)))
+          (func (export "get_flag") (result i32) (global.get $flag)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        tree_shake: true,
+        ..Default::default()
+    };
+
+    for modules in iter_permutations(modules) {
+        let merged = MergeConfiguration::new(&modules, options.clone()).merge()?;
+
+        // `get_flag`, `set_flag`, `start`, `patch` & the merged start remain
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        assert_eq!(parsed.funcs.iter_local().count(), 5);
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, get_flag [] [i32] };
+        assert_eq!(wasm_call!(store, get_flag), 42);
+    }
+
+    Ok(())
+}
+
+/// Locals of a reference type keep their type, as well as their values.
+#[test]
+fn reference_type_locals() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (type $answer (func (result i32)))
+          (table $t 2 funcref)
+          (elem declare func $forty_two)
+          (func $forty_two (result i32) (i32.const 42))
+          (func (export "via_local") (result i32)
+            (local $f funcref)
+            (local.set $f (ref.func $forty_two))
+            (table.set $t (i32.const 1) (local.get $f))
+            (local.set $f (table.get $t (i32.const 1)))
+            (table.set $t (i32.const 0) (local.get $f))
+            (call_indirect $t (type $answer) (i32.const 0)))
+          (func (export "nulls") (result i32)
+            (local $f funcref)
+            (local $e externref)
+            (i32.add (ref.is_null (local.get $f)) (ref.is_null (local.get $e)))))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "via_local" (func $via_local (result i32)))
+          (func (export "run") (result i32) (call $via_local)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    for modules in iter_permutations(modules) {
+        let merged = MergeConfiguration::new(&modules, MergeOptions::default()).merge()?;
+
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        let local_types: Vec<_> = parsed.locals.iter().map(walrus::Local::ty).collect();
+        for ref_type in [walrus::RefType::FUNCREF, walrus::RefType::EXTERNREF] {
+            assert!(local_types.contains(&walrus::ValType::Ref(ref_type)));
+        }
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, run [] [i32], nulls [] [i32] };
+        assert_eq!(wasm_call!(store, run), 42);
+        assert_eq!(wasm_call!(store, nulls), 2);
+    }
+
+    // Non-nullable locals remain non-nullable
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (elem declare func $f)
+          (func $f (export "f")
+            (local $g (ref func))
+            (local.set $g (ref.func $f))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("C", &wasm_c)];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let non_null_funcref = walrus::RefType {
+        nullable: false,
+        ..walrus::RefType::FUNCREF
+    };
+    let mut local_types = parsed.locals.iter().map(walrus::Local::ty);
+    assert!(local_types.any(|ty| ty == walrus::ValType::Ref(non_null_funcref)));
+
+    Ok(())
+}
+
+/// Several variants of the merge are produced from a single parse.
+#[test]
+fn merge_variants() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func $answer (export "answer") (result i32) (i32.const 42))
+          (func $unused (result i32) (i32.const 0)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "answer" (func $answer (result i32)))
+          (func $run (export "run") (result i32) (call $answer)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let release = MergeOptions {
+        strip_custom_sections: true,
+        emit_producers: false,
+        tree_shake: true,
+        ..Default::default()
+    };
+    let debug = MergeOptions {
+        preserve_function_names: true,
+        ..Default::default()
+    };
+
+    let variants = MergeConfiguration::new(modules, MergeOptions::default())
+        .merge_variants(&[release.clone(), debug.clone()])?;
+    let [stripped, debuggable] = &variants[..] else {
+        panic!("Expected one output per variant");
+    };
+
+    // Each variant matches the outcome of merging with its options, up to
+    // the order of the functions
+    let release_merged = MergeConfiguration::new(modules, release).merge()?;
+    assert_eq!(stripped.len(), release_merged.len());
+    let debug_merged = MergeConfiguration::new(modules, debug).merge()?;
+    assert_eq!(debuggable.len(), debug_merged.len());
+    assert!(stripped.len() < debuggable.len());
+
+    let parsed = walrus::Module::from_buffer(stripped)?;
+    assert_eq!(parsed.funcs.iter_local().count(), 2);
+    assert!(parsed.funcs.iter().all(|function| function.name.is_none()));
+    let parsed = walrus::Module::from_buffer(debuggable)?;
+    assert_eq!(parsed.funcs.iter_local().count(), 3);
+    assert!(parsed.funcs.iter().any(|function| function.name.is_some()));
+
+    for merged in &variants {
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, run [] [i32] };
+        assert_eq!(wasm_call!(store, run), 42);
+    }
+
+    Ok(())
+}
+
+/// Modules initialising overlapping ranges of a shared memory are reported.
+#[test]
+fn data_overlap() -> Result<(), Error> {
+    use wasm_mergers::error::Error;
+
+    let gen_wat = |offset: u32, bytes: &str| {
+        parse_str(format!(
+            r#"(module (import "env" "memory" (memory 1)) (data (i32.const {offset}) "{bytes}"))"#
+        ))
+    };
+    let wasm_a = gen_wat(0, "abcd")?;
+    let wasm_b = gen_wat(2, "efgh")?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let outcome = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(
+        outcome,
+        Err(Error::DataOverlap { memory, range }) if memory == "env::memory" && range == (2..4)
+    ));
+
+    // Adjacent ranges do not overlap
+    let wasm_b = gen_wat(4, "efgh")?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    // A memory defined by one module is shared with its importers
+    let wasm_a = parse_str(r#"(module (memory (export "memory") 1) (data (i32.const 0) "ab"))"#)?;
+    let wasm_b =
+        parse_str(r#"(module (import "A" "memory" (memory 1)) (data (i32.const 1) "c"))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let outcome = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    assert!(matches!(
+        outcome,
+        Err(Error::DataOverlap { memory, range }) if memory == "A" && range == (1..2)
+    ));
+
+    Ok(())
+}
+
+/// Content-addressed modules are named by a manifest of their hashes.
+#[test]
+fn from_bytes_with_manifest() -> Result<(), Error> {
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+    use wasm_mergers::ContentHash;
+
+    let wasm_a = parse_str(r#"(module (func (export "f") (result i32) (i32.const 42)))"#)?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (result i32)))
+          (func (export "g") (result i32) (call $f)))"#,
+    )?;
+    let hash = |wasm: &[u8]| -> ContentHash { Sha256::digest(wasm).into() };
+    let mut manifest = HashMap::from([(hash(&wasm_a), "A".to_string())]);
+    let modules: &[&[u8]] = &[&wasm_a, &wasm_b];
+
+    let outcome =
+        MergeConfiguration::from_bytes_with_manifest(modules, &manifest, MergeOptions::default());
+    assert!(matches!(
+        outcome,
+        Err(wasm_mergers::error::Error::UnmappedContentHash(_))
+    ));
+
+    manifest.insert(hash(&wasm_b), "B".to_string());
+    let mut configuration =
+        MergeConfiguration::from_bytes_with_manifest(modules, &manifest, MergeOptions::default())?;
+    assert_eq!(configuration.module_names()?, ["A", "B"]);
+    let (merged, report) = configuration.merge_with_report()?;
+    assert!(report.is_self_contained);
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, g [] [i32] };
+    assert_eq!(wasm_call!(store, g), 42);
+
+    Ok(())
+}
+
+/// Tree shaking is conservative: a function only called from code that
+/// never executes is retained, as it remains statically reachable, also when
+/// the call follows an `unreachable` and is hence dropped upon parsing.
+#[test]
+fn tree_shake_after_unreachable() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func $never_called (result i32) (i32.const 7))
+          (func $after_unreachable (result i32) (i32.const 42))
+          (func $from_dead_caller (result i32) (i32.const 43))
+          (func $dead_caller (result i32)
+            unreachable
+            (call $from_dead_caller))
+          (func (export "never") (result i32)
+            (if (result i32) (i32.const 0)
+              (then (call $never_called))
+              (else (i32.const 0))))
+          (func (export "trap") (result i32)
+            unreachable
+            (block (result i32)
+              (call $after_unreachable))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[&NamedModule::new("A", &wasm_a)];
+    let options = MergeOptions {
+        tree_shake: true,
+        preserve_function_names: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    assert_eq!(parsed.funcs.iter_local().count(), 4);
+    let names: Vec<_> = parsed.funcs.iter().filter_map(|f| f.name.clone()).collect();
+    assert!(names.iter().any(|name| name.ends_with("never_called")));
+    assert!(names.iter().any(|name| name.ends_with("after_unreachable")));
+    // Dead calls only keep their callee alive as long as the caller is kept
+    assert!(!names.iter().any(|name| name.contains("dead_caller")));
+
+    // The dead call is not restored in the merged module
+    let mut store = Store::<()>::default();
+    let module = Module::new(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let trap = instance.get_typed_func::<(), i32>(&mut store, "trap")?;
+    assert!(trap.call(&mut store, ()).is_err());
+
+    Ok(())
+}
+
+/// Calls in tail position become `return_call`s, such that the mutually
+/// recursive even & odd no longer overflow the stack on deep recursion.
+#[test]
+fn tailcall_optimize() -> Result<(), Error> {
+    let wasm_even = parse_str(
+        r#"
+        (module
+          (import "odd" "odd" (func $odd (param i32) (result i32)))
+          (func (export "even") (param $n i32) (result i32)
+            (if (result i32) (i32.eqz (local.get $n))
+              (then (i32.const 1))
+              (else (call $odd (i32.sub (local.get $n) (i32.const 1)))))))"#,
+    )?;
+    let wasm_odd = parse_str(
+        r#"
+        (module
+          (import "even" "even" (func $even (param i32) (result i32)))
+          (func (export "odd") (param $n i32) (result i32)
+            (if (i32.eqz (local.get $n))
+              (then (return (i32.const 0))))
+            (call $even (i32.sub (local.get $n) (i32.const 1)))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("even", &wasm_even),
+        &NamedModule::new("odd", &wasm_odd),
+    ];
+
+    let even = |tailcall_optimize| -> Result<Result<i32, Error>, Error> {
+        let mut keep_exports = KeepExports::default();
+        keep_exports.keep_function("even".to_string().into(), "even".into());
+        let options = MergeOptions {
+            keep_exports: Some(keep_exports),
+            tailcall_optimize,
+            ..Default::default()
+        };
+        let merged = MergeConfiguration::new(modules, options).merge()?;
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, even [i32] [i32] };
+        Ok(even.call(&mut store, 1_000_000))
+    };
+
+    assert!(even(false)?.is_err());
+    assert_eq!(even(true)??, 1);
+
+    Ok(())
+}
+
+/// Exports left out of the merged module are reported along with the reason,
+/// be it resolving an import of another input or being deny-listed.
+#[test]
+fn pruned_exports() -> Result<(), Error> {
+    use wasm_mergers::PruneReason;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "bridge") (result i32) (i32.const 42))
+          (func (export "hidden") (result i32) (i32.const 0)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "bridge" (func $bridge (result i32)))
+          (func (export "main") (result i32) (call $bridge)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        internal_exports: [("A".to_string(), "hidden".to_string())].into(),
+        ..Default::default()
+    };
+    let (merged, report) = MergeConfiguration::new(modules, options).merge_with_report()?;
+
+    assert_eq!(
+        report.pruned_exports,
+        vec![
+            ("A".into(), "bridge".into(), PruneReason::ResolvedInternally),
+            ("A".into(), "hidden".into(), PruneReason::DenyListed),
+        ]
+    );
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let exports: Vec<_> = parsed.exports.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(exports, ["main"]);
+
+    Ok(())
+}
+
+/// The designated main memory is exported as `memory`, the other memory by
+/// that name is prefixed by its module rather than clashing.
+#[test]
+fn main_memory() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "A"))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "B"))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        main_memory: Some(("A".to_string(), "memory".to_string())),
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let first_byte = |store: &mut Store<()>, name| {
+        let memory = instance.get_memory(&mut *store, name).unwrap();
+        memory.data(&*store)[0]
+    };
+    assert_eq!(first_byte(&mut store, "memory"), b'A');
+    assert_eq!(first_byte(&mut store, "B.memory"), b'B');
+
+    Ok(())
+}
+
+/// Table operations keep targeting their own table once merging shifts the
+/// table indices: B grows & fills what becomes the second table.
+#[test]
+fn table_grow_fill_reindexed() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (table $t 1 funcref)
+          (func $zero (result i32) (i32.const 0))
+          (elem (table $t) (i32.const 0) func $zero)
+          (func (export "a_size") (result i32) (table.size $t)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (type $get (func (result i32)))
+          (table $t 0 funcref)
+          (func $answer (result i32) (i32.const 42))
+          (elem declare func $answer)
+          (func (export "grow") (result i32)
+            (drop (table.grow $t (ref.null func) (i32.const 3)))
+            (table.fill $t (i32.const 1) (ref.func $answer) (i32.const 2))
+            (table.size $t))
+          (func (export "call") (param $slot i32) (result i32)
+            (call_indirect $t (type $get) (local.get $slot))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let merged = MergeConfiguration::new(modules, MergeOptions::default()).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store,
+        a_size [] [i32],
+        grow [] [i32],
+        call [i32] [i32]
+    };
+    assert_eq!(wasm_call!(store, grow), 3);
+    assert_eq!(wasm_call!(store, a_size), 1);
+    assert_eq!(wasm_call!(store, call, 1), 42);
+    assert_eq!(wasm_call!(store, call, 2), 42);
+    assert!(call.call(&mut store, 0).is_err()); // Null slot
+
+    Ok(())
+}
+
+/// A single name policy settles clashing imports and clashing exports alike:
+/// both are renamed within the namespace of their module, or both numbered.
+#[test]
+fn name_policy() -> Result<(), Error> {
+    use wasm_mergers::merge_options::NUMERIC_SUFFIX_RENAMER;
+
+    let gen_wat = |param: &str| {
+        format!(
+            r#"
+            (module
+              (import "env" "log" (func $log (param {param})))
+              (import "env" "flush" (func $flush))
+              (func (export "run") (call $flush)))"#
+        )
+    };
+    let wasm_a = parse_str(gen_wat("i32"))?;
+    let wasm_b = parse_str(gen_wat("f64"))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+            settle_imports: true,
+            dedupe_imports: true,
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let imports = parsed.imports.iter().map(|import| &import.name).sorted();
+    assert_eq!(imports.collect::<Vec<_>>(), ["A:log", "B:log", "flush"]);
+    let exports = parsed.exports.iter().map(|export| &export.name).sorted();
+    assert_eq!(exports.collect::<Vec<_>>(), ["A:run", "B:run"]);
+
+    // Signalling, the imports clash even once the exports no longer do
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            settle_imports: true,
+            ..Default::default()
+        },
+        export_renames: [(("B".into(), "run".into()), "run_b".into())].into(),
+        ..Default::default()
+    };
+    let error = MergeConfiguration::new(modules, options)
+        .merge()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        wasm_mergers::error::Error::ImportNameClash(clashes)
+            if clashes == [("env".to_string(), "log".to_string())]
+    ));
+
+    // Numbered, imports take the same suffixes as exports, in input order
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(NUMERIC_SUFFIX_RENAMER),
+            settle_imports: true,
+            dedupe_imports: true,
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let imports: Vec<_> = parsed
+        .imports
+        .iter()
+        .map(|import| (import.name.as_str(), import.kind.clone()))
+        .filter_map(|(name, kind)| match kind {
+            walrus::ImportKind::Function(id) => Some((name, id)),
+            _ => None,
+        })
+        .map(|(name, id)| {
+            let ty = parsed.types.get(parsed.funcs.get(id).ty());
+            (name, ty.params().to_vec())
+        })
+        .sorted()
+        .collect();
+    assert_eq!(
+        imports,
+        [
+            ("flush", vec![]),
+            ("log", vec![walrus::ValType::I32]),
+            ("log_1", vec![walrus::ValType::F64]),
+        ]
+    );
+    let exports = parsed.exports.iter().map(|export| &export.name).sorted();
+    assert_eq!(exports.collect::<Vec<_>>(), ["run", "run_1"]);
+
+    // Not settling imports, the clashing imports are imported side by side
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let imports = parsed.imports.iter().map(|import| &import.name).sorted();
+    assert_eq!(imports.collect::<Vec<_>>(), ["flush", "log", "log"]);
+    let exports = parsed.exports.iter().map(|export| &export.name).sorted();
+    assert_eq!(exports.collect::<Vec<_>>(), ["A:run", "B:run"]);
+
+    Ok(())
+}
+
+/// The call graph of the merged even & odd module holds the calls across the
+/// original modules, from `even` to `odd` and back.
+#[test]
+fn merge_with_callgraph() -> Result<(), Error> {
+    let gen_wat = |name: &str, other: &str, base: i32| {
+        format!(
+            r#"
+            (module
+              (import "{other}" "{other}" (func ${other} (param i32) (result i32)))
+              (func (export "{name}") (param $n i32) (result i32)
+                (if (result i32) (i32.eqz (local.get $n))
+                  (then (i32.const {base}))
+                  (else (call ${other} (i32.sub (local.get $n) (i32.const 1)))))))"#
+        )
+    };
+    let wasm_even = parse_str(gen_wat("even", "odd", 1))?;
+    let wasm_odd = parse_str(gen_wat("odd", "even", 0))?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("even", &wasm_even),
+        &NamedModule::new("odd", &wasm_odd),
+    ];
+    let mut keep_exports = KeepExports::default();
+    keep_exports.keep_function("even".to_string().into(), "even".into());
+    keep_exports.keep_function("odd".to_string().into(), "odd".into());
+    let options = MergeOptions {
+        keep_exports: Some(keep_exports),
+        ..Default::default()
+    };
+    let (merged, call_graph) = MergeConfiguration::new(modules, options).merge_with_callgraph()?;
+
+    let parsed = walrus::Module::from_buffer(&merged)?;
+    let index_of = |name| match parsed.exports.get_func(name) {
+        Ok(id) => u32::try_from(id.index()).unwrap(),
+        Err(error) => panic!("{error}"),
+    };
+    let (even, odd) = (index_of("even"), index_of("odd"));
+    assert!(call_graph.calls(even, odd));
+    assert!(call_graph.calls(odd, even));
+    assert_eq!(call_graph.nodes[&even].module.as_deref(), Some("even"));
+    assert_eq!(call_graph.nodes[&odd].module.as_deref(), Some("odd"));
+    assert_eq!(call_graph.nodes.len(), 2);
+    assert_eq!(parsed.customs.iter().count(), 0);
+
+    Ok(())
+}
+
+/// Element segments of a module importing a table populate the table it
+/// resolves to: A calls the function B installed in A's table.
+#[test]
+fn imported_table_elements() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (type $get (func (result i32)))
+          (table $t (export "t") 2 funcref)
+          (func (export "call") (param $slot i32) (result i32)
+            (call_indirect $t (type $get) (local.get $slot))))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "t" (table $t 2 funcref))
+          (func $answer (result i32) (i32.const 42))
+          (elem (table $t) (i32.const 1) func $answer))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    for permutation in iter_permutations(modules) {
+        let merged = MergeConfiguration::new(&permutation, MergeOptions::default()).merge()?;
+
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        assert!(parsed.imports.iter().next().is_none());
+        assert_eq!(parsed.tables.iter().count(), 1);
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, call [i32] [i32] };
+        assert_eq!(wasm_call!(store, call, 1), 42);
+        assert!(call.call(&mut store, 0).is_err()); // Null slot
+    }
+
+    // A table of at most 1 element cannot satisfy B's import of 2 elements
+    let wasm_a = parse_str(r#"(module (table (export "t") 1 1 funcref))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let result = MergeConfiguration::new(modules, MergeOptions::default()).merge();
+    let Err(wasm_mergers::error::Error::TableLimitsMismatch(mismatches)) = result else {
+        panic!("The table limits should mismatch");
+    };
+    assert_eq!(mismatches.len(), 1);
+
+    Ok(())
+}
+
+/// As a component's core module, the single memory is exported as `memory`
+/// and `cabi_realloc` survives even though it resolves B's import. A second
+/// module exporting `cabi_realloc` breaks the convention.
+#[test]
+fn component_core() -> Result<(), Error> {
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (memory (export "mem") 1)
+          (global $next (mut i32) (i32.const 16))
+          (func (export "cabi_realloc")
+            (param i32 i32 i32 i32) (result i32)
+            (local $pointer i32)
+            (local.set $pointer (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get 3)))
+            (local.get $pointer)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "cabi_realloc"
+            (func $realloc (param i32 i32 i32 i32) (result i32)))
+          (func (export "allocate") (param i32) (result i32)
+            (call $realloc (i32.const 0) (i32.const 0) (i32.const 1) (local.get 0))))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        component_core: true,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options.clone()).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let exports: Vec<&str> = module.exports().map(|export| export.name()).collect();
+    assert!(exports.contains(&"memory"));
+    assert!(exports.contains(&"cabi_realloc"));
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! {instance, store,
+        allocate [i32] [i32],
+    };
+    assert_eq!(allocate.call(&mut store, 8)?, 16);
+    assert_eq!(allocate.call(&mut store, 8)?, 24);
+    assert!(instance.get_memory(&mut store, "memory").is_some());
+
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (func (export "cabi_realloc")
+            (param i32 i32 i32 i32) (result i32)
+            (i32.const 0)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("C", &wasm_c),
+    ];
+    let merged = MergeConfiguration::new(modules, options).merge();
+    assert!(matches!(
+        merged,
+        Err(wasm_mergers::error::Error::ComponentCoreConvention(_))
+    ));
+
+    Ok(())
+}
+
+/// Merging many tiny modules, as a plugin host would, stays fast: a chain of
+/// 500 modules each adding one to the result of its predecessor.
+#[test]
+fn merge_many_tiny_modules() -> Result<(), Error> {
+    const MODULES: usize = 500;
+    let mut wasm = vec![parse_str(
+        r#"(module (func (export "f") (result i32) (i32.const 0)))"#,
+    )?];
+    for index in 1..MODULES {
+        let previous = index - 1;
+        wasm.push(parse_str(format!(
+            r#"
+            (module
+              (import "m{previous}" "f" (func $f (result i32)))
+              (func (export "f") (result i32) (i32.add (call $f) (i32.const 1))))"#,
+        ))?);
+    }
+    let names: Vec<String> = (0..MODULES).map(|index| format!("m{index}")).collect();
+    let named: Vec<NamedModule<'_, &[u8]>> = names
+        .iter()
+        .zip(&wasm)
+        .map(|(name, wasm)| NamedModule::new(name, wasm.as_slice()))
+        .collect();
+    let modules: Vec<&NamedModule<'_, &[u8]>> = named.iter().collect();
+
+    let started = std::time::Instant::now();
+    let merged = MergeConfiguration::new(&modules, MergeOptions::default()).merge()?;
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! {instance, store,
+        f [] [i32],
+    };
+    assert_eq!(f.call(&mut store, ())?, 499);
+
+    Ok(())
+}
+
+/// Only the functions are namespaced, the globals keep their original name.
+#[test]
+fn rename_functions_only() -> Result<(), Error> {
+    use wasm_mergers::merge_options::{RenameStrategy, namespace_dot_rename};
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) i32.const 1)
+          (global (export "x") i32 (i32.const 10)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) i32.const 2)
+          (global (export "y") i32 (i32.const 20)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let strategy = RenameStrategy::functions_only(namespace_dot_rename);
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::AlwaysPrefix(strategy.clone()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules, options).merge()?;
+
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let export_names = module.exports().map(|export| export.name()).sorted();
+    assert_eq!(
+        export_names.collect::<Vec<_>>(),
+        vec!["A.f", "B.f", "x", "y"]
+    );
+
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let f = instance.get_typed_func::<(), i32>(&mut store, "B.f")?;
+    assert_eq!(f.call(&mut store, ())?, 2);
+    let x = instance.get_global(&mut store, "x").unwrap();
+    assert_eq!(x.get(&mut store).i32(), Some(10));
+
+    // Globals that keep their name must not clash, whether renaming all
+    // exports or the clashing ones only
+    let wasm_b = parse_str(r#"(module (global (export "x") i32 (i32.const 20)))"#)?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    for clashes in [
+        ClashingExports::AlwaysPrefix(strategy.clone()),
+        ClashingExports::Rename(strategy.clone()),
+    ] {
+        let options = MergeOptions {
+            name_policy: NamePolicy {
+                clashes,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = MergeConfiguration::new(modules, options).merge();
+        let Err(wasm_mergers::error::Error::ExportNameClash(clashes)) = result else {
+            panic!("The globals named `x` should clash");
+        };
+        assert_eq!(clashes.keys().collect::<Vec<_>>(), ["x"]);
+    }
+
+    Ok(())
+}
+
+/// An import cycle among globals is reported as such, along with the modules
+/// taking part in it, C which is not part of the cycle is left out.
+#[test]
+fn global_import_cycle() -> Result<(), Error> {
+    use wasm_mergers::kinds::ExportKind;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (import "B" "y" (global $y i32))
+          (export "x" (global $y)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "x" (global $x i32))
+          (export "y" (global $x)))"#,
+    )?;
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (import "A" "x" (global $x i32))
+          (func (export "get") (result i32) (global.get $x)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+    let error = MergeConfiguration::new(modules, MergeOptions::default())
+        .merge()
+        .expect_err("Expect a global import cycle");
+
+    let wasm_mergers::error::Error::ImportCycle { kind, modules } = error else {
+        panic!("expected an import cycle, found {error:?}");
+    };
+    assert_eq!(kind, ExportKind::Global);
+    assert_eq!(modules, ["A", "B"]);
+
+    Ok(())
+}
+
+/// The report groups the final export names by the module contributing them.
+#[test]
+fn export_groups() -> Result<(), Error> {
+    use wasm_mergers::merge_options::NAMESPACE_DOT_RENAMER;
+
+    let wasm_a = parse_str(
+        r#"
+        (module
+          (func (export "f") (result i32) i32.const 1)
+          (global (export "x") i32 (i32.const 10)))"#,
+    )?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (result i32)))
+          (func (export "g") (result i32) (call $f))
+          (memory (export "memory") 1))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let options = MergeOptions {
+        name_policy: NamePolicy {
+            clashes: ClashingExports::AlwaysPrefix(NAMESPACE_DOT_RENAMER),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (merged, report) = MergeConfiguration::new(modules, options).merge_with_report()?;
+
+    let group = |module: &str| {
+        report.export_groups[module]
+            .iter()
+            .sorted()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(group("A"), ["A.x"]);
+    assert_eq!(group("B"), ["B.g", "B.memory"]);
+
+    let module = Module::new(&Engine::default(), &merged)?;
+    let exports: Vec<&str> = module
+        .exports()
+        .map(|export| export.name())
+        .sorted()
+        .collect();
+    assert_eq!(exports, ["A.x", "B.g", "B.memory"]);
+
+    Ok(())
+}
+
+/// Waiting & notifying on an unshared memory validates, yet waiting always
+/// traps. A module that waits is warned about, notifying is harmless.
+#[test]
+fn atomics_on_non_shared_memory() -> Result<(), Error> {
+    use wasm_mergers::MergeWarning;
+
+    let gen_wat = |memory: &str, atomic: &str| {
+        format!(
+            r#"
+            (module
+              (memory {memory})
+              (func (export "atomic") (result i32) ({atomic})))"#
+        )
+    };
+    let wait = "memory.atomic.wait32 (i32.const 0) (i32.const 1) (i64.const 0)";
+    let notify = "memory.atomic.notify (i32.const 0) (i32.const 1)";
+    let wasm_b = parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+
+    for (memory, atomic, warned) in [
+        ("1", wait, true),
+        ("1", notify, false),
+        ("1 1 shared", wait, false),
+    ] {
+        let wasm_a = parse_str(gen_wat(memory, atomic))?;
+        let modules: &[&NamedModule<'_, &[u8]>] = &[
+            &NamedModule::new("A", &wasm_a),
+            &NamedModule::new("B", &wasm_b),
+        ];
+        let (merged, report) =
+            MergeConfiguration::new(modules, MergeOptions::default()).merge_with_report()?;
+        wasmparser::validate(&merged)?;
+        let warning = MergeWarning::AtomicWaitOnUnshared("A".to_string());
+        assert_eq!(report.warnings.contains(&warning), warned);
+    }
+
+    // Notifying an unshared memory has no waiters to wake
+    let wasm_a = parse_str(gen_wat("1", notify))?;
+    let merged =
+        MergeConfiguration::new(&[&NamedModule::new("A", &wasm_a)], MergeOptions::default())
+            .merge()?;
+    let mut store = Store::<()>::default();
+    let module = Module::from_binary(store.engine(), &merged)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! { instance, store, atomic [] [i32] };
+    assert_eq!(wasm_call!(store, atomic), 0);
+
+    Ok(())
+}
+
+/// A module configured as `libold` is merged under its alias `libnew`, such
+/// that imports from `libnew` resolve against it.
+#[test]
+fn rename_modules() -> Result<(), Error> {
+    let wasm_lib = parse_str(r#"(module (func (export "f") (result i32) (i32.const 42)))"#)?;
+    let wasm_main = parse_str(
+        r#"
+        (module
+          (import "libnew" "f" (func $f (result i32)))
+          (func (export "main") (result i32) (call $f)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("libold", &wasm_lib),
+        &NamedModule::new("main", &wasm_main),
+    ];
+    let mut configuration = MergeConfiguration::new(modules, MergeOptions::default());
+    assert_eq!(configuration.module_names()?, ["libold", "main"]);
+    configuration
+        .rename_modules
+        .insert("libold".to_string(), "libnew".to_string());
+    assert_eq!(configuration.module_names()?, ["libnew", "main"]);
+    let merged = configuration.merge()?;
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &merged)?;
+    assert_eq!(module.imports().len(), 0);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    declare_fns_from_wasm! {instance, store, main [] [i32], }
+    assert_eq!(main.call(&mut store, ())?, 42);
+
+    Ok(())
+}
+
+/// Under `ResolvedExports::Keep`, an export that resolves an import remains,
+/// and thus clashes with a genuine export by the same name.
+#[test]
+fn keep_resolved_exports_clash() -> Result<(), Error> {
+    use wasm_mergers::error::Error;
+    use wasm_mergers::merge_options::ResolvedExports;
+
+    let wasm_a = parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "f" (func $f (result i32)))
+          (func (export "g") (result i32) (call $f)))"#,
+    )?;
+    let wasm_c = parse_str(r#"(module (func (export "f") (result i32) (i32.const 3)))"#)?;
+
+    let modules_ab: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+    let keep = || MergeOptions {
+        resolved_exports: ResolvedExports::Keep,
+        ..Default::default()
+    };
+    let merged = MergeConfiguration::new(modules_ab, keep()).merge()?;
+    let module = Module::new(&Engine::default(), &merged)?;
+    let exports: Vec<&str> = module.exports().map(|e| e.name()).sorted().collect();
+    assert_eq!(exports, ["f", "g"]);
+
+    // The resolved export `A::f` clashes with the genuine export `C::f`
+    let modules_abc: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+    let Err(Error::ExportNameClash(clashes)) = MergeConfiguration::new(modules_abc, keep()).merge()
+    else {
+        panic!("The kept resolved export should clash");
+    };
+    assert_eq!(clashes.keys().collect::<Vec<_>>(), ["f"]);
+
+    // Whereas removing resolved exports leaves no clash
+    let merged = MergeConfiguration::new(modules_abc, MergeOptions::default()).merge()?;
+    let module = Module::new(&Engine::default(), &merged)?;
+    let exports: Vec<&str> = module.exports().map(|e| e.name()).sorted().collect();
+    assert_eq!(exports, ["f", "g"]);
+
+    Ok(())
+}
+
+/// A global that is imported & re-exported resolves to its single definition,
+/// leaving no import behind.
+#[test]
+fn reexported_global_resolves() -> Result<(), Error> {
+    let wasm_a = parse_str(r#"(module (global (export "g") i32 (i32.const 42)))"#)?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "g" (global $g i32))
+          (export "h" (global $g)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+    ];
+
+    for modules in iter_permutations(modules) {
+        let (merged, report) =
+            MergeConfiguration::new(&modules, MergeOptions::default()).merge_with_report()?;
+        assert!(report.is_self_contained);
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        assert_eq!(parsed.imports.iter().count(), 0);
+        assert_eq!(parsed.globals.iter().count(), 1);
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let h = instance.get_global(&mut store, "h").expect("h is exported");
+        assert_eq!(h.get(&mut store).i32(), Some(42));
+    }
+
+    Ok(())
+}
+
+/// A global imported by multiple modules resolves to the one definition.
+#[test]
+fn shared_global_definition() -> Result<(), Error> {
+    let wasm_a = parse_str(r#"(module (global (export "counter") (mut i32) (i32.const 0)))"#)?;
+    let wasm_b = parse_str(
+        r#"
+        (module
+          (import "A" "counter" (global $counter (mut i32)))
+          (func (export "increment")
+            (global.set $counter (i32.add (global.get $counter) (i32.const 1)))))"#,
+    )?;
+    let wasm_c = parse_str(
+        r#"
+        (module
+          (import "A" "counter" (global $counter (mut i32)))
+          (func (export "read") (result i32) (global.get $counter)))"#,
+    )?;
+    let modules: &[&NamedModule<'_, &[u8]>] = &[
+        &NamedModule::new("A", &wasm_a),
+        &NamedModule::new("B", &wasm_b),
+        &NamedModule::new("C", &wasm_c),
+    ];
+
+    for modules in iter_permutations(modules) {
+        let (merged, report) =
+            MergeConfiguration::new(&modules, MergeOptions::default()).merge_with_report()?;
+        assert!(report.is_self_contained);
+        let parsed = walrus::Module::from_buffer(&merged)?;
+        assert_eq!(parsed.globals.iter().count(), 1);
+
+        let mut store = Store::<()>::default();
+        let module = Module::from_binary(store.engine(), &merged)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        declare_fns_from_wasm! { instance, store, increment [] [], read [] [i32] };
+        assert_eq!(wasm_call!(store, read), 0);
+        wasm_call!(store, increment);
+        wasm_call!(store, increment);
+        assert_eq!(wasm_call!(store, read), 2);
+    }
+
+    Ok(())
+}
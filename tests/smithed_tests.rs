@@ -1,6 +1,6 @@
 use wasm_mergers::NamedModule;
 use wasm_mergers::merge_options::DEFAULT_RENAMER;
-use wasm_mergers::merge_options::{ClashingExports, MergeOptions};
+use wasm_mergers::merge_options::{ClashingExports, MergeOptions, NamePolicy};
 
 use arbitrary::Unstructured;
 use rand_chacha::rand_core::{Rng, SeedableRng};
@@ -121,7 +121,10 @@ fn test_smithed_modules() {
             let refs = named_modules.iter().collect::<Vec<_>>();
             let modules: &[&NamedModule<'_, &[u8]>] = &refs[..];
             let merge_options = MergeOptions {
-                clashing_exports: ClashingExports::Rename(DEFAULT_RENAMER),
+                name_policy: NamePolicy {
+                    clashes: ClashingExports::Rename(DEFAULT_RENAMER),
+                    ..Default::default()
+                },
                 ..Default::default()
             };
             let mut merge_configuration =